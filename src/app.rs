@@ -1,9 +1,16 @@
 use crate::config::Config;
+use crate::dashboard::Dashboard;
 use crate::deck::{Deck, KeyboardMode, list_decks};
-use crate::keybind::{Chord, Keybind};
+use crate::keybind::{Chord, Keybind, key_event_to_chord};
+use crate::limits::{DailyLimits, DeckQuota, allowances, cap_due_counts, total};
 use crate::matcher::{MatchState, Matcher};
+use crate::notation::Notation;
 use crate::scheduler::{Rating, Scheduler};
-use crate::storage::{DeckStats, DeckSyncInput, Storage, StoredCard};
+use crate::speed::SpeedProfile;
+use crate::storage::{
+    CardGroup, DayCounts, DeckStats, DeckSyncInput, GroupStats, KeyPress, Storage, StoredCard,
+    SyncCard,
+};
 use crate::ui;
 use anyhow::{Context, Result};
 use crossterm::event::{
@@ -18,31 +25,290 @@ use std::io::stdout;
 use std::time::{Duration, Instant};
 
 struct SessionStats {
+    /// Cram session: practice only, scheduling is left untouched
+    cram: bool,
     reviewed: usize,
     correct: usize,
     start_time: Instant,
     end_time: Option<Instant>,
+    /// Due cards left out by the daily limits
+    held_back: usize,
+    /// Ended by the `session_minutes` time box
+    timed_out: bool,
 }
 
+#[derive(Clone)]
 struct StudyCard {
     stored: StoredCard,
-    keybind: Keybind,
+    /// Accepted keybinds; any one of them answers the card
+    keybinds: Vec<Keybind>,
+    keyboard_mode: KeyboardMode,
+    /// How the answer and typed chords are shown
+    notation: Notation,
+    /// Compare command-mode answers as shell words
+    shell_match: bool,
+    /// Command-mode answers have `{name}` and `<name>` placeholders
+    placeholders: bool,
 }
 
 struct DeckSelectionState {
     available_decks: Vec<DeckStats>,
+    /// Sections and tags of each deck (by name), shown when the deck is expanded
+    groups: HashMap<String, Vec<GroupStats>>,
+    /// Decks expanded to show their sections and tags (by name)
+    expanded: HashSet<String>,
+    /// Decks marked for a mixed session (by name)
+    marked_decks: HashSet<String>,
+    /// Sections and tags marked for a session, with their deck name
+    marked_groups: HashSet<(String, CardGroup)>,
+}
+
+/// A row in the deck picker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PickerEntry {
+    /// Every deck's due cards in one mixed session
+    AllDue,
+    /// A single deck (index into `available_decks`)
+    Deck(usize),
+    /// A section or tag of an expanded deck (deck index, index into its groups)
+    Group(usize, usize),
+}
+
+/// A deck to study, restricted to cards in any of `groups` (all cards if empty)
+struct DeckChoice<'a> {
+    deck: &'a DeckStats,
+    groups: Vec<CardGroup>,
+}
+
+impl DeckSelectionState {
+    fn new(available_decks: Vec<DeckStats>, groups: HashMap<String, Vec<GroupStats>>) -> Self {
+        Self {
+            available_decks,
+            groups,
+            expanded: HashSet::new(),
+            marked_decks: HashSet::new(),
+            marked_groups: HashSet::new(),
+        }
+    }
+
+    fn deck_groups(&self, deck: usize) -> &[GroupStats] {
+        self.groups
+            .get(&self.available_decks[deck].name)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Picker rows in display order: "All due" first, then each deck followed by
+    /// its sections and tags if expanded
+    fn entries(&self) -> Vec<PickerEntry> {
+        if self.available_decks.is_empty() {
+            return Vec::new();
+        }
+        let mut entries = vec![PickerEntry::AllDue];
+        for (i, deck) in self.available_decks.iter().enumerate() {
+            entries.push(PickerEntry::Deck(i));
+            if self.expanded.contains(&deck.name) {
+                entries.extend((0..self.deck_groups(i).len()).map(|g| PickerEntry::Group(i, g)));
+            }
+        }
+        entries
+    }
+
+    fn is_marked(&self, entry: PickerEntry) -> bool {
+        match entry {
+            PickerEntry::AllDue => false,
+            PickerEntry::Deck(i) => self.marked_decks.contains(&self.available_decks[i].name),
+            PickerEntry::Group(i, g) => self.marked_groups.contains(&(
+                self.available_decks[i].name.clone(),
+                self.deck_groups(i)[g].group.clone(),
+            )),
+        }
+    }
+
+    fn toggle_mark(&mut self, entry: PickerEntry) {
+        match entry {
+            PickerEntry::AllDue => {}
+            PickerEntry::Deck(i) => {
+                let name = self.available_decks[i].name.clone();
+                if !self.marked_decks.remove(&name) {
+                    self.marked_decks.insert(name);
+                }
+            }
+            PickerEntry::Group(i, g) => {
+                let key = (
+                    self.available_decks[i].name.clone(),
+                    self.deck_groups(i)[g].group.clone(),
+                );
+                if !self.marked_groups.remove(&key) {
+                    self.marked_groups.insert(key);
+                }
+            }
+        }
+    }
+
+    /// Decks to study for the selected row: the marked decks, sections and tags if any
+    /// are marked, otherwise the row under the cursor
+    fn decks_to_study(&self, selected: usize) -> Vec<DeckChoice<'_>> {
+        if !self.marked_decks.is_empty() || !self.marked_groups.is_empty() {
+            return self
+                .available_decks
+                .iter()
+                .filter_map(|deck| {
+                    if self.marked_decks.contains(&deck.name) {
+                        return Some(DeckChoice {
+                            deck,
+                            groups: Vec::new(),
+                        });
+                    }
+                    let groups: Vec<CardGroup> = self
+                        .marked_groups
+                        .iter()
+                        .filter(|(name, _)| *name == deck.name)
+                        .map(|(_, group)| group.clone())
+                        .collect();
+                    (!groups.is_empty()).then_some(DeckChoice { deck, groups })
+                })
+                .collect();
+        }
+        match self.entries().get(selected) {
+            Some(PickerEntry::AllDue) => self
+                .available_decks
+                .iter()
+                .map(|deck| DeckChoice {
+                    deck,
+                    groups: Vec::new(),
+                })
+                .collect(),
+            Some(PickerEntry::Deck(i)) => vec![DeckChoice {
+                deck: &self.available_decks[*i],
+                groups: Vec::new(),
+            }],
+            Some(PickerEntry::Group(i, g)) => vec![DeckChoice {
+                deck: &self.available_decks[*i],
+                groups: vec![self.deck_groups(*i)[*g].group.clone()],
+            }],
+            None => Vec::new(),
+        }
+    }
+
+    /// Picker rows for the UI
+    fn rows(&self) -> Vec<ui::PickerRow> {
+        let any_marked = !self.marked_decks.is_empty() || !self.marked_groups.is_empty();
+        let marked = |entry| any_marked.then(|| self.is_marked(entry));
+        self.entries()
+            .into_iter()
+            .map(|entry| match entry {
+                PickerEntry::AllDue => ui::PickerRow {
+                    label: "All due".to_string(),
+                    due_cards: self.available_decks.iter().map(|d| d.due_cards).sum(),
+                    total_cards: self.available_decks.iter().map(|d| d.total_cards).sum(),
+                    depth: 0,
+                    marked: None,
+                    expanded: None,
+                },
+                PickerEntry::Deck(i) => {
+                    let deck = &self.available_decks[i];
+                    ui::PickerRow {
+                        label: deck.name.clone(),
+                        due_cards: deck.due_cards,
+                        total_cards: deck.total_cards,
+                        depth: 0,
+                        marked: marked(entry),
+                        expanded: (!self.deck_groups(i).is_empty())
+                            .then(|| self.expanded.contains(&deck.name)),
+                    }
+                }
+                PickerEntry::Group(i, g) => {
+                    let stats = &self.deck_groups(i)[g];
+                    let label = match &stats.group {
+                        CardGroup::Section(name) => format!("§ {}", name),
+                        CardGroup::Tag(tag) => format!("#{}", tag),
+                    };
+                    ui::PickerRow {
+                        label,
+                        due_cards: stats.due_cards,
+                        total_cards: stats.total_cards,
+                        depth: 1,
+                        marked: marked(entry),
+                        expanded: None,
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// Most options offered on a reverse card
+const CHOICE_COUNT: usize = 4;
+
+/// Multiple-choice answers for a reverse card
+struct Choices {
+    options: Vec<String>,
+    correct: usize,
+    /// Options picked wrongly so far
+    wrong: HashSet<usize>,
+}
+
+impl Choices {
+    /// The card's description shuffled in with up to `CHOICE_COUNT - 1` other
+    /// descriptions from the same deck
+    fn new(answer: &str, descriptions: &[String]) -> Self {
+        let mut rng = rand::rng();
+        let mut others: Vec<&String> = descriptions
+            .iter()
+            .filter(|d| d.as_str() != answer)
+            .collect();
+        others.sort();
+        others.dedup();
+        others.shuffle(&mut rng);
+
+        let mut options: Vec<String> = others
+            .into_iter()
+            .take(CHOICE_COUNT - 1)
+            .cloned()
+            .chain(std::iter::once(answer.to_string()))
+            .collect();
+        options.shuffle(&mut rng);
+        let correct = options.iter().position(|o| o == answer).unwrap_or(0);
+
+        Self {
+            options,
+            correct,
+            wrong: HashSet::new(),
+        }
+    }
+
+    /// The option picked by a number key (1-based), if there is one
+    fn pick(&self, key: KeyEvent) -> Option<usize> {
+        let KeyCode::Char(c) = key.code else {
+            return None;
+        };
+        let n = c.to_digit(10)? as usize;
+        (1..=self.options.len()).contains(&n).then(|| n - 1)
+    }
 }
 
 struct StudyState {
     cards: Vec<StudyCard>,
+    /// Forward-card descriptions per deck, for reverse card choices
+    deck_descriptions: HashMap<String, Vec<String>>,
+    /// Options for the current card, if it's a reverse card
+    choices: Option<Choices>,
+    /// Whether cards come from more than one deck
+    mixed: bool,
     card_idx: usize,
     matcher: Matcher,
     card_start_time: Instant,
+    /// Keys pressed for the current card, recorded with its review
+    keys: Vec<KeyPress>,
     attempts: u8,
     scored_card_ids: HashSet<i64>,
     requeue_for_practice: bool,
     failed_display_until: Option<Instant>,
     success_display_until: Option<Instant>,
+    /// When the `session_minutes` time box runs out; the session ends after the card
+    /// in hand
+    deadline: Option<Instant>,
     stats: SessionStats,
 }
 
@@ -57,19 +323,88 @@ struct SummaryState {
 
 enum AppState {
     DeckSelection(DeckSelectionState),
-    Studying(StudyState),
+    Studying(Box<StudyState>),
     Paused(PausedState),
     Summary(SummaryState),
+    Stats(Box<Dashboard>),
 }
 
 impl Default for AppState {
     fn default() -> Self {
-        AppState::DeckSelection(DeckSelectionState {
-            available_decks: Vec::new(),
-        })
+        AppState::DeckSelection(DeckSelectionState::new(Vec::new(), HashMap::new()))
     }
 }
 
+/// Settings read from deck file directives, keyed by deck name
+#[derive(Default)]
+pub struct DeckSettings {
+    pub keyboard_modes: HashMap<String, KeyboardMode>,
+    /// Decks with a `# display:` notation
+    pub display_notations: HashMap<String, Notation>,
+    /// Decks with `# match: shell`
+    pub shell_match: HashSet<String>,
+    /// Decks with `# placeholders: on`
+    pub placeholders: HashSet<String>,
+    /// Each deck's own caps on new cards and reviews per day
+    pub daily_limits: HashMap<String, DailyLimits>,
+}
+
+/// Back up the database and sync all deck files into it.
+/// Returns each deck's settings, such as its keyboard mode.
+pub fn sync_deck_files(config: &Config, storage: &mut Storage) -> Result<DeckSettings> {
+    Storage::create_daily_backup(&config.db_path)?;
+
+    let deck_files = list_decks(&config.decks_dir)?;
+    let mut active_decks = HashSet::new();
+    let mut settings = DeckSettings::default();
+    let mut sync_inputs = Vec::new();
+
+    for path in deck_files {
+        let deck = Deck::load(&path)?;
+        active_decks.insert(deck.name.clone());
+        settings
+            .keyboard_modes
+            .insert(deck.name.clone(), deck.keyboard_mode);
+        if let Some(notation) = deck.display_notation {
+            settings
+                .display_notations
+                .insert(deck.name.clone(), notation);
+        }
+        if deck.shell_match {
+            settings.shell_match.insert(deck.name.clone());
+        }
+        if deck.placeholders {
+            settings.placeholders.insert(deck.name.clone());
+        }
+        settings
+            .daily_limits
+            .insert(deck.name.clone(), deck.daily_limits);
+
+        let cards = deck
+            .cards
+            .iter()
+            .map(|card| SyncCard {
+                keybind: deck.keyboard_mode.format_keybinds(&card.keybinds),
+                description: card.description.clone(),
+                card_key: card.id.clone(),
+                reset: card.reset,
+                section: card.section.clone(),
+                tags: card.tags.clone(),
+                reverse: card.reverse,
+            })
+            .collect();
+
+        sync_inputs.push(DeckSyncInput {
+            deck_name: deck.name,
+            cards,
+        });
+    }
+
+    storage.sync_decks(sync_inputs, &active_decks)?;
+
+    Ok(settings)
+}
+
 pub struct App {
     config: Config,
     storage: Storage,
@@ -78,7 +413,9 @@ pub struct App {
     quit_chord: Option<Chord>,
     should_exit: bool,
     current_keyboard_mode: Option<KeyboardMode>,
-    keyboard_modes: HashMap<String, KeyboardMode>,
+    deck_settings: DeckSettings,
+    /// Expected response times fitted to the review history, refitted each session
+    speed: SpeedProfile,
     selected_deck_idx: usize,
     show_hints: bool,
     state: AppState,
@@ -87,11 +424,15 @@ pub struct App {
 impl App {
     pub fn new(config: Config) -> Result<Self> {
         config.ensure_dirs()?;
-        let storage = Storage::open(&config.db_path)?;
+        let day = config.study_day();
+        let storage = Storage::open(&config.db_path)?.with_study_day(day);
+        let parameters = storage.get_fsrs_parameters()?;
         let scheduler = Scheduler::new(
+            parameters.as_deref(),
             config.desired_retention,
             config.interval_modifier,
             config.max_interval_days,
+            day,
         )?;
 
         let pause_chord = Some(Chord::parse(&config.pause_keybind).with_context(|| {
@@ -116,18 +457,23 @@ impl App {
             quit_chord,
             should_exit: false,
             current_keyboard_mode: None,
-            keyboard_modes: HashMap::new(),
+            deck_settings: DeckSettings::default(),
+            speed: SpeedProfile::default(),
             selected_deck_idx: 0,
             show_hints,
-            state: AppState::DeckSelection(DeckSelectionState {
-                available_decks: Vec::new(),
-            }),
+            state: AppState::default(),
         })
     }
 
-    pub fn run(mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+    /// Run the app until the user quits.
+    /// With `study_deck`, skips deck selection and starts studying that deck straight away.
+    pub fn run(mut self, terminal: &mut DefaultTerminal, study_deck: Option<&str>) -> Result<()> {
         self.sync_deck_info()?;
 
+        if let Some(name) = study_deck {
+            self.study_deck_by_name(name)?;
+        }
+
         while !self.should_exit {
             terminal.draw(|frame| self.render(frame))?;
             self.handle_events()?;
@@ -137,49 +483,50 @@ impl App {
     }
 
     fn sync_deck_info(&mut self) -> Result<()> {
-        Storage::create_daily_backup(&self.config.db_path)?;
-
-        let deck_files = list_decks(&self.config.decks_dir)?;
-        let mut active_decks = HashSet::new();
-        let mut sync_inputs = Vec::new();
-
-        for path in deck_files {
-            let deck = Deck::load(&path)?;
-            active_decks.insert(deck.name.clone());
-            self.keyboard_modes
-                .insert(deck.name.clone(), deck.keyboard_mode);
-
-            let keybinds = deck
-                .cards
-                .iter()
-                .map(|card| {
-                    let keybind_str = if deck.keyboard_mode == KeyboardMode::Command {
-                        card.keybind.as_command_string()
-                    } else {
-                        card.keybind.to_string()
-                    };
-                    (keybind_str, card.description.clone())
-                })
-                .collect();
-
-            sync_inputs.push(DeckSyncInput {
-                deck_name: deck.name,
-                keybinds,
-            });
-        }
-
-        self.storage.sync_decks(sync_inputs, &active_decks)?;
+        self.deck_settings = sync_deck_files(&self.config, &mut self.storage)?;
         self.refresh_deck_stats()?;
 
         Ok(())
     }
 
+    fn study_deck_by_name(&mut self, name: &str) -> Result<()> {
+        let AppState::DeckSelection(ds) = std::mem::take(&mut self.state) else {
+            return Ok(());
+        };
+        let Some(idx) = ds
+            .entries()
+            .iter()
+            .position(|e| matches!(e, PickerEntry::Deck(i) if ds.available_decks[*i].name == name))
+        else {
+            anyhow::bail!("Unknown deck '{}'", name);
+        };
+        self.selected_deck_idx = idx;
+        self.start_studying(ds, false)
+    }
+
     fn refresh_deck_stats(&mut self) -> Result<()> {
-        let available_decks = self.storage.get_deck_stats(&self.keyboard_modes)?;
+        let mut available_decks = self
+            .storage
+            .get_deck_stats(&self.deck_settings.keyboard_modes)?;
+        cap_due_counts(
+            &mut available_decks,
+            &self.deck_settings.daily_limits,
+            self.config.daily_limits(),
+            &self.storage.get_today_counts()?,
+        );
+        let mut groups = HashMap::new();
+        for deck in &available_decks {
+            let mut stats = self.storage.get_group_stats(&deck.name)?;
+            for group in &mut stats {
+                group.due_cards = group.due_cards.min(deck.due_cards);
+            }
+            groups.insert(deck.name.clone(), stats);
+        }
+        let deck_selection = DeckSelectionState::new(available_decks, groups);
         self.selected_deck_idx = self
             .selected_deck_idx
-            .min(available_decks.len().saturating_sub(1));
-        self.state = AppState::DeckSelection(DeckSelectionState { available_decks });
+            .min(deck_selection.entries().len().saturating_sub(1));
+        self.state = AppState::DeckSelection(deck_selection);
         Ok(())
     }
 
@@ -215,7 +562,7 @@ impl App {
             AppState::DeckSelection(s) => {
                 ui::render_deck_selection(
                     frame,
-                    &s.available_decks,
+                    &s.rows(),
                     self.selected_deck_idx,
                     self.show_hints,
                 );
@@ -223,34 +570,73 @@ impl App {
             AppState::Studying(s) => {
                 if let Some(card) = s.cards.get(s.card_idx) {
                     let match_state = s.matcher.state();
-
-                    let message = if s.attempts >= self.config.max_attempts {
-                        Some("Type the answer to continue")
-                    } else {
-                        None
+                    let revealed = s.attempts >= self.config.max_attempts;
+
+                    let mistake_hint = s.matcher.mistake().and_then(|m| m.describe(card.notation));
+                    let message = match (&s.choices, revealed) {
+                        _ if s.matcher.hint().is_some() => s.matcher.hint(),
+                        _ if mistake_hint.is_some() => mistake_hint.as_deref(),
+                        (_, false) => None,
+                        (Some(_), true) => Some("Choose the answer to continue"),
+                        (None, true) => Some("Type the answer to continue"),
                     };
 
-                    let answer_str = if self.current_keyboard_mode == Some(KeyboardMode::Command) {
-                        card.keybind.as_command_string()
+                    let is_command_mode = card.keyboard_mode == KeyboardMode::Command;
+                    let answers: Vec<String> = if is_command_mode {
+                        card.keybinds
+                            .iter()
+                            .map(|k| k.as_command_string())
+                            .collect()
                     } else {
-                        card.keybind.to_string()
+                        card.keybinds
+                            .iter()
+                            .map(|k| card.notation.format_keybind(k))
+                            .collect()
                     };
+                    let notation = self.config.display_notation;
                     let pause_str = self
                         .pause_chord
                         .as_ref()
-                        .map(|c| c.to_string())
+                        .map(|c| notation.format_chord(c))
                         .unwrap_or_default();
                     let quit_str = self
                         .quit_chord
                         .as_ref()
-                        .map(|c| c.to_string())
+                        .map(|c| notation.format_chord(c))
                         .unwrap_or_default();
+                    // Reverse cards show the keybind and offer descriptions to choose from
+                    let reverse_clue = answers.join(" or ");
+                    let choices: Vec<ui::Choice> = s
+                        .choices
+                        .iter()
+                        .flat_map(|c| {
+                            c.options.iter().enumerate().map(|(i, option)| ui::Choice {
+                                text: option,
+                                state: if c.wrong.contains(&i) {
+                                    ui::ChoiceState::Wrong
+                                } else if i == c.correct
+                                    && (revealed || s.success_display_until.is_some())
+                                {
+                                    ui::ChoiceState::Answer
+                                } else {
+                                    ui::ChoiceState::Open
+                                },
+                            })
+                        })
+                        .collect();
                     let ui_state = ui::UiState {
                         deck: &card.stored.deck,
-                        clue: &card.stored.description,
+                        keyboard_mode: s.mixed.then(|| card.keyboard_mode.label()),
+                        cram: s.stats.cram,
+                        clue: if s.choices.is_some() {
+                            &reverse_clue
+                        } else {
+                            &card.stored.description
+                        },
                         match_state: &match_state,
-                        showing_answer: s.attempts >= self.config.max_attempts,
-                        answer: &answer_str,
+                        choices: &choices,
+                        showing_answer: revealed && s.choices.is_none(),
+                        answers: &answers,
                         message,
                         show_success_checkmark: s.success_display_until.is_some(),
                         card_cleared: !s.requeue_for_practice,
@@ -258,7 +644,9 @@ impl App {
                         pause_keybind: &pause_str,
                         quit_keybind: &quit_str,
                         cards_remaining: s.cards.len() - s.card_idx,
-                        is_command_mode: self.current_keyboard_mode == Some(KeyboardMode::Command),
+                        is_command_mode,
+                        placeholders: card.placeholders,
+                        notation: card.notation,
                     };
                     ui::render(frame, &ui_state);
                 }
@@ -267,7 +655,7 @@ impl App {
                 let keybind_str = self
                     .pause_chord
                     .as_ref()
-                    .map(|c| c.to_string())
+                    .map(|c| self.config.display_notation.format_chord(c))
                     .unwrap_or_else(|| "pause keybind".to_string());
                 ui::render_paused(frame, &keybind_str);
             }
@@ -277,14 +665,32 @@ impl App {
                     .end_time
                     .map(|end| end.duration_since(s.stats.start_time))
                     .unwrap_or_else(|| s.stats.start_time.elapsed());
+                let mut notes = Vec::new();
+                if s.stats.timed_out {
+                    notes.push(format!(
+                        "Time's up: {} minute session",
+                        self.config.session_minutes
+                    ));
+                }
+                if s.stats.held_back > 0 {
+                    notes.push(format!(
+                        "{} due cards held back by the daily limits",
+                        s.stats.held_back
+                    ));
+                }
                 ui::render_summary(
                     frame,
+                    s.stats.cram,
                     s.stats.reviewed,
                     s.stats.correct,
                     elapsed.as_secs(),
+                    &notes,
                     self.show_hints,
                 );
             }
+            AppState::Stats(dashboard) => {
+                ui::render_stats(frame, dashboard, self.show_hints);
+            }
         }
     }
 
@@ -340,7 +746,7 @@ impl App {
                         return Ok(());
                     } else if !matches!(
                         self.state,
-                        AppState::DeckSelection(_) | AppState::Summary(_)
+                        AppState::DeckSelection(_) | AppState::Summary(_) | AppState::Stats(_)
                     ) {
                         self.pause();
                         return Ok(());
@@ -351,7 +757,7 @@ impl App {
                     AppState::DeckSelection(_) => self.handle_deck_selection_key(key)?,
                     AppState::Studying(_) => self.handle_studying_key(key)?,
                     AppState::Paused(_) => {}
-                    AppState::Summary(_) => self.handle_summary_key(key)?,
+                    AppState::Summary(_) | AppState::Stats(_) => self.handle_summary_key(key)?,
                 }
             }
         } else if matches!(self.state, AppState::Studying(_)) {
@@ -366,21 +772,51 @@ impl App {
             return Ok(());
         };
         match key.code {
-            KeyCode::Up | KeyCode::Char('k') => {
-                if self.selected_deck_idx > 0 {
-                    self.selected_deck_idx -= 1;
+            KeyCode::Up | KeyCode::Char('k') if self.selected_deck_idx > 0 => {
+                self.selected_deck_idx -= 1;
+            }
+            KeyCode::Down | KeyCode::Char('j')
+                if self.selected_deck_idx + 1 < s.entries().len() =>
+            {
+                self.selected_deck_idx += 1;
+            }
+            KeyCode::Char(' ') => {
+                if let Some(entry) = s.entries().get(self.selected_deck_idx) {
+                    s.toggle_mark(*entry);
+                }
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                if let Some(PickerEntry::Deck(i)) = s.entries().get(self.selected_deck_idx) {
+                    let name = s.available_decks[*i].name.clone();
+                    s.expanded.insert(name);
                 }
             }
-            KeyCode::Down | KeyCode::Char('j') => {
-                if self.selected_deck_idx + 1 < s.available_decks.len() {
-                    self.selected_deck_idx += 1;
+            KeyCode::Left | KeyCode::Char('h') => {
+                let entries = s.entries();
+                if let Some(PickerEntry::Deck(i) | PickerEntry::Group(i, _)) =
+                    entries.get(self.selected_deck_idx)
+                {
+                    s.expanded.remove(&s.available_decks[*i].name);
+                    // Keep the cursor on the collapsed deck
+                    if let Some(row) = s.entries().iter().position(|e| *e == PickerEntry::Deck(*i))
+                    {
+                        self.selected_deck_idx = row;
+                    }
                 }
             }
             KeyCode::Enter => {
                 if let AppState::DeckSelection(ds) = std::mem::take(&mut self.state) {
-                    self.start_studying(ds)?;
+                    self.start_studying(ds, false)?;
+                };
+            }
+            KeyCode::Char('c') => {
+                if let AppState::DeckSelection(ds) = std::mem::take(&mut self.state) {
+                    self.start_studying(ds, true)?;
                 };
             }
+            KeyCode::Char('s') => {
+                self.state = AppState::Stats(Box::new(Dashboard::load(&self.storage)?));
+            }
             KeyCode::Esc | KeyCode::Char('q') => {
                 self.should_exit = true;
             }
@@ -406,181 +842,338 @@ impl App {
 
         if key.code == KeyCode::Esc && study.attempts < self.config.max_attempts {
             study.attempts = self.config.max_attempts;
-            study.matcher = Matcher::new(
-                study.cards[study.card_idx].keybind.clone(),
-                self.current_keyboard_mode.unwrap_or_default(),
-            );
+            let card = &study.cards[study.card_idx];
+            study.matcher = new_matcher(card, &self.config);
             return Ok(());
         }
 
-        let result = study.matcher.process(key);
-
-        match result {
-            MatchState::Complete(_) => {
-                let AppState::Studying(ref mut study) = self.state else {
-                    unreachable!()
-                };
+        if let Some(choices) = &mut study.choices {
+            let Some(pick) = choices.pick(key) else {
+                return Ok(());
+            };
+            if pick == choices.correct {
+                self.complete_card(1)?;
+            } else if choices.wrong.insert(pick) {
                 study.attempts = study.attempts.saturating_add(1);
-                let response_time_ms = study.card_start_time.elapsed().as_millis() as u64;
-                let card = &study.cards[study.card_idx];
-                let card_id = card.stored.id;
-                let num_chords = card.keybind.len();
-                let easy_ms = Rating::scale_threshold(self.config.easy_threshold_ms, num_chords);
-                let hard_ms = Rating::scale_threshold(self.config.hard_threshold_ms, num_chords);
-                let rating = Rating::from_speed(
-                    response_time_ms,
-                    study.attempts,
-                    easy_ms,
-                    hard_ms,
-                    self.config.max_attempts,
-                );
+            }
+            return Ok(());
+        }
 
-                if study.scored_card_ids.insert(card_id) {
-                    let memory_state = card.stored.stability.and_then(|s| {
-                        card.stored
-                            .difficulty
-                            .map(|d| Scheduler::memory_state_from_stored(s, d))
-                    });
-                    let last_review = card.stored.last_review;
-                    let (new_memory, due_date) =
-                        self.scheduler.schedule(memory_state, last_review, rating)?;
-                    self.storage.update_card_after_review(
-                        card_id,
-                        new_memory.stability,
-                        new_memory.difficulty,
-                        due_date,
-                    )?;
-                    self.storage.record_review(
-                        card_id,
-                        rating.as_u32() as i32,
-                        response_time_ms as i64,
-                        study.attempts as i32,
-                    )?;
-                    study.stats.reviewed += 1;
-                }
+        let result = study.matcher.process(key);
+        let correct = matches!(result, MatchState::InProgress(_) | MatchState::Complete(_));
+        // Editing keys aren't part of the answer, so only chords that went into it are logged
+        if let Some(position) = study.matcher.position() {
+            study.keys.push(KeyPress {
+                position,
+                chord: key_event_to_chord(&key).to_string(),
+                expected: (!correct)
+                    .then(|| study.matcher.expected_chord().map(Chord::to_string))
+                    .flatten(),
+                elapsed_ms: study.card_start_time.elapsed().as_millis() as u64,
+                correct,
+            });
+        }
+        // Cram is practice, so its mistakes aren't counted against the card
+        if let Some(mistake) = study.matcher.mistake().filter(|_| !study.stats.cram) {
+            let card_id = study.cards[study.card_idx].stored.id;
+            self.storage.record_mistake(card_id, mistake.kind.name())?;
+        }
 
-                if rating != Rating::Easy {
-                    study.requeue_for_practice = true;
+        match result {
+            // Thresholds scale with the alternative actually typed
+            MatchState::Complete(typed) => {
+                // A corrected mistake while line editing counts as a second attempt
+                if study.matcher.mistakes() > 0 {
+                    study.attempts = study.attempts.max(1);
                 }
-                study.success_display_until =
-                    Some(Instant::now() + Duration::from_millis(self.config.success_delay_ms));
+                self.complete_card(typed.len())?
             }
             MatchState::Failed(_) => {
                 study.attempts = study.attempts.saturating_add(1);
                 study.failed_display_until =
                     Some(Instant::now() + Duration::from_millis(self.config.failed_flash_delay_ms));
             }
-            MatchState::InProgress(_) => {}
+            MatchState::InProgress(_) | MatchState::Mistyped(_) => {}
         }
 
         Ok(())
     }
 
+    /// Rate and schedule the current card once it has been answered correctly
+    fn complete_card(&mut self, num_chords: usize) -> Result<()> {
+        let AppState::Studying(ref mut study) = self.state else {
+            return Ok(());
+        };
+        study.attempts = study.attempts.saturating_add(1);
+        let response_time_ms = study.card_start_time.elapsed().as_millis() as u64;
+        let card = &study.cards[study.card_idx];
+        let card_id = card.stored.id;
+        // Picking a reverse card's choice isn't typing, so it keeps the fixed thresholds
+        let adaptive = match study.choices {
+            Some(_) => None,
+            None => self.speed.thresholds(card.keyboard_mode, num_chords),
+        };
+        let (easy_ms, hard_ms) = adaptive.unwrap_or_else(|| {
+            (
+                Rating::scale_threshold(self.config.easy_threshold_ms, num_chords),
+                Rating::scale_threshold(self.config.hard_threshold_ms, num_chords),
+            )
+        });
+        let rating = Rating::from_speed(
+            response_time_ms,
+            study.attempts,
+            easy_ms,
+            hard_ms,
+            self.config.max_attempts,
+        );
+
+        if study.scored_card_ids.insert(card_id) {
+            // Cram sessions only log practice reviews; FSRS state is left alone
+            if !study.stats.cram {
+                let memory_state = card.stored.stability.and_then(|s| {
+                    card.stored
+                        .difficulty
+                        .map(|d| Scheduler::memory_state_from_stored(s, d))
+                });
+                let last_review = card.stored.last_review;
+                let (new_memory, due_date) =
+                    self.scheduler.schedule(memory_state, last_review, rating)?;
+                self.storage.update_card_after_review(
+                    card_id,
+                    new_memory.stability,
+                    new_memory.difficulty,
+                    due_date,
+                )?;
+            }
+            let review_id = self.storage.record_review(
+                card_id,
+                rating.as_u32() as i32,
+                response_time_ms as i64,
+                study.attempts as i32,
+                num_chords,
+                study.stats.cram,
+            )?;
+            self.storage.record_review_keys(review_id, &study.keys)?;
+            study.stats.reviewed += 1;
+        }
+
+        if rating != Rating::Easy {
+            study.requeue_for_practice = true;
+        }
+        study.success_display_until =
+            Some(Instant::now() + Duration::from_millis(self.config.success_delay_ms));
+
+        Ok(())
+    }
+
     fn handle_summary_key(&mut self, _key: KeyEvent) -> Result<()> {
         self.refresh_deck_stats()?;
         Ok(())
     }
 
-    fn start_studying(&mut self, deck_selection: DeckSelectionState) -> Result<()> {
-        let mut cards = Vec::new();
-        let stats = SessionStats {
+    /// Start a session on the decks chosen in the picker.
+    /// A cram session loads the least stable cards whether due or not, and doesn't
+    /// touch scheduling.
+    fn start_studying(&mut self, deck_selection: DeckSelectionState, cram: bool) -> Result<()> {
+        let mut stats = SessionStats {
+            cram,
             reviewed: 0,
             correct: 0,
             start_time: Instant::now(),
             end_time: None,
+            held_back: 0,
+            timed_out: false,
         };
 
-        let deck = &deck_selection.available_decks[self.selected_deck_idx];
-        let keyboard_mode = deck.keyboard_mode;
-        let name = deck.name.clone();
-        self.load_due_cards(&name, keyboard_mode, &mut cards)?;
-
-        if cards.is_empty() {
-            self.state = AppState::Summary(SummaryState {
-                stats: SessionStats {
-                    reviewed: 0,
-                    correct: 0,
-                    start_time: Instant::now(),
-                    end_time: Some(Instant::now()),
-                },
-            });
-        } else {
-            self.push_keyboard_mode(keyboard_mode);
-
+        let decks: Vec<(String, KeyboardMode, Vec<CardGroup>)> = deck_selection
+            .decks_to_study(self.selected_deck_idx)
+            .into_iter()
+            .map(|c| (c.deck.name.clone(), c.deck.keyboard_mode, c.groups))
+            .collect();
+
+        let mut per_deck = Vec::new();
+        let mut deck_descriptions = HashMap::new();
+        for (name, keyboard_mode, groups) in &decks {
+            deck_descriptions.insert(name.clone(), self.storage.get_descriptions(name)?);
+            let mut stored_cards = if cram {
+                self.storage.get_cram_cards(name)?
+            } else {
+                self.storage.get_due_cards(name, self.config.session_due)?
+            };
+            if !groups.is_empty() {
+                stored_cards.retain(|card| groups.iter().any(|g| g.contains(card)));
+            }
+            let notation = self
+                .deck_settings
+                .display_notations
+                .get(name)
+                .copied()
+                .unwrap_or(self.config.display_notation);
+            let shell_match = self.deck_settings.shell_match.contains(name);
+            let placeholders = self.deck_settings.placeholders.contains(name);
+            let cards = to_study_cards(
+                stored_cards,
+                *keyboard_mode,
+                notation,
+                shell_match,
+                placeholders,
+            );
+            if !cards.is_empty() {
+                per_deck.push(cards);
+            }
+        }
+        // Cram sessions have their own limit
+        if !cram {
+            stats.held_back = self.apply_daily_limits(&mut per_deck)?;
+            per_deck.retain(|cards| !cards.is_empty());
+        }
+        let mixed = per_deck.len() > 1;
+
+        let cards = if cram {
+            let mut cards: Vec<StudyCard> = per_deck.into_iter().flatten().collect();
+            // Least stable first, with never-reviewed cards ahead of everything
+            let stability = |c: &StudyCard| c.stored.stability.unwrap_or(f32::NEG_INFINITY);
+            cards.sort_by(|a, b| stability(a).total_cmp(&stability(b)));
+            if self.config.cram_limit > 0 {
+                cards.truncate(self.config.cram_limit);
+            }
             if self.config.shuffle_cards {
                 cards.shuffle(&mut rand::rng());
             }
+            cards
+        } else if self.config.shuffle_cards {
+            let mut cards: Vec<StudyCard> = per_deck.into_iter().flatten().collect();
+            cards.shuffle(&mut rand::rng());
+            cards
+        } else {
+            interleave(per_deck)
+        };
 
-            let matcher = Matcher::new(cards[0].keybind.clone(), keyboard_mode);
+        if cards.is_empty() {
+            stats.end_time = Some(stats.start_time);
+            self.state = AppState::Summary(SummaryState { stats });
+        } else {
+            let keyboard_mode = cards[0].keyboard_mode;
+            self.push_keyboard_mode(keyboard_mode);
+            let matcher = new_matcher(&cards[0], &self.config);
+            let choices = choices_for(&cards[0], &deck_descriptions);
+            self.speed = if self.config.adaptive_rating {
+                SpeedProfile::load(&self.storage, &self.deck_settings.keyboard_modes)?
+            } else {
+                SpeedProfile::default()
+            };
 
-            self.state = AppState::Studying(StudyState {
+            self.state = AppState::Studying(Box::new(StudyState {
                 cards,
+                deck_descriptions,
+                choices,
+                mixed,
                 card_idx: 0,
                 matcher,
                 card_start_time: Instant::now(),
+                keys: Vec::new(),
                 attempts: 0,
                 scored_card_ids: HashSet::new(),
                 requeue_for_practice: false,
                 failed_display_until: None,
                 success_display_until: None,
+                deadline: (self.config.session_minutes > 0).then(|| {
+                    Instant::now() + Duration::from_secs(self.config.session_minutes * 60)
+                }),
                 stats,
-            });
+            }));
         }
 
         Ok(())
     }
 
-    fn load_due_cards(
-        &mut self,
-        deck_name: &str,
-        mode: KeyboardMode,
-        cards: &mut Vec<StudyCard>,
-    ) -> Result<()> {
-        let stored_cards = self.storage.get_due_cards(deck_name)?;
-
-        for stored in stored_cards {
-            let keybind = if mode == KeyboardMode::Command {
-                Keybind::parse_command(&stored.keybind)
-            } else {
-                Keybind::parse(&stored.keybind)
-            };
-            if let Ok(keybind) = keybind {
-                cards.push(StudyCard { stored, keybind });
-            }
+    /// Trim each deck's due cards to what today's limits leave, keeping the order
+    /// they came in. Returns how many cards were held back.
+    fn apply_daily_limits(&self, per_deck: &mut [Vec<StudyCard>]) -> Result<usize> {
+        let today = self.storage.get_today_counts()?;
+        let global_done = total(today.values());
+        let quotas: Vec<DeckQuota> = per_deck
+            .iter()
+            .filter_map(|cards| {
+                let deck = &cards.first()?.stored.deck;
+                let new_cards = cards
+                    .iter()
+                    .filter(|c| c.stored.last_review.is_none())
+                    .count();
+                Some(DeckQuota {
+                    limits: self
+                        .deck_settings
+                        .daily_limits
+                        .get(deck)
+                        .copied()
+                        .unwrap_or_default(),
+                    done: today.get(deck).copied().unwrap_or_default(),
+                    due: DayCounts {
+                        new_cards,
+                        reviews: cards.len() - new_cards,
+                    },
+                })
+            })
+            .collect();
+
+        let mut held_back = 0;
+        for (cards, mut allowed) in per_deck
+            .iter_mut()
+            .filter(|cards| !cards.is_empty())
+            .zip(allowances(&quotas, self.config.daily_limits(), global_done))
+        {
+            let before = cards.len();
+            cards.retain(|card| {
+                let left = if card.stored.last_review.is_none() {
+                    &mut allowed.new_cards
+                } else {
+                    &mut allowed.reviews
+                };
+                let keep = *left > 0;
+                *left = left.saturating_sub(1);
+                keep
+            });
+            held_back += before - cards.len();
         }
-
-        Ok(())
+        Ok(held_back)
     }
 
-    fn setup_current_card(study: &mut StudyState, mode: KeyboardMode) {
+    /// Set up the matcher for the current card, switching keyboard mode if the
+    /// card comes from a deck with a different mode
+    fn setup_current_card(&mut self, study: &mut StudyState) {
         if let Some(card) = study.cards.get(study.card_idx) {
-            study.matcher = Matcher::new(card.keybind.clone(), mode);
+            if self.current_keyboard_mode != Some(card.keyboard_mode) {
+                self.push_keyboard_mode(card.keyboard_mode);
+            }
+            study.matcher = new_matcher(card, &self.config);
+            study.choices = choices_for(card, &study.deck_descriptions);
             study.card_start_time = Instant::now();
+            study.keys.clear();
             study.attempts = 0;
             study.requeue_for_practice = false;
         }
     }
 
-    fn next_card(&mut self, mut study: StudyState) -> Result<()> {
+    fn next_card(&mut self, mut study: Box<StudyState>) -> Result<()> {
         if study.requeue_for_practice {
             if let Some(card) = study.cards.get(study.card_idx) {
-                study.cards.push(StudyCard {
-                    stored: card.stored.clone(),
-                    keybind: card.keybind.clone(),
-                });
+                study.cards.push(card.clone());
             }
         } else {
             study.stats.correct += 1;
         }
 
         study.card_idx += 1;
+        study.stats.timed_out = study
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline);
 
-        if study.card_idx >= study.cards.len() {
+        if study.card_idx >= study.cards.len() || study.stats.timed_out {
             study.stats.end_time = Some(Instant::now());
             self.pop_keyboard_mode();
             self.state = AppState::Summary(SummaryState { stats: study.stats });
         } else {
-            Self::setup_current_card(&mut study, self.current_keyboard_mode.unwrap_or_default());
+            self.setup_current_card(&mut study);
             self.state = AppState::Studying(study);
         }
 
@@ -603,6 +1196,9 @@ impl App {
         let delta = paused.started_at.elapsed();
         if let AppState::Studying(ref mut s) = prev {
             s.card_start_time += delta;
+            if let Some(deadline) = &mut s.deadline {
+                *deadline += delta;
+            }
         }
         self.state = prev;
     }
@@ -616,10 +1212,72 @@ impl App {
 
         if elapsed >= timeout && study.attempts < self.config.max_attempts {
             study.attempts = self.config.max_attempts;
-            study.matcher = Matcher::new(
-                study.cards[study.card_idx].keybind.clone(),
-                self.current_keyboard_mode.unwrap_or_default(),
-            );
+            let card = &study.cards[study.card_idx];
+            study.matcher = new_matcher(card, &self.config);
+        }
+    }
+}
+
+/// A matcher for the card, with command-mode line editing, shell matching and
+/// placeholders if turned on
+fn new_matcher(card: &StudyCard, config: &Config) -> Matcher {
+    Matcher::new(card.keybinds.clone(), card.keyboard_mode)
+        .with_line_editing(config.command_line_editing)
+        .with_shell_matching(card.shell_match)
+        .with_placeholders(card.placeholders)
+}
+
+/// Parse stored cards into study cards, skipping any whose keybind no longer parses.
+/// Stored keybinds are always in the standard notation.
+fn to_study_cards(
+    stored_cards: Vec<StoredCard>,
+    mode: KeyboardMode,
+    notation: Notation,
+    shell_match: bool,
+    placeholders: bool,
+) -> Vec<StudyCard> {
+    stored_cards
+        .into_iter()
+        .filter_map(|stored| {
+            mode.parse_keybinds(&stored.keybind, Some(Notation::Standard))
+                .ok()
+                .map(|keybinds| StudyCard {
+                    stored,
+                    keybinds,
+                    keyboard_mode: mode,
+                    notation,
+                    shell_match,
+                    placeholders,
+                })
+        })
+        .collect()
+}
+
+/// Multiple-choice options if the card is a reverse card
+fn choices_for(
+    card: &StudyCard,
+    deck_descriptions: &HashMap<String, Vec<String>>,
+) -> Option<Choices> {
+    card.stored.reverse.then(|| {
+        let descriptions = deck_descriptions
+            .get(&card.stored.deck)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        Choices::new(&card.stored.description, descriptions)
+    })
+}
+
+/// Interleave cards round-robin across decks, so a mixed session alternates between them
+fn interleave(per_deck: Vec<Vec<StudyCard>>) -> Vec<StudyCard> {
+    let total = per_deck.iter().map(Vec::len).sum();
+    let mut iters: Vec<_> = per_deck.into_iter().map(Vec::into_iter).collect();
+    let mut cards = Vec::with_capacity(total);
+    while cards.len() < total {
+        for iter in &mut iters {
+            if let Some(card) = iter.next() {
+                cards.push(card);
+            }
         }
     }
+    cards
 }
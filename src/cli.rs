@@ -0,0 +1,395 @@
+use crate::app::{DeckSettings, sync_deck_files};
+use crate::config::Config;
+use crate::import::{ImportOptions, ImportSource};
+use crate::limits::cap_due_counts;
+use crate::status::StatusFormat;
+use crate::storage::{DeckStats, Storage};
+use anyhow::{Result, bail};
+use chrono::{Local, Utc};
+
+pub const USAGE: &str = "\
+Usage: kbsr [COMMAND]
+
+Commands:
+  (none)          Open the deck picker
+  study <deck>    Start studying a deck straight away
+  list            List decks with due and total card counts
+  due [--json]    Show cards due today per deck
+  stats <deck>    Show review statistics for a deck
+  status [--format waybar|text|i3blocks]
+                  Print due counts for a status bar (read-only)
+  optimize        Fit FSRS parameters to your review history
+  import <source> [FILE...] [--name <deck>] [--force]
+                  Create decks from existing keybindings (vscode, tmux,
+                  hyprland, sway, i3, vim, history)
+
+Options:
+  -h, --help      Print help
+  -V, --version   Print version";
+
+/// A parsed command line
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Launch the TUI, optionally going straight into a deck
+    Tui {
+        deck: Option<String>,
+    },
+    List,
+    Due {
+        json: bool,
+    },
+    Stats {
+        deck: String,
+    },
+    Status {
+        format: StatusFormat,
+    },
+    Optimize,
+    Import(ImportOptions),
+    Help,
+    Version,
+}
+
+impl Command {
+    /// Parse command line arguments (excluding the program name)
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let args: Vec<String> = args.into_iter().collect();
+
+        if args.iter().any(|a| a == "--version" || a == "-V") {
+            return Ok(Command::Version);
+        }
+        if args.iter().any(|a| a == "--help" || a == "-h") {
+            return Ok(Command::Help);
+        }
+
+        let Some((command, rest)) = args.split_first() else {
+            return Ok(Command::Tui { deck: None });
+        };
+
+        let command = match command.as_str() {
+            "study" => Command::Tui {
+                deck: Some(single_arg(command, rest, "<deck>")?),
+            },
+            "list" => {
+                no_args(command, rest)?;
+                Command::List
+            }
+            "due" => match rest {
+                [] => Command::Due { json: false },
+                [flag] if flag == "--json" => Command::Due { json: true },
+                _ => bail!("Usage: kbsr due [--json]"),
+            },
+            "stats" => Command::Stats {
+                deck: single_arg(command, rest, "<deck>")?,
+            },
+            "status" => match rest {
+                [] => Command::Status {
+                    format: StatusFormat::default(),
+                },
+                [flag, format] if flag == "--format" => Command::Status {
+                    format: StatusFormat::parse(format)?,
+                },
+                [flag] if flag.starts_with("--format=") => Command::Status {
+                    format: StatusFormat::parse(&flag["--format=".len()..])?,
+                },
+                _ => bail!("Usage: kbsr status [--format waybar|text|i3blocks]"),
+            },
+            "optimize" => {
+                no_args(command, rest)?;
+                Command::Optimize
+            }
+            "import" => Command::Import(parse_import(rest)?),
+            "help" => Command::Help,
+            other => bail!("Unknown command '{}'\n\n{}", other, USAGE),
+        };
+
+        Ok(command)
+    }
+}
+
+fn single_arg(command: &str, rest: &[String], name: &str) -> Result<String> {
+    match rest {
+        [arg] => Ok(arg.clone()),
+        _ => bail!("Usage: kbsr {} {}", command, name),
+    }
+}
+
+const IMPORT_USAGE: &str = "Usage: kbsr import <source> [FILE...] [--name <deck>] [--prefix <key>] [--leader <key>] [--limit <n>] [--force]";
+
+fn parse_import(rest: &[String]) -> Result<ImportOptions> {
+    let Some((source, rest)) = rest.split_first() else {
+        bail!(IMPORT_USAGE);
+    };
+
+    let mut options = ImportOptions::new(ImportSource::parse(source)?);
+    let mut args = rest.iter();
+    while let Some(arg) = args.next() {
+        if arg == "-" || !arg.starts_with('-') {
+            options.paths.push(arg.clone());
+            continue;
+        }
+
+        // Options take their value as the next argument or after `=`
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let mut value = || match inline_value.clone().or_else(|| args.next().cloned()) {
+            Some(value) => Ok(value),
+            None => Err(anyhow::anyhow!(
+                "Missing value for {}\n{}",
+                flag,
+                IMPORT_USAGE
+            )),
+        };
+        match flag {
+            "--force" | "-f" => options.force = true,
+            "--name" => options.name = Some(value()?),
+            "--prefix" => options.prefix = Some(value()?),
+            "--leader" => options.leader = Some(value()?),
+            "--limit" => {
+                let limit = value()?;
+                match limit.parse() {
+                    Ok(limit) => options.limit = Some(limit),
+                    Err(_) => bail!("Invalid --limit '{}': expected a number", limit),
+                }
+            }
+            _ => bail!("Unknown option '{}'\n{}", flag, IMPORT_USAGE),
+        }
+    }
+
+    Ok(options)
+}
+
+fn no_args(command: &str, rest: &[String]) -> Result<()> {
+    if !rest.is_empty() {
+        bail!("Usage: kbsr {}", command);
+    }
+    Ok(())
+}
+
+/// Open the database and sync deck files, as the TUI does on startup
+fn open_synced(config: &Config) -> Result<(Storage, DeckSettings)> {
+    config.ensure_dirs()?;
+    let mut storage = Storage::open(&config.db_path)?.with_study_day(config.study_day());
+    let settings = sync_deck_files(config, &mut storage)?;
+    Ok((storage, settings))
+}
+
+/// Every deck's stats, with due counts capped by today's limits
+fn deck_stats(
+    config: &Config,
+    storage: &Storage,
+    settings: &DeckSettings,
+) -> Result<Vec<DeckStats>> {
+    let mut decks = storage.get_deck_stats(&settings.keyboard_modes)?;
+    cap_due_counts(
+        &mut decks,
+        &settings.daily_limits,
+        config.daily_limits(),
+        &storage.get_today_counts()?,
+    );
+    Ok(decks)
+}
+
+/// `kbsr list`: print every deck with its due and total card counts
+pub fn list(config: &Config) -> Result<()> {
+    let (storage, settings) = open_synced(config)?;
+    let decks = deck_stats(config, &storage, &settings)?;
+
+    if decks.is_empty() {
+        println!("No decks found in {}", config.decks_dir.display());
+        return Ok(());
+    }
+
+    let width = decks.iter().map(|d| d.name.len()).max().unwrap_or(0);
+    for deck in &decks {
+        println!(
+            "{:<width$}  {} due / {} total",
+            deck.name,
+            deck.due_cards,
+            deck.total_cards,
+            width = width
+        );
+    }
+
+    Ok(())
+}
+
+/// `kbsr due`: print decks with cards due today, as text or JSON
+pub fn due(config: &Config, json: bool) -> Result<()> {
+    let (storage, settings) = open_synced(config)?;
+    let decks = deck_stats(config, &storage, &settings)?;
+    let total: i32 = decks.iter().map(|d| d.due_cards).sum();
+
+    if json {
+        let decks: Vec<_> = decks
+            .iter()
+            .map(|d| {
+                serde_json::json!({
+                    "name": d.name,
+                    "due": d.due_cards,
+                    "total": d.total_cards,
+                })
+            })
+            .collect();
+        let output = serde_json::json!({ "due": total, "decks": decks });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    for deck in decks.iter().filter(|d| d.due_cards > 0) {
+        println!("{}: {}", deck.name, deck.due_cards);
+    }
+    println!("Total: {}", total);
+
+    Ok(())
+}
+
+/// `kbsr stats <deck>`: print review statistics for a deck
+pub fn stats(config: &Config, deck: &str) -> Result<()> {
+    let (storage, settings) = open_synced(config)?;
+    let Some(detail) = storage.get_deck_detail(deck)? else {
+        bail!("Unknown deck '{}'", deck);
+    };
+    // Capped by today's limits, as `kbsr list` and `kbsr due` count it
+    let due_today = deck_stats(config, &storage, &settings)?
+        .into_iter()
+        .find(|d| d.name == deck)
+        .map_or(detail.due_cards, |d| d.due_cards);
+
+    println!("{}", deck);
+    println!("  Cards:          {}", detail.total_cards);
+    println!("  Due today:      {}", due_today);
+    println!("  New:            {}", detail.new_cards);
+    println!("  Reviews:        {}", detail.total_reviews);
+    if let Some(retention) = detail.retention {
+        println!("  Retention:      {:.0}%", retention * 100.0);
+    }
+    if let Some(avg_ms) = detail.avg_response_ms {
+        println!("  Avg response:   {:.1}s", avg_ms / 1000.0);
+    }
+    if let Some(stability) = detail.avg_stability {
+        println!("  Avg stability:  {:.1} days", stability);
+    }
+    if let Some(next_due) = detail.next_due {
+        if next_due <= Utc::now() {
+            println!("  Next due:       now");
+        } else {
+            println!(
+                "  Next due:       {}",
+                next_due.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command> {
+        Command::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse(&[]).unwrap(), Command::Tui { deck: None });
+        assert_eq!(
+            parse(&["study", "vim"]).unwrap(),
+            Command::Tui {
+                deck: Some("vim".to_string())
+            }
+        );
+        assert_eq!(parse(&["list"]).unwrap(), Command::List);
+        assert_eq!(parse(&["due"]).unwrap(), Command::Due { json: false });
+        assert_eq!(
+            parse(&["due", "--json"]).unwrap(),
+            Command::Due { json: true }
+        );
+        assert_eq!(
+            parse(&["stats", "git"]).unwrap(),
+            Command::Stats {
+                deck: "git".to_string()
+            }
+        );
+        assert_eq!(
+            parse(&["status"]).unwrap(),
+            Command::Status {
+                format: StatusFormat::Waybar
+            }
+        );
+        assert_eq!(
+            parse(&["status", "--format", "i3blocks"]).unwrap(),
+            Command::Status {
+                format: StatusFormat::I3blocks
+            }
+        );
+        assert_eq!(
+            parse(&["status", "--format=text"]).unwrap(),
+            Command::Status {
+                format: StatusFormat::Text
+            }
+        );
+        assert_eq!(parse(&["optimize"]).unwrap(), Command::Optimize);
+        assert_eq!(
+            parse(&[
+                "import",
+                "vscode",
+                "defaults.json",
+                "keybindings.json",
+                "--name=code",
+                "--force"
+            ])
+            .unwrap(),
+            Command::Import(ImportOptions {
+                source: ImportSource::Vscode,
+                paths: vec!["defaults.json".to_string(), "keybindings.json".to_string()],
+                name: Some("code".to_string()),
+                force: true,
+                ..ImportOptions::new(ImportSource::Vscode)
+            })
+        );
+        assert_eq!(
+            parse(&["import", "tmux", "--prefix", "C-a"]).unwrap(),
+            Command::Import(ImportOptions {
+                prefix: Some("C-a".to_string()),
+                ..ImportOptions::new(ImportSource::Tmux)
+            })
+        );
+        assert_eq!(
+            parse(&["import", "vim", "maps.txt", "--leader=<Space>"]).unwrap(),
+            Command::Import(ImportOptions {
+                paths: vec!["maps.txt".to_string()],
+                leader: Some("<Space>".to_string()),
+                ..ImportOptions::new(ImportSource::Vim)
+            })
+        );
+        assert_eq!(
+            parse(&["import", "shell", "--limit", "20"]).unwrap(),
+            Command::Import(ImportOptions {
+                limit: Some(20),
+                ..ImportOptions::new(ImportSource::History)
+            })
+        );
+        assert_eq!(parse(&["-V"]).unwrap(), Command::Version);
+        assert_eq!(parse(&["list", "--help"]).unwrap(), Command::Help);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(&["study"]).is_err());
+        assert!(parse(&["stats", "a", "b"]).is_err());
+        assert!(parse(&["due", "--yaml"]).is_err());
+        assert!(parse(&["status", "--format", "xml"]).is_err());
+        assert!(parse(&["bogus"]).is_err());
+        assert!(parse(&["import"]).is_err());
+        assert!(parse(&["import", "emacs"]).is_err());
+        assert!(parse(&["import", "i3", "--prefix"]).is_err());
+        assert!(parse(&["import", "history", "--limit", "lots"]).is_err());
+        assert!(parse(&["import", "vscode", "--name"]).is_err());
+        assert!(parse(&["import", "vscode", "--bogus"]).is_err());
+    }
+}
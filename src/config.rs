@@ -1,4 +1,7 @@
-use anyhow::Result;
+use crate::limits::DailyLimits;
+use crate::notation::Notation;
+use crate::study_day::{DueBy, StudyDay};
+use anyhow::{Result, bail};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
@@ -20,6 +23,11 @@ pub struct Config {
     #[serde(default = "default_hard_threshold")]
     pub hard_threshold_ms: u64,
 
+    /// Rate against expected times fitted to your own review history, falling back
+    /// to the fixed thresholds until there is enough of it (default: false)
+    #[serde(default)]
+    pub adaptive_rating: bool,
+
     /// Delay in milliseconds to show success indicator (default: 500)
     #[serde(default = "default_success_delay")]
     pub success_delay_ms: u64,
@@ -36,10 +44,42 @@ pub struct Config {
     #[serde(default = "default_quit_keybind")]
     pub quit_keybind: String,
 
+    /// Notation for answers and typed chords: standard, vim, emacs or mac (default: standard)
+    #[serde(default)]
+    pub display_notation: Notation,
+
+    /// Edit command-mode answers with Backspace, Ctrl+W and Ctrl+U, judged on Enter (default: false)
+    #[serde(default)]
+    pub command_line_editing: bool,
+
     /// Shuffle cards before each study session (default: true)
     #[serde(default = "default_shuffle_cards")]
     pub shuffle_cards: bool,
 
+    /// New cards per day across all decks; 0 = no limit (default: 0)
+    #[serde(default)]
+    pub new_cards_per_day: usize,
+
+    /// Reviews per day across all decks; 0 = no limit (default: 0)
+    #[serde(default)]
+    pub reviews_per_day: usize,
+
+    /// Minutes before a session wraps up after the current card; 0 = no time box (default: 0)
+    #[serde(default)]
+    pub session_minutes: u64,
+
+    /// Local hour (0-23) a new study day starts, for due counts and daily limits (default: 4)
+    #[serde(default = "default_day_starts_at")]
+    pub day_starts_at: u32,
+
+    /// Cards a session picks up: those due now, or all due today (default: now)
+    #[serde(default)]
+    pub session_due: DueBy,
+
+    /// Maximum cards in a cram session, least stable first; 0 = whole deck (default: 20)
+    #[serde(default = "default_cram_limit")]
+    pub cram_limit: usize,
+
     /// FSRS desired retention rate 0.0-1.0 (default: 0.9)
     #[serde(default = "default_desired_retention")]
     pub desired_retention: f32,
@@ -98,6 +138,14 @@ fn default_shuffle_cards() -> bool {
     true
 }
 
+fn default_day_starts_at() -> u32 {
+    0
+}
+
+fn default_cram_limit() -> usize {
+    20
+}
+
 fn default_desired_retention() -> f32 {
     0.9
 }
@@ -129,11 +177,20 @@ impl Default for Config {
             max_attempts: default_max_attempts(),
             easy_threshold_ms: default_easy_threshold(),
             hard_threshold_ms: default_hard_threshold(),
+            adaptive_rating: false,
             success_delay_ms: default_success_delay(),
             failed_flash_delay_ms: default_failed_flash_delay(),
             pause_keybind: default_pause_keybind(),
             quit_keybind: default_quit_keybind(),
+            display_notation: Notation::default(),
+            command_line_editing: false,
             shuffle_cards: default_shuffle_cards(),
+            new_cards_per_day: 0,
+            reviews_per_day: 0,
+            session_minutes: 0,
+            day_starts_at: default_day_starts_at(),
+            session_due: DueBy::default(),
+            cram_limit: default_cram_limit(),
             desired_retention: default_desired_retention(),
             interval_modifier: default_interval_modifier(),
             max_interval_days: default_max_interval_days(),
@@ -162,6 +219,12 @@ impl Config {
             let mut config: Config = toml::from_str(&content)?;
             config.decks_dir = expand_tilde(&config.decks_dir);
             config.db_path = expand_tilde(&config.db_path);
+            if config.day_starts_at > 23 {
+                bail!(
+                    "day_starts_at must be an hour from 0 to 23, got {}",
+                    config.day_starts_at
+                );
+            }
             Ok(config)
         } else {
             Ok(Config::default())
@@ -175,6 +238,19 @@ impl Config {
             .unwrap_or_else(|| PathBuf::from("config.toml"))
     }
 
+    /// Limits on new cards and reviews per day across all decks
+    pub fn daily_limits(&self) -> DailyLimits {
+        DailyLimits {
+            new_cards: self.new_cards_per_day,
+            reviews: self.reviews_per_day,
+        }
+    }
+
+    /// Study days as set by `day_starts_at`
+    pub fn study_day(&self) -> StudyDay {
+        StudyDay::new(self.day_starts_at)
+    }
+
     /// Ensure required directories exist
     pub fn ensure_dirs(&self) -> Result<()> {
         if let Some(parent) = self.decks_dir.parent() {
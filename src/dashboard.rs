@@ -0,0 +1,587 @@
+use crate::matcher::MistakeKind;
+use crate::storage::{MistakeCount, Review, ReviewKey, Storage, StoredCard};
+use crate::study_day::StudyDay;
+use anyhow::Result;
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::HashMap;
+
+/// Weeks of history shown in the review heatmap
+pub const HEATMAP_WEEKS: usize = 20;
+/// Days of history in the response time trend
+pub const TREND_DAYS: usize = 30;
+/// Days ahead in the due forecast
+pub const FORECAST_DAYS: usize = 30;
+/// Cards listed in the slowest and most-failed tables
+const TOP_CARDS: usize = 5;
+/// Chord positions in the hesitation breakdown; later chords count towards the last
+pub const PAUSE_POSITIONS: usize = 5;
+
+/// Upper bounds (days, exclusive) and labels of the stability histogram buckets
+const STABILITY_BUCKETS: &[(f32, &str)] = &[
+    (1.0, "< 1d"),
+    (3.0, "1-3d"),
+    (7.0, "3-7d"),
+    (14.0, "1-2w"),
+    (30.0, "2-4w"),
+    (f32::INFINITY, "4w+"),
+];
+
+/// Retention for one deck
+pub struct DeckRetention {
+    pub name: String,
+    /// Reviews counted: those of cards last seen on an earlier day
+    pub reviews: usize,
+    /// Share of counted reviews not rated Again (None if there are none)
+    pub retention: Option<f64>,
+}
+
+/// A card in the slowest or most-failed list
+pub struct CardRank {
+    pub deck: String,
+    pub keybind: String,
+    pub description: String,
+    /// Average response time in ms (slowest) or number of Again ratings (most failed)
+    pub value: f64,
+}
+
+/// A chord within a card: where the user pauses longest, or slips most often
+pub struct ChordRank {
+    pub chord: String,
+    /// The card, with the average pause in ms or the number of wrong keys as its value
+    pub card: CardRank,
+    /// The card's most common kind of mistake, for mistyped chords
+    pub usual_mistake: Option<MistakeKind>,
+}
+
+/// Everything shown on the stats screen, computed from `cards` and `reviews`.
+/// Cram practice reviews aren't counted, matching `kbsr stats`.
+pub struct Dashboard {
+    /// First day of the heatmap (a Monday)
+    pub heatmap_start: NaiveDate,
+    /// Reviews per day from `heatmap_start` up to and including today
+    pub daily_reviews: Vec<u32>,
+    pub deck_retention: Vec<DeckRetention>,
+    /// Average response time (ms) per day over the last `TREND_DAYS` days, oldest first
+    pub response_trend: Vec<Option<f64>>,
+    /// Cards per stability bucket (label, count); never-reviewed cards are counted as "new"
+    pub stability_buckets: Vec<(&'static str, usize)>,
+    pub new_cards: usize,
+    /// Cards due on each of the next `FORECAST_DAYS` days, today first (overdue included)
+    pub due_forecast: Vec<usize>,
+    pub slowest: Vec<CardRank>,
+    pub most_failed: Vec<CardRank>,
+    /// Average time (ms) to the first key of first-try answers
+    pub first_key_ms: Option<f64>,
+    /// Average time (ms) from the first key to the last of first-try answers
+    pub execution_ms: Option<f64>,
+    /// Average pause (ms) before the chord at each position of first-try answers;
+    /// the first is the time to the first key
+    pub position_pauses: Vec<Option<f64>>,
+    /// Chords after the first with the longest average pause before them
+    pub hesitations: Vec<ChordRank>,
+    /// Chords most often mistyped, by the chord the answer needed
+    pub mistyped: Vec<ChordRank>,
+    /// Mistakes of each kind across all cards, most common first
+    pub mistake_kinds: Vec<(MistakeKind, usize)>,
+}
+
+impl Dashboard {
+    /// Load cards, reviews and mistakes and compute the dashboard for today
+    pub fn load(storage: &Storage) -> Result<Self> {
+        let cards = storage.get_all_cards()?;
+        let reviews = storage.get_all_reviews()?;
+        let keys = storage.get_review_keys()?;
+        let mistakes = storage.get_mistake_counts()?;
+        let day = storage.study_day();
+        Ok(Self::build(
+            &cards,
+            &reviews,
+            &keys,
+            &mistakes,
+            day,
+            day.today(),
+        ))
+    }
+
+    /// Compute the dashboard for the study day `today`. `reviews` must be sorted by
+    /// card, then time, and `keys` grouped by review in the order they were pressed.
+    pub fn build(
+        cards: &[StoredCard],
+        reviews: &[Review],
+        keys: &[ReviewKey],
+        mistakes: &[MistakeCount],
+        day: StudyDay,
+        today: NaiveDate,
+    ) -> Self {
+        let local_day = |r: &Review| day.date_of(r.reviewed_at);
+
+        // Heatmap: whole weeks, Monday first, ending with the current week
+        let heatmap_start = today
+            - Duration::days(today.weekday().num_days_from_monday() as i64)
+            - Duration::weeks(HEATMAP_WEEKS as i64 - 1);
+        let mut daily_reviews = vec![0u32; (today - heatmap_start).num_days() as usize + 1];
+        for review in reviews {
+            let offset = (local_day(review) - heatmap_start).num_days();
+            if let Some(count) = usize::try_from(offset)
+                .ok()
+                .and_then(|i| daily_reviews.get_mut(i))
+            {
+                *count += 1;
+            }
+        }
+
+        // Response time trend
+        let trend_start = today - Duration::days(TREND_DAYS as i64 - 1);
+        let mut trend_totals = vec![(0i64, 0i64); TREND_DAYS];
+        for review in reviews {
+            let offset = (local_day(review) - trend_start).num_days();
+            if let Some((total, count)) = usize::try_from(offset)
+                .ok()
+                .and_then(|i| trend_totals.get_mut(i))
+            {
+                *total += review.response_time_ms;
+                *count += 1;
+            }
+        }
+        let response_trend = trend_totals
+            .into_iter()
+            .map(|(total, count)| (count > 0).then(|| total as f64 / count as f64))
+            .collect();
+
+        // Retention, counting only reviews of cards last seen on an earlier day
+        // (same-day repeats say little about long-term memory)
+        let card_by_id: HashMap<i64, &StoredCard> = cards.iter().map(|c| (c.id, c)).collect();
+        let mut retention_counts: HashMap<&str, (usize, usize)> = HashMap::new();
+        for card_reviews in reviews.chunk_by(|a, b| a.card_id == b.card_id) {
+            let Some(card) = card_by_id.get(&card_reviews[0].card_id) else {
+                continue;
+            };
+            for pair in card_reviews.windows(2) {
+                if local_day(&pair[1]) > local_day(&pair[0]) {
+                    let (passed, total) = retention_counts.entry(&card.deck).or_default();
+                    *total += 1;
+                    if pair[1].rating > 1 {
+                        *passed += 1;
+                    }
+                }
+            }
+        }
+        let mut deck_names: Vec<&str> = cards.iter().map(|c| c.deck.as_str()).collect();
+        deck_names.dedup();
+        let deck_retention = deck_names
+            .into_iter()
+            .map(|name| {
+                let (passed, total) = retention_counts.get(name).copied().unwrap_or_default();
+                DeckRetention {
+                    name: name.to_string(),
+                    reviews: total,
+                    retention: (total > 0).then(|| passed as f64 / total as f64),
+                }
+            })
+            .collect();
+
+        // Stability distribution
+        let mut stability_buckets: Vec<(&'static str, usize)> = STABILITY_BUCKETS
+            .iter()
+            .map(|(_, label)| (*label, 0))
+            .collect();
+        let mut new_cards = 0;
+        for card in cards {
+            match card.stability {
+                Some(stability) => {
+                    let bucket = STABILITY_BUCKETS
+                        .iter()
+                        .position(|(max, _)| stability < *max)
+                        .unwrap_or(STABILITY_BUCKETS.len() - 1);
+                    stability_buckets[bucket].1 += 1;
+                }
+                None => new_cards += 1,
+            }
+        }
+
+        // Due forecast; unscheduled and overdue cards are due today
+        let mut due_forecast = vec![0usize; FORECAST_DAYS];
+        for card in cards {
+            let offset = card
+                .due_date
+                .map(|due| (day.date_of(due) - today).num_days())
+                .unwrap_or(0)
+                .max(0) as usize;
+            if let Some(count) = due_forecast.get_mut(offset) {
+                *count += 1;
+            }
+        }
+
+        // Slowest and most-failed cards
+        let mut per_card: Vec<(&StoredCard, f64, usize)> = reviews
+            .chunk_by(|a, b| a.card_id == b.card_id)
+            .filter_map(|card_reviews| {
+                let card = card_by_id.get(&card_reviews[0].card_id)?;
+                let total: i64 = card_reviews.iter().map(|r| r.response_time_ms).sum();
+                let failures = card_reviews.iter().filter(|r| r.rating == 1).count();
+                Some((*card, total as f64 / card_reviews.len() as f64, failures))
+            })
+            .collect();
+        let rank = |card: &StoredCard, value: f64| CardRank {
+            deck: card.deck.clone(),
+            keybind: card.keybind.clone(),
+            description: card.description.clone(),
+            value,
+        };
+
+        per_card.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let slowest = per_card
+            .iter()
+            .take(TOP_CARDS)
+            .map(|(card, avg_ms, _)| rank(card, *avg_ms))
+            .collect();
+
+        per_card.sort_by_key(|c| std::cmp::Reverse(c.2));
+        let most_failed = per_card
+            .iter()
+            .filter(|(_, _, failures)| *failures > 0)
+            .take(TOP_CARDS)
+            .map(|(card, _, failures)| rank(card, *failures as f64))
+            .collect();
+
+        // Chord timing, from the keys of first-try answers
+        let mut first_key = Vec::new();
+        let mut execution = Vec::new();
+        let mut position_totals = vec![(0u64, 0u64); PAUSE_POSITIONS];
+        let mut pauses: HashMap<(i64, usize), (&str, u64, u64)> = HashMap::new();
+        for review_keys in keys.chunk_by(|a, b| a.review_id == b.review_id) {
+            let (Some(first), Some(last)) = (review_keys.first(), review_keys.last()) else {
+                continue;
+            };
+            if !first.first_try {
+                continue;
+            }
+            first_key.push(first.key.elapsed_ms as f64);
+            execution.push(last.key.elapsed_ms.saturating_sub(first.key.elapsed_ms) as f64);
+
+            let mut previous_ms = 0;
+            for key in review_keys {
+                let pause = key.key.elapsed_ms.saturating_sub(previous_ms);
+                previous_ms = key.key.elapsed_ms;
+                let (total, count) =
+                    &mut position_totals[key.key.position.min(PAUSE_POSITIONS - 1)];
+                *total += pause;
+                *count += 1;
+                if key.key.position > 0 {
+                    let (_, total, count) = pauses
+                        .entry((key.card_id, key.key.position))
+                        .or_insert((&key.key.chord, 0, 0));
+                    *total += pause;
+                    *count += 1;
+                }
+            }
+        }
+        let average = |values: &[f64]| {
+            (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+        };
+        let position_pauses = position_totals
+            .into_iter()
+            .map(|(total, count)| (count > 0).then(|| total as f64 / count as f64))
+            .collect();
+
+        let chord_rank = |card_id: i64, chord: &str, value: f64| {
+            card_by_id.get(&card_id).map(|card| ChordRank {
+                chord: chord.to_string(),
+                card: rank(card, value),
+                usual_mistake: None,
+            })
+        };
+        let mut hesitations: Vec<ChordRank> = pauses
+            .into_iter()
+            .filter_map(|((card_id, _), (chord, total, count))| {
+                chord_rank(card_id, chord, total as f64 / count as f64)
+            })
+            .collect();
+        hesitations.sort_by(|a, b| {
+            b.card
+                .value
+                .total_cmp(&a.card.value)
+                .then_with(|| a.chord.cmp(&b.chord))
+        });
+        hesitations.truncate(TOP_CARDS);
+
+        // Kinds of mistake, overall and per card
+        let mut kind_totals: HashMap<MistakeKind, usize> = HashMap::new();
+        let mut usual_mistakes: HashMap<i64, (MistakeKind, i64)> = HashMap::new();
+        for mistake in mistakes {
+            let Some(kind) = MistakeKind::from_name(&mistake.kind) else {
+                continue;
+            };
+            *kind_totals.entry(kind).or_default() += mistake.count as usize;
+            let usual = usual_mistakes
+                .entry(mistake.card_id)
+                .or_insert((kind, mistake.count));
+            if mistake.count > usual.1 {
+                *usual = (kind, mistake.count);
+            }
+        }
+
+        let mut misses: HashMap<(i64, &str), usize> = HashMap::new();
+        for key in keys {
+            if let Some(expected) = &key.key.expected {
+                *misses.entry((key.card_id, expected)).or_default() += 1;
+            }
+        }
+        let mut mistyped: Vec<ChordRank> = misses
+            .into_iter()
+            .filter_map(|((card_id, chord), count)| {
+                let mut rank = chord_rank(card_id, chord, count as f64)?;
+                rank.usual_mistake = usual_mistakes.get(&card_id).map(|(kind, _)| *kind);
+                Some(rank)
+            })
+            .collect();
+        mistyped.sort_by(|a, b| {
+            b.card
+                .value
+                .total_cmp(&a.card.value)
+                .then_with(|| a.chord.cmp(&b.chord))
+        });
+        mistyped.truncate(TOP_CARDS);
+
+        let mut mistake_kinds: Vec<(MistakeKind, usize)> = MistakeKind::ALL
+            .into_iter()
+            .filter_map(|kind| kind_totals.get(&kind).map(|n| (kind, *n)))
+            .collect();
+        mistake_kinds.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+
+        Dashboard {
+            heatmap_start,
+            daily_reviews,
+            deck_retention,
+            response_trend,
+            stability_buckets,
+            new_cards,
+            due_forecast,
+            slowest,
+            most_failed,
+            first_key_ms: average(&first_key),
+            execution_ms: average(&execution),
+            position_pauses,
+            hesitations,
+            mistyped,
+            mistake_kinds,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::KeyPress;
+    use chrono::{DateTime, Local, TimeZone, Utc};
+
+    fn card(id: i64, deck: &str, stability: Option<f32>, due_in_days: Option<i64>) -> StoredCard {
+        StoredCard {
+            id,
+            deck: deck.to_string(),
+            keybind: format!("Ctrl+{}", id),
+            description: format!("Card {}", id),
+            stability,
+            difficulty: stability.map(|_| 5.0),
+            due_date: due_in_days.map(noon),
+            last_review: None,
+            review_count: 0,
+            section: None,
+            tags: Vec::new(),
+            reverse: false,
+        }
+    }
+
+    /// Local noon `days` from the test's "today"
+    fn noon(days: i64) -> DateTime<Utc> {
+        let day = today() + Duration::days(days);
+        Local
+            .from_local_datetime(&day.and_hms_opt(12, 0, 0).unwrap())
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, 11).unwrap() // a Wednesday
+    }
+
+    fn review(card_id: i64, rating: i32, response_time_ms: i64, days_ago: i64) -> Review {
+        Review {
+            id: 0,
+            card_id,
+            rating,
+            response_time_ms,
+            attempts: 1,
+            reviewed_at: noon(-days_ago),
+        }
+    }
+
+    #[test]
+    fn test_build_dashboard() {
+        let cards = vec![
+            card(1, "git", Some(0.5), Some(-2)),
+            card(2, "git", Some(10.0), Some(3)),
+            card(3, "vim", None, None),
+        ];
+        let reviews = vec![
+            review(1, 3, 1000, 5),
+            review(1, 1, 3000, 2),
+            review(1, 3, 2000, 2),
+            review(2, 3, 500, 4),
+            review(2, 4, 500, 0),
+        ];
+
+        let dashboard = Dashboard::build(&cards, &reviews, &[], &[], StudyDay::default(), today());
+
+        // Heatmap starts on a Monday and ends today
+        assert_eq!(dashboard.heatmap_start.weekday(), chrono::Weekday::Mon);
+        assert_eq!(
+            dashboard.heatmap_start + Duration::days(dashboard.daily_reviews.len() as i64 - 1),
+            today()
+        );
+        assert_eq!(dashboard.daily_reviews.iter().sum::<u32>(), 5);
+        assert_eq!(dashboard.daily_reviews.last(), Some(&1));
+
+        // git: card 1 fails then passes the same day (only the first counts), card 2 passes
+        assert_eq!(dashboard.deck_retention[0].name, "git");
+        assert_eq!(dashboard.deck_retention[0].reviews, 2);
+        assert_eq!(dashboard.deck_retention[0].retention, Some(0.5));
+        assert_eq!(dashboard.deck_retention[1].retention, None);
+
+        assert_eq!(dashboard.response_trend.len(), TREND_DAYS);
+        assert_eq!(dashboard.response_trend[TREND_DAYS - 3], Some(2500.0));
+        assert_eq!(dashboard.response_trend[TREND_DAYS - 2], None);
+
+        assert_eq!(dashboard.new_cards, 1);
+        assert_eq!(dashboard.stability_buckets[0], ("< 1d", 1));
+        assert_eq!(dashboard.stability_buckets[3], ("1-2w", 1));
+
+        // Overdue and new cards are due today
+        assert_eq!(dashboard.due_forecast[0], 2);
+        assert_eq!(dashboard.due_forecast[3], 1);
+
+        assert_eq!(dashboard.slowest[0].keybind, "Ctrl+1");
+        assert_eq!(dashboard.slowest[0].value, 2000.0);
+        assert_eq!(dashboard.most_failed.len(), 1);
+        assert_eq!(dashboard.most_failed[0].value, 1.0);
+    }
+
+    /// Keys of one review, each (position, chord, expected chord if wrong, elapsed ms)
+    fn keys(
+        review_id: i64,
+        card_id: i64,
+        first_try: bool,
+        presses: &[(usize, &str, Option<&str>, u64)],
+    ) -> Vec<ReviewKey> {
+        presses
+            .iter()
+            .map(|&(position, chord, expected, elapsed_ms)| ReviewKey {
+                review_id,
+                card_id,
+                first_try,
+                key: KeyPress {
+                    position,
+                    chord: chord.to_string(),
+                    expected: expected.map(str::to_string),
+                    elapsed_ms,
+                    correct: expected.is_none(),
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chord_timing() {
+        let cards = vec![card(1, "vscode", None, None), card(2, "vim", None, None)];
+        let mut all_keys = keys(
+            1,
+            1,
+            true,
+            &[(0, "Ctrl+K", None, 1000), (1, "Ctrl+C", None, 3000)],
+        );
+        all_keys.extend(keys(
+            2,
+            1,
+            true,
+            &[(0, "Ctrl+K", None, 600), (1, "Ctrl+C", None, 1600)],
+        ));
+        all_keys.extend(keys(
+            3,
+            2,
+            true,
+            &[
+                (0, "y", None, 800),
+                (1, "s", None, 1100),
+                (2, "i", None, 1200),
+                (3, "w", None, 1250),
+                (4, "(", None, 1300),
+                (5, ")", None, 1400),
+            ],
+        ));
+        // A retried answer counts towards mistakes but not timing
+        all_keys.extend(keys(
+            4,
+            1,
+            false,
+            &[
+                (0, "Ctrl+K", None, 5000),
+                (1, "Ctrl+X", Some("Ctrl+C"), 9000),
+                (0, "Ctrl+K", None, 9500),
+                (1, "Alt+C", Some("Ctrl+C"), 9600),
+                (0, "Ctrl+J", Some("Ctrl+K"), 9800),
+            ],
+        ));
+
+        let mistakes = vec![
+            MistakeCount {
+                card_id: 1,
+                kind: "wrong_modifier".to_string(),
+                count: 3,
+            },
+            MistakeCount {
+                card_id: 1,
+                kind: "wrong_key".to_string(),
+                count: 1,
+            },
+            MistakeCount {
+                card_id: 2,
+                kind: "wrong_key".to_string(),
+                count: 1,
+            },
+        ];
+        let dashboard = Dashboard::build(
+            &cards,
+            &[],
+            &all_keys,
+            &mistakes,
+            StudyDay::default(),
+            today(),
+        );
+
+        assert_eq!(dashboard.first_key_ms, Some(800.0));
+        assert_eq!(dashboard.execution_ms, Some(1200.0));
+        assert_eq!(dashboard.position_pauses[0], Some(800.0));
+        assert_eq!(dashboard.position_pauses[1], Some(1100.0));
+        // The fifth and sixth chords share the last position
+        assert_eq!(dashboard.position_pauses[PAUSE_POSITIONS - 1], Some(75.0));
+
+        assert_eq!(dashboard.hesitations[0].chord, "Ctrl+C");
+        assert_eq!(dashboard.hesitations[0].card.value, 1500.0);
+        assert_eq!(dashboard.hesitations[1].chord, "s");
+        assert_eq!(dashboard.hesitations.len(), 5);
+
+        assert_eq!(dashboard.mistyped.len(), 2);
+        assert_eq!(dashboard.mistyped[0].chord, "Ctrl+C");
+        assert_eq!(dashboard.mistyped[0].card.value, 2.0);
+        assert_eq!(dashboard.mistyped[0].card.keybind, "Ctrl+1");
+        assert_eq!(dashboard.mistyped[1].chord, "Ctrl+K");
+        assert_eq!(
+            dashboard.mistyped[0].usual_mistake,
+            Some(MistakeKind::WrongModifier)
+        );
+        assert_eq!(
+            dashboard.mistake_kinds,
+            vec![(MistakeKind::WrongModifier, 3), (MistakeKind::WrongKey, 2)]
+        );
+    }
+}
@@ -1,4 +1,6 @@
-use crate::keybind::Keybind;
+use crate::keybind::{Keybind, check_placeholders};
+use crate::limits::DailyLimits;
+use crate::notation::Notation;
 use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 
@@ -17,11 +19,56 @@ pub enum KeyboardMode {
     Command,
 }
 
+impl KeyboardMode {
+    /// Short name as used in the `# mode:` directive
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeyboardMode::Raw => "raw",
+            KeyboardMode::Chars => "chars",
+            KeyboardMode::Command => "command",
+        }
+    }
+
+    /// Parse a deck's keybind column into the card's accepted keybinds, written in
+    /// `notation` or detected chord by chord when `None`.
+    /// Command mode takes the whole column as one command, since `|` is a shell pipe.
+    pub fn parse_keybinds(&self, s: &str, notation: Option<Notation>) -> Result<Vec<Keybind>> {
+        match self {
+            KeyboardMode::Command => Ok(vec![Keybind::parse_command(s)?]),
+            KeyboardMode::Raw | KeyboardMode::Chars => Keybind::parse_alternatives(s, notation),
+        }
+    }
+
+    /// Format accepted keybinds as they are written in a deck file
+    pub fn format_keybinds(&self, keybinds: &[Keybind]) -> String {
+        match self {
+            KeyboardMode::Command => keybinds
+                .iter()
+                .map(|kb| kb.as_command_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            KeyboardMode::Raw | KeyboardMode::Chars => Keybind::format_alternatives(keybinds),
+        }
+    }
+}
+
 /// A single card in a deck
 #[derive(Debug, Clone)]
 pub struct Card {
-    pub keybind: Keybind,
+    /// Accepted keybinds; typing any one of them answers the card
+    pub keybinds: Vec<Keybind>,
     pub description: String,
+    /// Stable identity from an `id:` column, so edits keep progress
+    pub id: Option<String>,
+    /// Reset progress whenever the keybind or description changes (`reset` column)
+    pub reset: bool,
+    /// Section from the nearest `# section:` directive above the card
+    pub section: Option<String>,
+    /// Tags from the third column (comma or space separated)
+    pub tags: Vec<String>,
+    /// Also study this card in reverse: show the keybind, pick the description
+    /// (`reverse` column, or `# reverse: on` for the rest of the deck)
+    pub reverse: bool,
 }
 
 /// A deck of cards loaded from a TSV file
@@ -30,12 +77,24 @@ pub struct Deck {
     pub name: String,
     pub cards: Vec<Card>,
     pub keyboard_mode: KeyboardMode,
+    /// Notation answers are shown in (`# display:`), overriding the config
+    pub display_notation: Option<Notation>,
+    /// Command mode: compare commands as shell words, so flags can come in any order
+    /// (`# match: shell`)
+    pub shell_match: bool,
+    /// Command mode: `{name}` and `<name>` in a command take any argument
+    /// (`# placeholders: on`)
+    pub placeholders: bool,
+    /// This deck's own caps on new cards and reviews per day
+    /// (`# new-per-day:` and `# reviews-per-day:`)
+    pub daily_limits: DailyLimits,
 }
 
 impl Deck {
     /// Load a deck from a TSV file
-    /// Format: keybind<TAB>description
-    /// Lines starting with # are comments (or directives like `# mode: chars`)
+    /// Format: keybind[ | keybind...]<TAB>description[<TAB>tags][<TAB>id:name][<TAB>reset][<TAB>reverse]
+    /// Lines starting with # are comments (or directives like `# mode: chars`, `# notation: emacs`
+    /// and `# section: Name`)
     /// Empty lines are skipped
     pub fn load(path: &Path) -> Result<Self> {
         let name = path
@@ -47,8 +106,15 @@ impl Deck {
         let content = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read deck file: {}", path.display()))?;
 
-        let mut cards = Vec::new();
+        let mut cards: Vec<Card> = Vec::new();
         let mut keyboard_mode = KeyboardMode::default();
+        let mut notation: Option<Notation> = None;
+        let mut display_notation: Option<Notation> = None;
+        let mut shell_match = false;
+        let mut placeholders = false;
+        let mut daily_limits = DailyLimits::default();
+        let mut section: Option<String> = None;
+        let mut reverse_all = false;
 
         for (line_num, line) in content.lines().enumerate() {
             let line = line.trim();
@@ -75,13 +141,77 @@ impl Deck {
                                 path.display()
                             ),
                         }
+                    } else if let Some(notation_value) = rest.strip_prefix("notation:") {
+                        let notation_value = notation_value.trim();
+                        notation = if notation_value.eq_ignore_ascii_case("auto") {
+                            None
+                        } else {
+                            Some(Notation::from_name(notation_value).ok_or_else(|| {
+                                anyhow::anyhow!(
+                                    "Unknown notation '{}' on line {} in {}. Use 'auto', 'standard', 'vim', 'emacs' or 'mac'.",
+                                    notation_value,
+                                    line_num + 1,
+                                    path.display()
+                                )
+                            })?)
+                        };
+                    } else if let Some(display_value) = rest.strip_prefix("display:") {
+                        let display_value = display_value.trim();
+                        display_notation = Some(Notation::from_name(display_value).ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "Unknown display notation '{}' on line {} in {}. Use 'standard', 'vim', 'emacs' or 'mac'.",
+                                display_value,
+                                line_num + 1,
+                                path.display()
+                            )
+                        })?);
+                    } else if let Some(match_value) = rest.strip_prefix("match:") {
+                        shell_match = match match_value.trim().to_lowercase().as_str() {
+                            "shell" => true,
+                            "exact" => false,
+                            other => anyhow::bail!(
+                                "Unknown match setting '{}' on line {} in {}. Use 'exact' or 'shell'.",
+                                other,
+                                line_num + 1,
+                                path.display()
+                            ),
+                        };
+                    } else if let Some(value) = rest.strip_prefix("placeholders:") {
+                        placeholders = match value.trim().to_lowercase().as_str() {
+                            "on" | "yes" | "true" => true,
+                            "off" | "no" | "false" => false,
+                            other => anyhow::bail!(
+                                "Unknown placeholders setting '{}' on line {} in {}. Use 'on' or 'off'.",
+                                other,
+                                line_num + 1,
+                                path.display()
+                            ),
+                        };
+                    } else if let Some(value) = rest.strip_prefix("new-per-day:") {
+                        daily_limits.new_cards = parse_daily_limit(value, line_num, path)?;
+                    } else if let Some(value) = rest.strip_prefix("reviews-per-day:") {
+                        daily_limits.reviews = parse_daily_limit(value, line_num, path)?;
+                    } else if let Some(reverse_value) = rest.strip_prefix("reverse:") {
+                        reverse_all = match reverse_value.trim().to_lowercase().as_str() {
+                            "on" | "yes" | "true" => true,
+                            "off" | "no" | "false" => false,
+                            other => anyhow::bail!(
+                                "Unknown reverse setting '{}' on line {} in {}. Use 'on' or 'off'.",
+                                other,
+                                line_num + 1,
+                                path.display()
+                            ),
+                        };
+                    } else if let Some(section_value) = rest.strip_prefix("section:") {
+                        let section_value = section_value.trim();
+                        section = (!section_value.is_empty()).then(|| section_value.to_string());
                     }
                 }
                 continue;
             }
 
-            let parts: Vec<&str> = line.splitn(2, '\t').collect();
-            if parts.len() != 2 {
+            let parts: Vec<&str> = line.split('\t').collect();
+            if parts.len() < 2 {
                 anyhow::bail!(
                     "Invalid line {} in {}: expected keybind<TAB>description",
                     line_num + 1,
@@ -89,22 +219,83 @@ impl Deck {
                 );
             }
 
-            let keybind = if keyboard_mode == KeyboardMode::Command {
-                Keybind::parse_command(parts[0])
-            } else {
-                Keybind::parse(parts[0])
+            let mut id = None;
+            let mut reset = false;
+            let mut reverse = reverse_all;
+            let mut tags: Vec<String> = Vec::new();
+            for (i, column) in parts.iter().enumerate().skip(2) {
+                let column = column.trim();
+                if let Some(value) = column.strip_prefix("id:") {
+                    let value = value.trim();
+                    if value.is_empty() {
+                        anyhow::bail!("Empty id on line {} in {}", line_num + 1, path.display());
+                    }
+                    if cards.iter().any(|c| c.id.as_deref() == Some(value)) {
+                        anyhow::bail!(
+                            "Duplicate id '{}' on line {} in {}",
+                            value,
+                            line_num + 1,
+                            path.display()
+                        );
+                    }
+                    id = Some(value.to_string());
+                } else if column.eq_ignore_ascii_case("reset") {
+                    reset = true;
+                } else if column.eq_ignore_ascii_case("reverse") {
+                    reverse = true;
+                } else if i == 2 {
+                    for tag in column.split(|c: char| c == ',' || c.is_whitespace()) {
+                        // A lone `reset` or `reverse` column is read as that column, so
+                        // a tag by those names would mean different things in different places
+                        if tag.eq_ignore_ascii_case("reset")
+                            || tag.eq_ignore_ascii_case("reverse")
+                            || tag.starts_with("id:")
+                        {
+                            anyhow::bail!(
+                                "Tag '{}' on line {} in {} is a column name. Put it in a column of its own or rename the tag.",
+                                tag,
+                                line_num + 1,
+                                path.display()
+                            );
+                        }
+                        if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+                            tags.push(tag.to_string());
+                        }
+                    }
+                } else if !column.is_empty() {
+                    anyhow::bail!(
+                        "Unknown column '{}' on line {} in {}. Use 'id:<name>', 'reset' or 'reverse'.",
+                        column,
+                        line_num + 1,
+                        path.display()
+                    );
+                }
             }
-            .with_context(|| {
-                format!(
-                    "Failed to parse keybind on line {} in {}",
-                    line_num + 1,
-                    path.display()
-                )
-            })?;
+
+            let keybinds = keyboard_mode
+                .parse_keybinds(parts[0], notation)
+                .and_then(|keybinds| {
+                    if placeholders && keyboard_mode == KeyboardMode::Command {
+                        check_placeholders(parts[0].trim())?;
+                    }
+                    Ok(keybinds)
+                })
+                .with_context(|| {
+                    format!(
+                        "Failed to parse keybind on line {} in {}",
+                        line_num + 1,
+                        path.display()
+                    )
+                })?;
 
             cards.push(Card {
-                keybind,
-                description: parts[1].to_string(),
+                keybinds,
+                description: parts[1].trim().to_string(),
+                id,
+                reset,
+                section: section.clone(),
+                tags,
+                reverse,
             });
         }
 
@@ -112,10 +303,26 @@ impl Deck {
             name,
             cards,
             keyboard_mode,
+            display_notation,
+            shell_match,
+            placeholders,
+            daily_limits,
         })
     }
 }
 
+/// Parse the value of a `# new-per-day:` or `# reviews-per-day:` directive
+fn parse_daily_limit(value: &str, line_num: usize, path: &Path) -> Result<usize> {
+    value.trim().parse().map_err(|_| {
+        anyhow::anyhow!(
+            "Invalid daily limit '{}' on line {} in {}. Use a number of cards, or 0 for no limit.",
+            value.trim(),
+            line_num + 1,
+            path.display()
+        )
+    })
+}
+
 /// List available deck files in a directory
 pub fn list_decks(dir: &Path) -> Result<Vec<PathBuf>> {
     if !dir.exists() {
@@ -149,13 +356,214 @@ mod tests {
         writeln!(file, "Ctrl+S\tSave file").unwrap();
         writeln!(file, "g g\tGo to top").unwrap();
         writeln!(file, "# This is a comment").unwrap();
-        writeln!(file, "").unwrap();
+        writeln!(file).unwrap();
         writeln!(file, "Ctrl+K Ctrl+C\tComment selection").unwrap();
 
         let deck = Deck::load(file.path()).unwrap();
         assert_eq!(deck.cards.len(), 3);
         assert_eq!(deck.cards[0].description, "Save file");
-        assert_eq!(deck.cards[1].keybind.len(), 2);
-        assert_eq!(deck.cards[2].keybind.len(), 2);
+        assert_eq!(deck.cards[1].keybinds[0].len(), 2);
+        assert_eq!(deck.cards[2].keybinds[0].len(), 2);
+        assert!(deck.cards.iter().all(|c| c.id.is_none() && !c.reset));
+        assert!(
+            deck.cards
+                .iter()
+                .all(|c| c.section.is_none() && c.tags.is_empty())
+        );
+    }
+
+    #[test]
+    fn test_load_deck_id_and_reset_columns() {
+        let mut file = NamedTempFile::with_suffix(".tsv").unwrap();
+        writeln!(file, "Ctrl+S\tSave file\tid:save").unwrap();
+        writeln!(file, "Ctrl+Z\tUndo\treset").unwrap();
+        writeln!(file, "Ctrl+Y\tRedo\tid:redo\treset").unwrap();
+
+        let deck = Deck::load(file.path()).unwrap();
+        assert_eq!(deck.cards[0].id.as_deref(), Some("save"));
+        assert!(!deck.cards[0].reset);
+        assert_eq!(deck.cards[1].id, None);
+        assert!(deck.cards[1].reset);
+        assert_eq!(deck.cards[2].id.as_deref(), Some("redo"));
+        assert!(deck.cards[2].reset);
+    }
+
+    #[test]
+    fn test_load_deck_sections_and_tags() {
+        let mut file = NamedTempFile::with_suffix(".tsv").unwrap();
+        writeln!(file, "Ctrl+S\tSave file").unwrap();
+        writeln!(file, "# section: Navigation").unwrap();
+        writeln!(file, "g g\tGo to top\tmotion, jump").unwrap();
+        writeln!(file, "# Not a section, just a comment").unwrap();
+        writeln!(file, "w\tNext word\tmotion word motion\tid:word").unwrap();
+        writeln!(file, "# section: Editing").unwrap();
+        writeln!(file, "d d\tDelete line\treset").unwrap();
+
+        let deck = Deck::load(file.path()).unwrap();
+        assert_eq!(deck.cards[0].section, None);
+        assert_eq!(deck.cards[1].section.as_deref(), Some("Navigation"));
+        assert_eq!(deck.cards[1].tags, vec!["motion", "jump"]);
+        assert_eq!(deck.cards[2].section.as_deref(), Some("Navigation"));
+        assert_eq!(deck.cards[2].tags, vec!["motion", "word"]);
+        assert_eq!(deck.cards[2].id.as_deref(), Some("word"));
+        assert_eq!(deck.cards[3].section.as_deref(), Some("Editing"));
+        assert!(deck.cards[3].tags.is_empty());
+        assert!(deck.cards[3].reset);
+    }
+
+    #[test]
+    fn test_load_deck_tag_named_like_column() {
+        for tags in ["motion reset", "reverse, motion", "motion id:word"] {
+            let mut file = NamedTempFile::with_suffix(".tsv").unwrap();
+            writeln!(file, "Ctrl+S\tSave file").unwrap();
+            writeln!(file, "w\tNext word\t{}", tags).unwrap();
+            let err = Deck::load(file.path()).unwrap_err().to_string();
+            assert!(err.contains("line 2"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_load_deck_reverse() {
+        let mut file = NamedTempFile::with_suffix(".tsv").unwrap();
+        writeln!(file, "Ctrl+S\tSave file\treverse").unwrap();
+        writeln!(file, "Ctrl+Z\tUndo").unwrap();
+        writeln!(file, "# reverse: on").unwrap();
+        writeln!(file, "Ctrl+Y\tRedo").unwrap();
+        writeln!(file, "# reverse: off").unwrap();
+        writeln!(file, "Ctrl+X\tCut").unwrap();
+
+        let deck = Deck::load(file.path()).unwrap();
+        let reverse: Vec<bool> = deck.cards.iter().map(|c| c.reverse).collect();
+        assert_eq!(reverse, vec![true, false, true, false]);
+        assert!(deck.cards[0].tags.is_empty());
+    }
+
+    #[test]
+    fn test_load_deck_alternatives() {
+        let mut file = NamedTempFile::with_suffix(".tsv").unwrap();
+        writeln!(file, "Ctrl+Y | Ctrl+Shift+Z\tRedo").unwrap();
+        writeln!(file, "# mode: command").unwrap();
+        writeln!(file, "ls | wc -l\tCount files").unwrap();
+
+        let deck = Deck::load(file.path()).unwrap();
+        assert_eq!(deck.cards[0].keybinds.len(), 2);
+        assert_eq!(
+            KeyboardMode::Raw.format_keybinds(&deck.cards[0].keybinds),
+            "Ctrl+Y | Ctrl+Shift+Z"
+        );
+        assert_eq!(deck.cards[1].keybinds.len(), 1);
+        assert_eq!(deck.cards[1].keybinds[0].as_command_string(), "ls | wc -l");
+        assert!(!deck.shell_match);
+
+        let mut file = NamedTempFile::with_suffix(".tsv").unwrap();
+        writeln!(file, "# mode: command").unwrap();
+        writeln!(file, "# match: shell").unwrap();
+        writeln!(file, "ls -la\tList files").unwrap();
+        assert!(Deck::load(file.path()).unwrap().shell_match);
+    }
+
+    #[test]
+    fn test_load_placeholders_directive() {
+        let mut file = NamedTempFile::with_suffix(".tsv").unwrap();
+        writeln!(file, "# mode: command").unwrap();
+        writeln!(file, "echo {{a}}{{b}}\tLiteral braces").unwrap();
+        let deck = Deck::load(file.path()).unwrap();
+        assert!(!deck.placeholders);
+
+        writeln!(file, "# placeholders: on").unwrap();
+        writeln!(file, "git checkout -b {{branch}}\tNew branch").unwrap();
+        let deck = Deck::load(file.path()).unwrap();
+        assert!(deck.placeholders);
+        assert_eq!(deck.cards.len(), 2);
+
+        // Placeholders that run together can't be matched
+        writeln!(file, "echo {{a}}{{b}}\tTwo placeholders").unwrap();
+        assert!(Deck::load(file.path()).is_err());
+    }
+
+    #[test]
+    fn test_keybinds_round_trip() {
+        let mut file = NamedTempFile::with_suffix(".tsv").unwrap();
+        // tmux binds both `|` and `\` to split-window -h
+        writeln!(file, "Ctrl+b Pipe | Ctrl+b \\\tSplit horizontally").unwrap();
+        writeln!(file, "|\tPipe key").unwrap();
+        writeln!(file, "Ctrl+| | g\tPipe with Ctrl").unwrap();
+        writeln!(file, "Ctrl+Plus | Plus | C-+ | ⌘+\tZoom in").unwrap();
+
+        let deck = Deck::load(file.path()).unwrap();
+        assert_eq!(
+            KeyboardMode::Raw.format_keybinds(&deck.cards[0].keybinds),
+            "Ctrl+B Pipe | Ctrl+B \\"
+        );
+        assert_eq!(
+            KeyboardMode::Raw.format_keybinds(&deck.cards[3].keybinds),
+            "Ctrl+Plus | Plus | Ctrl+Plus | Super+Plus"
+        );
+        for card in &deck.cards {
+            let stored = KeyboardMode::Raw.format_keybinds(&card.keybinds);
+            let parsed = KeyboardMode::Raw
+                .parse_keybinds(&stored, Some(Notation::Standard))
+                .unwrap();
+            assert_eq!(parsed, card.keybinds, "{}", stored);
+        }
+    }
+
+    #[test]
+    fn test_load_deck_notation() {
+        let mut file = NamedTempFile::with_suffix(".tsv").unwrap();
+        writeln!(file, "# mode: chars").unwrap();
+        writeln!(file, "C-x C-s | <C-s>\tSave file").unwrap();
+        writeln!(file, "# notation: vim").unwrap();
+        writeln!(file, "gg\tGo to top").unwrap();
+        writeln!(file, "# notation: auto").unwrap();
+        writeln!(file, "⌘⇧P\tCommand palette").unwrap();
+
+        let deck = Deck::load(file.path()).unwrap();
+        assert_eq!(
+            KeyboardMode::Chars.format_keybinds(&deck.cards[0].keybinds),
+            "Ctrl+X Ctrl+S | Ctrl+S"
+        );
+        assert_eq!(deck.cards[1].keybinds[0].to_string(), "g g");
+        assert_eq!(deck.cards[2].keybinds[0].to_string(), "Shift+Super+P");
+        assert_eq!(deck.display_notation, None);
+
+        let mut file = NamedTempFile::with_suffix(".tsv").unwrap();
+        writeln!(file, "# display: emacs").unwrap();
+        writeln!(file, "Ctrl+S\tSave file").unwrap();
+        let deck = Deck::load(file.path()).unwrap();
+        assert_eq!(deck.display_notation, Some(Notation::Emacs));
+
+        let mut file = NamedTempFile::with_suffix(".tsv").unwrap();
+        writeln!(file, "# notation: klingon").unwrap();
+        assert!(Deck::load(file.path()).is_err());
+    }
+
+    #[test]
+    fn test_load_deck_daily_limits() {
+        let mut file = NamedTempFile::with_suffix(".tsv").unwrap();
+        writeln!(file, "# new-per-day: 5").unwrap();
+        writeln!(file, "# reviews-per-day: 50").unwrap();
+        writeln!(file, "Ctrl+S\tSave file").unwrap();
+        let deck = Deck::load(file.path()).unwrap();
+        assert_eq!(
+            deck.daily_limits,
+            DailyLimits {
+                new_cards: 5,
+                reviews: 50
+            }
+        );
+
+        let mut file = NamedTempFile::with_suffix(".tsv").unwrap();
+        writeln!(file, "# new-per-day: lots").unwrap();
+        assert!(Deck::load(file.path()).is_err());
+    }
+
+    #[test]
+    fn test_load_deck_duplicate_id() {
+        let mut file = NamedTempFile::with_suffix(".tsv").unwrap();
+        writeln!(file, "Ctrl+S\tSave file\tid:save").unwrap();
+        writeln!(file, "Ctrl+Shift+S\tSave as\tid:save").unwrap();
+
+        assert!(Deck::load(file.path()).is_err());
     }
 }
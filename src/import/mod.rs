@@ -0,0 +1,374 @@
+mod history;
+mod tmux;
+mod vim;
+mod vscode;
+mod wm;
+
+use crate::config::Config;
+use crate::deck::KeyboardMode;
+use crate::keybind::{ALTERNATIVE_SEPARATOR, Keybind};
+use anyhow::{Context, Result, bail};
+use crossterm::event::KeyCode;
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Where `kbsr import` reads keybindings from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSource {
+    /// VS Code `keybindings.json` (or the default keymap dump)
+    Vscode,
+    /// Saved `tmux list-keys` output, one deck per key table
+    Tmux,
+    /// Hyprland config (`bind = SUPER, Q, killactive`)
+    Hyprland,
+    /// Sway config (`bindsym $mod+q kill`)
+    Sway,
+    /// i3 config, which uses the same syntax as Sway
+    I3,
+    /// Vim/Neovim `:verbose map` output or an `nvim_get_keymap()` JSON dump
+    Vim,
+    /// Shell history (zsh, bash, fish) and git aliases, as a command-mode deck
+    History,
+}
+
+impl ImportSource {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "vscode" | "code" => Ok(ImportSource::Vscode),
+            "tmux" => Ok(ImportSource::Tmux),
+            "hyprland" | "hypr" => Ok(ImportSource::Hyprland),
+            "sway" => Ok(ImportSource::Sway),
+            "i3" => Ok(ImportSource::I3),
+            "vim" | "nvim" | "neovim" => Ok(ImportSource::Vim),
+            "history" | "shell" => Ok(ImportSource::History),
+            other => bail!(
+                "Unknown import source '{}'. Use 'vscode', 'tmux', 'hyprland', 'sway', 'i3', 'vim' or 'history'.",
+                other
+            ),
+        }
+    }
+
+    /// Deck name used when `--name` isn't given
+    fn default_deck_name(&self) -> &'static str {
+        match self {
+            ImportSource::Vscode => "vscode",
+            ImportSource::Tmux => "tmux",
+            ImportSource::Hyprland => "hyprland",
+            ImportSource::Sway => "sway",
+            ImportSource::I3 => "i3",
+            ImportSource::Vim => "vim",
+            ImportSource::History => "commands",
+        }
+    }
+
+    /// Human-readable name for the deck file header
+    fn label(&self) -> &'static str {
+        match self {
+            ImportSource::Vscode => "VS Code keybindings",
+            ImportSource::Tmux => "tmux list-keys",
+            ImportSource::Hyprland => "Hyprland config",
+            ImportSource::Sway => "Sway config",
+            ImportSource::I3 => "i3 config",
+            ImportSource::Vim => "Vim mappings",
+            ImportSource::History => "shell history and git aliases",
+        }
+    }
+}
+
+/// Arguments to `kbsr import`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportOptions {
+    pub source: ImportSource,
+    /// Files to read, in order; empty (or `-`) reads stdin
+    pub paths: Vec<String>,
+    /// Deck name, defaulting to the source's name
+    pub name: Option<String>,
+    /// Overwrite an existing deck file
+    pub force: bool,
+    /// tmux prefix key (e.g. `C-a`), when it can't be read from the bindings
+    pub prefix: Option<String>,
+    /// Vim leader key for `<leader>` (e.g. `<Space>`), defaulting to `\`
+    pub leader: Option<String>,
+    /// Most history commands to keep
+    pub limit: Option<usize>,
+}
+
+impl ImportOptions {
+    pub fn new(source: ImportSource) -> Self {
+        ImportOptions {
+            source,
+            paths: Vec::new(),
+            name: None,
+            force: false,
+            prefix: None,
+            leader: None,
+            limit: None,
+        }
+    }
+}
+
+/// A card produced by an importer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedCard {
+    pub keybinds: Vec<Keybind>,
+    pub description: String,
+}
+
+/// A deck produced by an importer, ready to be written as TSV
+#[derive(Debug, Clone)]
+pub struct ImportedDeck {
+    pub name: String,
+    pub keyboard_mode: KeyboardMode,
+    pub cards: Vec<ImportedCard>,
+    /// Bindings that couldn't be converted or were dropped
+    pub skipped: usize,
+}
+
+impl ImportedDeck {
+    pub fn new(name: impl Into<String>, keyboard_mode: KeyboardMode) -> Self {
+        ImportedDeck {
+            name: name.into(),
+            keyboard_mode,
+            cards: Vec::new(),
+            skipped: 0,
+        }
+    }
+
+    /// Add a binding. A keybind already in the deck is skipped (the first binding wins),
+    /// and bindings with the same description become alternatives on one card.
+    pub fn add(&mut self, keybind: Keybind, description: &str) {
+        if self.cards.iter().any(|c| c.keybinds.contains(&keybind)) {
+            self.skipped += 1;
+            return;
+        }
+        let description = clean_description(description);
+        match self.cards.iter_mut().find(|c| c.description == description) {
+            // Alternatives can't be written in command mode, where `|` is a pipe
+            Some(card) if self.keyboard_mode != KeyboardMode::Command => {
+                card.keybinds.push(keybind)
+            }
+            _ => self.cards.push(ImportedCard {
+                keybinds: vec![keybind],
+                description,
+            }),
+        }
+    }
+
+    /// Render as a deck file
+    fn to_tsv(&self, label: &str) -> String {
+        let mut out = format!(
+            "# Imported from {} by `kbsr import`\n# mode: {}\n",
+            label,
+            self.keyboard_mode.label()
+        );
+        for card in &self.cards {
+            let keybinds = match self.keyboard_mode {
+                KeyboardMode::Command => self.keyboard_mode.format_keybinds(&card.keybinds),
+                KeyboardMode::Raw | KeyboardMode::Chars => card
+                    .keybinds
+                    .iter()
+                    .map(format_keybind)
+                    .collect::<Vec<_>>()
+                    .join(&format!(" {} ", ALTERNATIVE_SEPARATOR)),
+            };
+            out.push_str(&format!("{}\t{}\n", keybinds, card.description));
+        }
+        out
+    }
+}
+
+/// Format a keybind for a deck file, naming `#` at the start of a line so it isn't
+/// read as a comment. `|` and `+` are already written as `Pipe` and `Plus`.
+fn format_keybind(keybind: &Keybind) -> String {
+    let s = keybind.to_string();
+    match keybind.0.first() {
+        Some(chord) if chord.0.code == KeyCode::Char('#') => {
+            let first = chord.to_string();
+            format!("{}Hash{}", &first[..first.len() - 1], &s[first.len()..])
+        }
+        _ => s,
+    }
+}
+
+/// Descriptions are a single TSV column: no tabs or newlines
+fn clean_description(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Deck names become file names in the decks directory, so they can't lead out of it
+fn check_deck_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        bail!(
+            "Invalid deck name '{}': it can't be empty or contain '/', '\\' or '..'",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Read each input in order, with a label for error messages. With `lossy`, input that
+/// isn't valid UTF-8 (such as zsh's "metafied" history) doesn't fail the read: the lines
+/// holding it are dropped instead.
+fn read_inputs(paths: &[String], lossy: bool) -> Result<Vec<(String, String)>> {
+    if paths.is_empty() {
+        return read_inputs(&["-".to_string()], lossy);
+    }
+
+    paths
+        .iter()
+        .map(|path| {
+            let (label, bytes) = if path == "-" {
+                let mut bytes = Vec::new();
+                std::io::stdin()
+                    .read_to_end(&mut bytes)
+                    .context("Failed to read stdin")?;
+                ("stdin".to_string(), bytes)
+            } else {
+                let bytes =
+                    std::fs::read(path).with_context(|| format!("Failed to read {}", path))?;
+                (path.clone(), bytes)
+            };
+            let content = if lossy {
+                valid_lines(&bytes)
+            } else {
+                String::from_utf8(bytes)
+                    .with_context(|| format!("Failed to read {}: not valid UTF-8", label))?
+            };
+            Ok((label, content))
+        })
+        .collect()
+}
+
+/// The lines of `bytes` that are valid UTF-8
+fn valid_lines(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .lines()
+        .filter(|line| !line.contains(char::REPLACEMENT_CHARACTER))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// Run `kbsr import`: convert the inputs and write deck files to the decks directory
+pub fn run(config: &Config, options: &ImportOptions) -> Result<()> {
+    // History files have well-known homes, so look there rather than at stdin
+    let paths = if options.paths.is_empty() && options.source == ImportSource::History {
+        let paths: Vec<String> = history::default_paths()
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        if paths.is_empty() {
+            bail!("No shell history or git config found. Pass the files to read.");
+        }
+        println!("Reading {}", paths.join(", "));
+        paths
+    } else {
+        options.paths.clone()
+    };
+    let name = options
+        .name
+        .clone()
+        .unwrap_or_else(|| options.source.default_deck_name().to_string());
+    check_deck_name(&name)?;
+    let inputs = read_inputs(&paths, options.source == ImportSource::History)?;
+
+    let decks = match options.source {
+        ImportSource::Vscode => vec![vscode::import(&name, &inputs)?],
+        ImportSource::Tmux => tmux::import(&name, &inputs, options.prefix.as_deref())?,
+        ImportSource::Hyprland => wm::import_hyprland(&name, &inputs)?,
+        ImportSource::Sway | ImportSource::I3 => wm::import_sway(&name, &inputs)?,
+        ImportSource::Vim => vec![vim::import(&name, &inputs, options.leader.as_deref())?],
+        ImportSource::History => vec![history::import(
+            &name,
+            &inputs,
+            options.limit.unwrap_or(history::DEFAULT_LIMIT),
+        )?],
+    };
+
+    let (decks, empty): (Vec<ImportedDeck>, Vec<ImportedDeck>) =
+        decks.into_iter().partition(|d| !d.cards.is_empty());
+    for deck in &empty {
+        println!(
+            "No bindings to import for deck '{}' ({} skipped)",
+            deck.name, deck.skipped
+        );
+    }
+
+    // Check every file first, so a clash doesn't leave a partial import
+    for deck in &decks {
+        check_deck_name(&deck.name)?;
+    }
+    let paths: Vec<PathBuf> = decks
+        .iter()
+        .map(|deck| config.decks_dir.join(format!("{}.tsv", deck.name)))
+        .collect();
+    if !options.force
+        && let Some(path) = paths.iter().find(|p| p.exists())
+    {
+        bail!(
+            "{} already exists. Use --force to overwrite it, or --name to pick another deck name.",
+            path.display()
+        );
+    }
+
+    config.ensure_dirs()?;
+    for (deck, path) in decks.iter().zip(&paths) {
+        std::fs::write(path, deck.to_tsv(options.source.label()))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        print!(
+            "Imported {} cards into {}",
+            deck.cards.len(),
+            path.display()
+        );
+        if deck.skipped > 0 {
+            print!(" ({} bindings skipped)", deck.skipped);
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_imported_deck_to_tsv() {
+        let mut deck = ImportedDeck::new("test", KeyboardMode::Raw);
+        deck.add(Keybind::parse("Ctrl+Y").unwrap(), "Redo\tlast\nchange");
+        deck.add(Keybind::parse("Ctrl+S").unwrap(), "Save");
+        deck.add(Keybind::parse("Ctrl+Shift+Z").unwrap(), "Redo last change");
+        deck.add(Keybind::parse("Ctrl+S").unwrap(), "Save all");
+        deck.add(Keybind::parse("# x").unwrap(), "Hash");
+        deck.add(Keybind::parse("g Ctrl+pipe plus").unwrap(), "Names");
+
+        assert_eq!(deck.skipped, 1);
+        assert_eq!(
+            deck.to_tsv("Test"),
+            "# Imported from Test by `kbsr import`\n# mode: raw\n\
+             Ctrl+Y | Ctrl+Shift+Z\tRedo last change\nCtrl+S\tSave\nHash x\tHash\ng Ctrl+Pipe Plus\tNames\n"
+        );
+    }
+
+    #[test]
+    fn test_check_deck_name() {
+        assert!(check_deck_name("vscode").is_ok());
+        assert!(check_deck_name("tmux-copy-mode-vi").is_ok());
+        assert!(check_deck_name("../../foo").is_err());
+        assert!(check_deck_name("decks/foo").is_err());
+        assert!(check_deck_name("..\\foo").is_err());
+        assert!(check_deck_name("").is_err());
+    }
+
+    #[test]
+    fn test_valid_lines() {
+        // zsh writes non-ASCII history bytes "metafied", which isn't UTF-8
+        let history = b": 1700000000:0;ls -la\n: 1700000001:0;echo \x83\xa9t\xe9\n: 1700000002:0;git status\n";
+        assert_eq!(
+            valid_lines(history),
+            ": 1700000000:0;ls -la\n: 1700000002:0;git status\n"
+        );
+        assert_eq!(valid_lines("echo héllo".as_bytes()), "echo héllo\n");
+    }
+}
@@ -0,0 +1,322 @@
+use super::ImportedDeck;
+use crate::deck::KeyboardMode;
+use crate::keybind::{Chord, Keybind, char_chord};
+use anyhow::{Result, bail};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// tmux's default prefix
+const DEFAULT_PREFIX: &str = "C-b";
+
+/// A `bind-key` line from `tmux list-keys`
+#[derive(Debug, PartialEq, Eq)]
+struct Binding {
+    table: String,
+    key: String,
+    note: Option<String>,
+    command: String,
+}
+
+/// Import `tmux list-keys` output as one chars-mode deck per key table.
+/// The prefix table becomes `name` and its keys are preceded by the prefix;
+/// other tables become `name-table` (e.g. `tmux-copy-mode-vi`).
+pub fn import(
+    name: &str,
+    inputs: &[(String, String)],
+    prefix: Option<&str>,
+) -> Result<Vec<ImportedDeck>> {
+    let bindings: Vec<Binding> = inputs
+        .iter()
+        .flat_map(|(_, content)| content.lines())
+        .filter_map(parse_line)
+        .collect();
+
+    // The key bound to `send-prefix` is the prefix, unless given explicitly
+    let prefix = match prefix {
+        Some(prefix) => prefix.to_string(),
+        None => bindings
+            .iter()
+            .find(|b| b.table == "prefix" && b.command == "send-prefix")
+            .map(|b| b.key.clone())
+            .unwrap_or_else(|| DEFAULT_PREFIX.to_string()),
+    };
+    let Some(prefix_chord) = parse_key(&prefix) else {
+        bail!("Unsupported tmux prefix key '{}'", prefix);
+    };
+
+    let mut decks: Vec<(String, ImportedDeck)> = Vec::new();
+    for binding in &bindings {
+        let index = match decks.iter().position(|(table, _)| *table == binding.table) {
+            Some(index) => index,
+            None => {
+                let deck_name = if binding.table == "prefix" {
+                    name.to_string()
+                } else {
+                    format!("{}-{}", name, binding.table)
+                };
+                decks.push((
+                    binding.table.clone(),
+                    ImportedDeck::new(deck_name, KeyboardMode::Chars),
+                ));
+                decks.len() - 1
+            }
+        };
+        let deck = &mut decks[index].1;
+
+        let Some(chord) = parse_key(&binding.key) else {
+            deck.skipped += 1;
+            continue;
+        };
+        let chords = if binding.table == "prefix" {
+            vec![prefix_chord.clone(), chord]
+        } else {
+            vec![chord]
+        };
+        let description = binding.note.as_deref().unwrap_or(&binding.command);
+        deck.add(Keybind(chords), description);
+    }
+
+    if decks.is_empty() {
+        bail!("No tmux bindings found. Save the output of `tmux list-keys` and import that.");
+    }
+
+    Ok(decks.into_iter().map(|(_, deck)| deck).collect())
+}
+
+/// Parse `bind-key [-nr] [-N note] [-T table] key command...`
+fn parse_line(line: &str) -> Option<Binding> {
+    let words = split_words(line);
+    let (first, _) = words.first()?;
+    if first != "bind-key" && first != "bind" {
+        return None;
+    }
+
+    let mut table = "prefix".to_string();
+    let mut note = None;
+    let mut rest = words[1..].iter();
+    let (key, key_end) = loop {
+        let (word, end) = rest.next()?;
+        match word.as_str() {
+            "-T" => table = rest.next()?.0.clone(),
+            "-N" => note = Some(rest.next()?.0.clone()),
+            flag if flag.starts_with('-') && flag.len() > 1 => {
+                if flag.contains('n') {
+                    table = "root".to_string();
+                }
+            }
+            _ => break (word.clone(), *end),
+        }
+    };
+
+    let command = line[key_end..].trim().to_string();
+    if command.is_empty() {
+        return None;
+    }
+    Some(Binding {
+        table,
+        key,
+        note,
+        command,
+    })
+}
+
+/// Split a line into words as tmux quotes them, with the byte offset where each ends.
+/// Handles `'...'`, `"..."` and backslash escapes (`\;`, `\#`).
+fn split_words(line: &str) -> Vec<(String, usize)> {
+    let mut words = Vec::new();
+    let mut chars = line.char_indices().peekable();
+
+    while let Some(&(_, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut word = String::new();
+        let mut end = line.len();
+        let mut quote: Option<char> = None;
+        while let Some(&(i, c)) = chars.peek() {
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some('"') if c == '\\' => {
+                    chars.next();
+                    if let Some(&(_, escaped)) = chars.peek() {
+                        word.push(escaped);
+                    }
+                }
+                Some(_) => word.push(c),
+                None if c.is_whitespace() => {
+                    end = i;
+                    break;
+                }
+                None if c == '\'' || c == '"' => quote = Some(c),
+                None if c == '\\' => {
+                    chars.next();
+                    if let Some(&(_, escaped)) = chars.peek() {
+                        word.push(escaped);
+                    }
+                }
+                None => word.push(c),
+            }
+            chars.next();
+        }
+        words.push((word, end));
+    }
+
+    words
+}
+
+/// Convert a tmux key like `C-b`, `M-Up`, `%` or `NPage`. Mouse keys aren't supported.
+fn parse_key(key: &str) -> Option<Chord> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = key;
+    loop {
+        let modifier = match rest.get(..2) {
+            Some("C-") => KeyModifiers::CONTROL,
+            Some("M-") => KeyModifiers::ALT,
+            Some("S-") => KeyModifiers::SHIFT,
+            _ => break,
+        };
+        if rest.len() == 2 {
+            break;
+        }
+        modifiers |= modifier;
+        rest = &rest[2..];
+    }
+
+    let code = match rest {
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "NPage" | "PageDown" | "PgDn" => KeyCode::PageDown,
+        "PPage" | "PageUp" | "PgUp" => KeyCode::PageUp,
+        "IC" | "Insert" => KeyCode::Insert,
+        "DC" | "Delete" => KeyCode::Delete,
+        "BSpace" => KeyCode::Backspace,
+        "Enter" => KeyCode::Enter,
+        "Escape" => KeyCode::Esc,
+        "Tab" => KeyCode::Tab,
+        "BTab" => KeyCode::BackTab,
+        "Space" => KeyCode::Char(' '),
+        k if k.len() > 1 && k.starts_with('F') && k[1..].chars().all(|c| c.is_ascii_digit()) => {
+            KeyCode::F(k[1..].parse().ok()?)
+        }
+        k if k.chars().count() == 1 => return Some(char_chord(k.chars().next()?, modifiers)),
+        _ => return None,
+    };
+
+    Some(Chord(KeyEvent::new(code, modifiers)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST_KEYS: &str = r#"bind-key    -T copy-mode-vi C-b               send-keys -X page-up
+bind-key    -T copy-mode-vi j                 send-keys -X cursor-down
+bind-key    -T copy-mode-vi Down              send-keys -X cursor-down
+bind-key    -T copy-mode-vi MouseDrag1Pane    select-pane \; send-keys -X begin-selection
+bind-key    -T prefix       C-a               send-prefix
+bind-key    -T prefix       '"'               split-window
+bind-key -N "Split window horizontally" -T prefix       %                 split-window -h
+bind-key    -T prefix       \;                last-pane
+bind-key -r -T prefix       M-Up              resize-pane -U 5
+bind-key    -T root         C-S-Left          swap-window -t -1
+"#;
+
+    fn cards(deck: &ImportedDeck) -> Vec<(String, &str)> {
+        deck.cards
+            .iter()
+            .map(|c| {
+                (
+                    KeyboardMode::Chars.format_keybinds(&c.keybinds),
+                    c.description.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line(r##"bind-key -N "Rename \"window\"" -T prefix , command-prompt -I "#W""##),
+            Some(Binding {
+                table: "prefix".to_string(),
+                key: ",".to_string(),
+                note: Some("Rename \"window\"".to_string()),
+                command: r##"command-prompt -I "#W""##.to_string(),
+            })
+        );
+        assert_eq!(
+            parse_line("bind -n M-h select-pane -L").map(|b| b.table),
+            Some("root".to_string())
+        );
+        assert_eq!(parse_line("set -g prefix C-a"), None);
+    }
+
+    #[test]
+    fn test_import_list_keys() {
+        let decks = import("tmux", &[("test".to_string(), LIST_KEYS.to_string())], None).unwrap();
+        let names: Vec<&str> = decks.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["tmux-copy-mode-vi", "tmux", "tmux-root"]);
+
+        assert_eq!(
+            cards(&decks[0]),
+            vec![
+                ("Ctrl+B".to_string(), "send-keys -X page-up"),
+                ("j | Down".to_string(), "send-keys -X cursor-down"),
+            ]
+        );
+        assert_eq!(decks[0].skipped, 1);
+
+        // The prefix comes from the send-prefix binding
+        assert_eq!(
+            cards(&decks[1]),
+            vec![
+                ("Ctrl+A Ctrl+A".to_string(), "send-prefix"),
+                ("Ctrl+A \"".to_string(), "split-window"),
+                ("Ctrl+A %".to_string(), "Split window horizontally"),
+                ("Ctrl+A ;".to_string(), "last-pane"),
+                ("Ctrl+A Alt+Up".to_string(), "resize-pane -U 5"),
+            ]
+        );
+        assert_eq!(
+            cards(&decks[2]),
+            vec![("Ctrl+Shift+Left".to_string(), "swap-window -t -1")]
+        );
+    }
+
+    #[test]
+    fn test_import_pipe_key() {
+        let input = (
+            "test".to_string(),
+            "bind-key -T prefix | split-window -h\nbind-key -T prefix \\\\ split-window -h\n"
+                .to_string(),
+        );
+        let decks = import("tmux", &[input], None).unwrap();
+        let keybinds = cards(&decks[0])[0].0.clone();
+        assert_eq!(keybinds, "Ctrl+B Pipe | Ctrl+B \\");
+        assert_eq!(
+            KeyboardMode::Chars
+                .parse_keybinds(&keybinds, None)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_import_prefix_option() {
+        let input = (
+            "test".to_string(),
+            "bind-key -T prefix c new-window\n".to_string(),
+        );
+        let decks = import("tmux", std::slice::from_ref(&input), None).unwrap();
+        assert_eq!(cards(&decks[0])[0].0, "Ctrl+B c");
+
+        let decks = import("tmux", &[input], Some("M-Space")).unwrap();
+        assert_eq!(cards(&decks[0])[0].0, "Alt+Space c");
+    }
+}
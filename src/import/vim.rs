@@ -0,0 +1,302 @@
+use super::ImportedDeck;
+use crate::deck::KeyboardMode;
+use crate::keybind::char_chord;
+use crate::keybind::{Chord, Keybind};
+use crate::notation::parse_vim_key;
+use anyhow::{Context, Result, bail};
+use crossterm::event::KeyModifiers;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Vim's default `mapleader`
+const DEFAULT_LEADER: &str = "\\";
+
+/// A mapping read from `:map` output or `nvim_get_keymap()`
+#[derive(Debug, Deserialize)]
+struct Mapping {
+    #[serde(default)]
+    mode: String,
+    lhs: String,
+    #[serde(default)]
+    rhs: String,
+    #[serde(default)]
+    desc: Option<String>,
+}
+
+/// `nvim_get_keymap()` dumps: a list of mappings, or a table of lists keyed by mode
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeymapDump {
+    List(Vec<Mapping>),
+    ByMode(HashMap<String, Vec<Mapping>>),
+}
+
+/// Import Vim/Neovim mappings from `:verbose map` output or a JSON dump of
+/// `nvim_get_keymap()`, as a chars-mode deck. Normal mode mappings come first;
+/// a key sequence mapped in several modes is kept once.
+pub fn import(
+    name: &str,
+    inputs: &[(String, String)],
+    leader: Option<&str>,
+) -> Result<ImportedDeck> {
+    let leader = parse_keys(leader.unwrap_or(DEFAULT_LEADER), &[])
+        .filter(|chords| !chords.is_empty())
+        .context("Unsupported leader key")?;
+
+    let mut mappings: Vec<Mapping> = Vec::new();
+    for (label, content) in inputs {
+        let trimmed = content.trim_start();
+        if trimmed.starts_with('[') || trimmed.starts_with('{') {
+            let dump: KeymapDump = serde_json::from_str(trimmed)
+                .with_context(|| format!("Failed to parse keymap JSON in {}", label))?;
+            match dump {
+                KeymapDump::List(list) => mappings.extend(list),
+                KeymapDump::ByMode(by_mode) => {
+                    let mut by_mode: Vec<_> = by_mode.into_iter().collect();
+                    by_mode.sort_by(|a, b| a.0.cmp(&b.0));
+                    for (mode, list) in by_mode {
+                        mappings.extend(list.into_iter().map(|m| Mapping {
+                            mode: if m.mode.is_empty() {
+                                mode.clone()
+                            } else {
+                                m.mode
+                            },
+                            ..m
+                        }));
+                    }
+                }
+            }
+        } else {
+            mappings.extend(parse_map_output(content));
+        }
+    }
+    if mappings.is_empty() {
+        bail!("No mappings found. Save the output of `:verbose map` or `nvim_get_keymap()`.");
+    }
+
+    mappings.sort_by_key(|m| mode_order(&m.mode));
+
+    let mut deck = ImportedDeck::new(name, KeyboardMode::Chars);
+    for mapping in &mappings {
+        let description = mapping
+            .desc
+            .as_deref()
+            .filter(|d| !d.trim().is_empty())
+            .map(str::to_string)
+            .or_else(|| describe_rhs(&mapping.rhs));
+        match (parse_keys(&mapping.lhs, &leader), description) {
+            (Some(chords), Some(description)) if !chords.is_empty() => {
+                deck.add(Keybind(chords), &description)
+            }
+            _ => deck.skipped += 1,
+        }
+    }
+
+    Ok(deck)
+}
+
+/// Normal mode first, then the others in the order `:map` shows them
+fn mode_order(mode: &str) -> usize {
+    const ORDER: &[&str] = &["n", "", "v", "x", "s", "o", "i", "!", "c", "t", "l"];
+    let mode = mode.trim();
+    let first = mode.get(..1).unwrap_or_default();
+    ORDER
+        .iter()
+        .position(|m| *m == mode || *m == first)
+        .unwrap_or(ORDER.len())
+}
+
+/// Parse `:map` / `:verbose map` output:
+///
+/// ```text
+/// n  <Space>ff   * <Cmd>Telescope find_files<CR>
+///                  Find files
+///         Last set from ~/.config/nvim/init.lua line 10
+/// ```
+///
+/// The first three columns are the mode. Indented lines that follow are
+/// Neovim's `desc` or Vim's "Last set from" note.
+fn parse_map_output(content: &str) -> Vec<Mapping> {
+    let mut mappings: Vec<Mapping> = Vec::new();
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (mode, rest) = line.split_at(line.char_indices().nth(3).map_or(line.len(), |(i, _)| i));
+
+        if rest.starts_with(char::is_whitespace) || !mode.chars().all(is_mode_char) {
+            // Continuation line: a description, unless it's where the mapping was set
+            let text = line.trim();
+            if let Some(last) = mappings.last_mut()
+                && !text.starts_with("Last set from")
+                && last.desc.is_none()
+            {
+                last.desc = Some(text.to_string());
+            }
+            continue;
+        }
+
+        let Some((lhs, rhs)) = rest.split_once(char::is_whitespace) else {
+            continue;
+        };
+        // Flags before the rhs: * (noremap), & (script-local), @ (buffer-local)
+        let rhs = rhs.trim_start().trim_start_matches(['*', '&', '@']).trim();
+        mappings.push(Mapping {
+            mode: mode.trim().to_string(),
+            lhs: lhs.to_string(),
+            rhs: rhs.to_string(),
+            desc: None,
+        });
+    }
+    mappings
+}
+
+fn is_mode_char(c: char) -> bool {
+    c.is_whitespace() || "nvxsoilct!".contains(c)
+}
+
+/// Describe a mapping by its rhs: `<Cmd>Telescope find_files<CR>` → "Telescope find_files".
+/// Lua callbacks have nothing readable to show.
+fn describe_rhs(rhs: &str) -> Option<String> {
+    let rhs = rhs.trim();
+    if rhs.is_empty() || rhs.starts_with("<Lua") || rhs.eq_ignore_ascii_case("<Nop>") {
+        return None;
+    }
+    let mut description = rhs;
+    for prefix in ["<Cmd>", "<cmd>", ":<C-U>", ":<C-u>", ":"] {
+        if let Some(rest) = description.strip_prefix(prefix) {
+            description = rest;
+            break;
+        }
+    }
+    for suffix in ["<CR>", "<cr>", "<Enter>"] {
+        if let Some(rest) = description.strip_suffix(suffix) {
+            description = rest;
+            break;
+        }
+    }
+    let description = description.trim();
+    (!description.is_empty()).then(|| description.to_string())
+}
+
+/// Convert Vim key notation (`<C-w>v`, `<leader>ff`, `<Space>`) into chords.
+/// Returns None for keys that can't be typed, such as `<Plug>` mappings and mouse buttons.
+fn parse_keys(lhs: &str, leader: &[Chord]) -> Option<Vec<Chord>> {
+    let mut chords = Vec::new();
+    let mut rest = lhs;
+    while let Some(c) = rest.chars().next() {
+        if c == '<'
+            && let Some(end) = rest.find('>')
+            && end > 1
+        {
+            let name = &rest[1..end];
+            match name.to_lowercase().as_str() {
+                "leader" | "localleader" => chords.extend_from_slice(leader),
+                "plug" | "snr" | "sid" => return None,
+                _ => match parse_vim_key(name) {
+                    Some(chord) => chords.push(chord),
+                    // Mouse and keypad keys like `<LeftMouse>` and `<kPlus>` can't be studied
+                    None if name.chars().all(|c| c.is_alphanumeric() || c == '-') => {
+                        return None;
+                    }
+                    // Not notation after all: a literal `<`
+                    None => {
+                        chords.push(char_chord(c, KeyModifiers::NONE));
+                        rest = &rest[1..];
+                        continue;
+                    }
+                },
+            }
+            rest = &rest[end + 1..];
+        } else {
+            chords.push(char_chord(c, KeyModifiers::NONE));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    Some(chords)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(deck: &ImportedDeck) -> Vec<(String, &str)> {
+        deck.cards
+            .iter()
+            .map(|c| {
+                (
+                    KeyboardMode::Chars.format_keybinds(&c.keybinds),
+                    c.description.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    fn input(content: &str) -> Vec<(String, String)> {
+        vec![("test".to_string(), content.to_string())]
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        let leader = parse_keys(",", &[]).unwrap();
+        let keys = |lhs: &str| parse_keys(lhs, &leader).map(|chords| Keybind(chords).to_string());
+        assert_eq!(keys("<C-W>v").as_deref(), Some("Ctrl+W v"));
+        assert_eq!(keys("<leader>ff").as_deref(), Some(", f f"));
+        assert_eq!(keys("<Space>g<CR>").as_deref(), Some("Space g Enter"));
+        assert_eq!(keys("<M-S-x>").as_deref(), Some("Alt+Shift+x"));
+        assert_eq!(keys("<lt>a").as_deref(), Some("< a"));
+        assert_eq!(keys("<<").as_deref(), Some("< <"));
+        assert_eq!(keys("<Plug>(foo)"), None);
+        assert_eq!(keys("<LeftMouse>"), None);
+        assert_eq!(keys("<C-LeftMouse>"), None);
+        assert_eq!(keys("<ScrollWheelUp>"), None);
+        assert_eq!(keys("g<kPlus>"), None);
+    }
+
+    #[test]
+    fn test_import_map_output() {
+        let output = "
+n  <Space>ff   * <Cmd>Telescope find_files<CR>
+                 Find files
+\tLast set from ~/.config/nvim/init.lua line 10
+n  <C-H>       * <C-W>h
+\tLast set from ~/.vimrc line 3
+   <Space>w    * :w<CR>
+x  <Space>w    * :'<,'>w<CR>
+n  gx          * <Lua 42: vim/_defaults.lua:100>
+n  <Plug>(foo) * <Nop>
+";
+        let deck = import("vim", &input(output), None).unwrap();
+        assert_eq!(
+            cards(&deck),
+            vec![
+                ("Space f f".to_string(), "Find files"),
+                ("Ctrl+H".to_string(), "<C-W>h"),
+                ("Space w".to_string(), "w"),
+            ]
+        );
+        assert_eq!(deck.skipped, 3);
+    }
+
+    #[test]
+    fn test_import_keymap_json() {
+        let json = r#"{
+            "n": [
+                { "mode": "n", "lhs": "<leader>e", "rhs": "", "desc": "Explorer" },
+                { "mode": "n", "lhs": "<leader>q", "rhs": "<Cmd>q<CR>" }
+            ],
+            "v": [
+                { "lhs": "<leader>y", "rhs": "\"+y" }
+            ]
+        }"#;
+        let deck = import("nvim", &input(json), Some("<Space>")).unwrap();
+        assert_eq!(
+            cards(&deck),
+            vec![
+                ("Space e".to_string(), "Explorer"),
+                ("Space q".to_string(), "q"),
+                ("Space y".to_string(), "\"+y"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,351 @@
+use super::ImportedDeck;
+use crate::deck::KeyboardMode;
+use crate::keybind::{Chord, Keybind};
+use anyhow::{Context, Result, bail};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// One entry of a VS Code keybindings file
+#[derive(Debug, Deserialize)]
+struct Binding {
+    #[serde(default)]
+    key: String,
+    #[serde(default)]
+    command: String,
+}
+
+/// Import VS Code keybinding files, applied in order: later `-command` entries
+/// remove earlier bindings, so the default keymap dump can come first.
+/// A command bound to several keys becomes one card with alternatives.
+pub fn import(name: &str, inputs: &[(String, String)]) -> Result<ImportedDeck> {
+    let mut bindings: Vec<Binding> = Vec::new();
+    for (label, content) in inputs {
+        let entries: Vec<Binding> = serde_json::from_str(&strip_jsonc(content))
+            .with_context(|| format!("Failed to parse VS Code keybindings in {}", label))?;
+        for entry in entries {
+            if let Some(removed) = entry.command.strip_prefix('-') {
+                bindings.retain(|b| {
+                    b.command != removed || (!entry.key.is_empty() && b.key != entry.key)
+                });
+            } else if !entry.command.is_empty() && !entry.key.is_empty() {
+                bindings.push(entry);
+            }
+        }
+    }
+
+    let mut commands: Vec<&str> = Vec::new();
+    for binding in &bindings {
+        if !commands.contains(&binding.command.as_str()) {
+            commands.push(&binding.command);
+        }
+    }
+    let descriptions: HashMap<&str, String> = commands
+        .iter()
+        .copied()
+        .zip(describe_commands(&commands))
+        .collect();
+
+    let mut deck = ImportedDeck::new(name, KeyboardMode::Raw);
+    for binding in &bindings {
+        match parse_key(&binding.key) {
+            Ok(keybind) => deck.add(keybind, &descriptions[binding.command.as_str()]),
+            Err(_) => deck.skipped += 1,
+        }
+    }
+
+    Ok(deck)
+}
+
+/// Convert a VS Code key like `ctrl+k ctrl+c` or `shift+alt+down`
+fn parse_key(s: &str) -> Result<Keybind> {
+    let chords: Result<Vec<Chord>> = s.split_whitespace().map(parse_chord).collect();
+    let chords = chords?;
+    if chords.is_empty() {
+        bail!("Empty key");
+    }
+    Ok(Keybind(chords))
+}
+
+fn parse_chord(s: &str) -> Result<Chord> {
+    // Split on '+' but keep a trailing '+' as the key itself (e.g. `ctrl++`)
+    let (mods, key) = match s.strip_suffix("++") {
+        Some(mods) => (mods, "+"),
+        None => match s.rsplit_once('+') {
+            Some((mods, key)) => (mods, key),
+            None => ("", s),
+        },
+    };
+
+    let mut modifiers = KeyModifiers::NONE;
+    for part in mods.split('+').filter(|p| !p.is_empty()) {
+        modifiers |= match part.to_lowercase().as_str() {
+            "ctrl" => KeyModifiers::CONTROL,
+            "shift" => KeyModifiers::SHIFT,
+            "alt" => KeyModifiers::ALT,
+            "cmd" | "meta" | "win" | "super" => KeyModifiers::SUPER,
+            other => bail!("Unknown modifier: {}", other),
+        };
+    }
+
+    let code = match key.to_lowercase().as_str() {
+        "enter" => KeyCode::Enter,
+        "escape" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "space" => KeyCode::Char(' '),
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "capslock" => KeyCode::CapsLock,
+        "numlock" => KeyCode::NumLock,
+        "scrolllock" => KeyCode::ScrollLock,
+        "pausebreak" => KeyCode::Pause,
+        "contextmenu" => KeyCode::Menu,
+        "printscreen" => KeyCode::PrintScreen,
+        k if k.len() > 1 && k.starts_with('f') && k[1..].chars().all(|c| c.is_ascii_digit()) => {
+            KeyCode::F(k[1..].parse()?)
+        }
+        k if k.chars().count() == 1 => {
+            let c = k.chars().next().unwrap_or(' ');
+            // Match the deck style: Ctrl+K, not Ctrl+k
+            KeyCode::Char(if modifiers.is_empty() {
+                c
+            } else {
+                c.to_ascii_uppercase()
+            })
+        }
+        other => bail!("Unsupported key: {}", other),
+    };
+
+    Ok(Chord(KeyEvent::new(code, modifiers)))
+}
+
+/// Humanised titles for command ids, e.g. `editor.action.commentLine` → "Comment line".
+/// Titles that would repeat get the preceding id segment: "Toggle (terminal)".
+fn describe_commands(commands: &[&str]) -> Vec<String> {
+    let titles: Vec<String> = commands
+        .iter()
+        .map(|c| humanise(c.rsplit('.').next().unwrap_or(c)))
+        .collect();
+
+    titles
+        .iter()
+        .zip(commands)
+        .map(|(title, command)| {
+            if titles.iter().filter(|t| *t == title).count() == 1 {
+                return title.clone();
+            }
+            let context = command
+                .rsplit('.')
+                .skip(1)
+                .find(|segment| *segment != "action")
+                .map(|segment| humanise(segment).to_lowercase());
+            match context {
+                Some(context) => format!("{} ({})", title, context),
+                None => title.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Split a camelCase or snake_case identifier into a sentence
+fn humanise(id: &str) -> String {
+    let chars: Vec<char> = id.chars().collect();
+    let mut words: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' || c == '-' || c == ' ' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        // A new word starts at "aB" and at the last capital of "ABc" (HTMLPreview)
+        let prev = i.checked_sub(1).map(|j| chars[j]);
+        let next = chars.get(i + 1);
+        let boundary = c.is_uppercase()
+            && prev.is_some_and(|p| {
+                p.is_lowercase()
+                    || p.is_ascii_digit()
+                    || (p.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+            });
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    let sentence = words
+        .iter()
+        .map(|w| {
+            // Keep acronyms like "HTML" as they are
+            if w.chars().all(|c| c.is_uppercase()) && w.len() > 1 {
+                w.clone()
+            } else {
+                w.to_lowercase()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut chars = sentence.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => sentence,
+    }
+}
+
+/// Turn JSONC (comments, trailing commas) into plain JSON
+fn strip_jsonc(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            if c == '\\' {
+                if let Some(escaped) = chars.next() {
+                    out.push(escaped);
+                }
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            ']' | '}' => {
+                // Drop a trailing comma before the closing bracket
+                let trimmed = out.trim_end().len();
+                if out[..trimmed].ends_with(',') {
+                    out.truncate(trimmed - 1);
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(content: &str) -> (String, String) {
+        ("test".to_string(), content.to_string())
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(
+            parse_key("ctrl+k ctrl+c").unwrap().to_string(),
+            "Ctrl+K Ctrl+C"
+        );
+        assert_eq!(
+            parse_key("shift+alt+down").unwrap().to_string(),
+            "Alt+Shift+Down"
+        );
+        assert_eq!(
+            parse_key("cmd+shift+p").unwrap().to_string(),
+            "Shift+Super+P"
+        );
+        assert_eq!(parse_key("ctrl+`").unwrap().to_string(), "Ctrl+`");
+        assert_eq!(parse_key("f12").unwrap().to_string(), "F12");
+        assert!(parse_key("ctrl+numpad_add").is_err());
+    }
+
+    #[test]
+    fn test_humanise() {
+        assert_eq!(humanise("commentLine"), "Comment line");
+        assert_eq!(humanise("quickOpen"), "Quick open");
+        assert_eq!(humanise("toggleHTMLPreview"), "Toggle HTML preview");
+        assert_eq!(humanise("save_all"), "Save all");
+    }
+
+    #[test]
+    fn test_import_keybindings() {
+        let defaults = input(
+            r#"// Default keybindings
+            [
+                { "key": "ctrl+k ctrl+c", "command": "editor.action.addCommentLine", "when": "editorTextFocus" },
+                { "key": "ctrl+shift+p", "command": "workbench.action.showCommands" },
+                { "key": "f1", "command": "workbench.action.showCommands" },
+                { "key": "ctrl+`", "command": "workbench.action.terminal.toggleTerminal" },
+                { "key": "ctrl+b", "command": "workbench.action.toggleSidebarVisibility" },
+            ]"#,
+        );
+        let user = input(
+            r#"[
+                /* Free up Ctrl+B */
+                { "key": "ctrl+b", "command": "-workbench.action.toggleSidebarVisibility" },
+                { "key": "ctrl+j", "command": "workbench.action.terminal.toggle" },
+                { "key": "ctrl+alt+j", "command": "workbench.action.togglePanel.toggle" },
+                { "key": "ctrl+shift+p", "command": "editor.action.formatDocument" }, // already bound
+            ]"#,
+        );
+
+        let deck = import("vscode", &[defaults, user]).unwrap();
+        let cards: Vec<(String, &str)> = deck
+            .cards
+            .iter()
+            .map(|c| {
+                (
+                    KeyboardMode::Raw.format_keybinds(&c.keybinds),
+                    c.description.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            cards,
+            vec![
+                ("Ctrl+K Ctrl+C".to_string(), "Add comment line"),
+                ("Ctrl+Shift+P | F1".to_string(), "Show commands"),
+                ("Ctrl+`".to_string(), "Toggle terminal"),
+                ("Ctrl+J".to_string(), "Toggle (terminal)"),
+                ("Ctrl+Alt+J".to_string(), "Toggle (toggle panel)"),
+            ]
+        );
+        assert_eq!(deck.skipped, 1);
+    }
+
+    #[test]
+    fn test_import_invalid_json() {
+        assert!(import("vscode", &[input("[{ \"key\": ")]).is_err());
+    }
+}
@@ -0,0 +1,481 @@
+use super::ImportedDeck;
+use crate::deck::KeyboardMode;
+use crate::keybind::{Chord, Keybind};
+use anyhow::{Result, bail};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A binding read from a window manager config
+struct Binding {
+    /// Submap (Hyprland) or mode (Sway/i3); None for the default bindings
+    mode: Option<String>,
+    chord: Option<Chord>,
+    description: String,
+}
+
+/// Import Hyprland `bind = MODS, key, dispatcher, args` lines.
+/// Bindings inside a `submap = name` block go to their own `name-<submap>` deck.
+pub fn import_hyprland(name: &str, inputs: &[(String, String)]) -> Result<Vec<ImportedDeck>> {
+    let mut variables: Vec<(String, String)> = Vec::new();
+    let mut bindings = Vec::new();
+    let mut submap: Option<String> = None;
+
+    for (_, content) in inputs {
+        let mut comment: Option<String> = None;
+        for line in content.lines() {
+            let line = line.trim();
+            let preceding = comment.take();
+            if let Some(text) = line.strip_prefix('#') {
+                comment = Some(text.trim().to_string());
+                continue;
+            }
+            let line = strip_comment(line);
+            let Some((keyword, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (keyword, value) = (keyword.trim(), value.trim());
+
+            if let Some(variable) = keyword.strip_prefix('$') {
+                variables.push((variable.to_string(), expand(value, &variables)));
+                continue;
+            }
+            if keyword == "submap" {
+                submap = (value != "reset").then(|| value.to_string());
+                continue;
+            }
+            let Some(flags) = keyword.strip_prefix("bind") else {
+                continue;
+            };
+            // Mouse bindings (bindm) can't be typed
+            if flags.contains('m') {
+                continue;
+            }
+
+            let value = expand(value, &variables);
+            let mut fields = value.splitn(if flags.contains('d') { 5 } else { 4 }, ',');
+            let mods = fields.next().unwrap_or_default();
+            let key = fields.next().unwrap_or_default().trim();
+            let given = flags
+                .contains('d')
+                .then(|| fields.next().unwrap_or_default().trim().to_string());
+            let dispatcher = fields.next().unwrap_or_default().trim();
+            let args = fields.next().unwrap_or_default().trim();
+
+            let mods: Vec<&str> = mods
+                .split(|c: char| c == '_' || c.is_whitespace())
+                .filter(|m| !m.is_empty())
+                .collect();
+            let description = given
+                .filter(|d| !d.is_empty())
+                .or(preceding.filter(|c| !c.is_empty()))
+                .unwrap_or_else(|| describe_dispatcher(dispatcher, args));
+            bindings.push(Binding {
+                mode: submap.clone(),
+                chord: parse_chord(&mods, key),
+                description,
+            });
+        }
+    }
+
+    build_decks(name, bindings)
+}
+
+/// Import Sway/i3 `bindsym` lines, expanding `set $var value`.
+/// Bindings inside a `mode "name" { ... }` block go to their own `name-<mode>` deck.
+pub fn import_sway(name: &str, inputs: &[(String, String)]) -> Result<Vec<ImportedDeck>> {
+    let mut variables: Vec<(String, String)> = Vec::new();
+    let mut bindings = Vec::new();
+
+    for (_, content) in inputs {
+        let mut comment: Option<String> = None;
+        let mut modes: Vec<Option<String>> = Vec::new();
+        for line in join_continuations(content) {
+            let line = line.trim();
+            let preceding = comment.take();
+            if let Some(text) = line.strip_prefix('#') {
+                comment = Some(text.trim().to_string());
+                continue;
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["set", variable, value @ ..] => {
+                    if let Some(variable) = variable.strip_prefix('$') {
+                        let value = expand(&value.join(" "), &variables);
+                        variables.push((variable.to_string(), value));
+                    }
+                }
+                ["mode", rest @ ..] if line.ends_with('{') => {
+                    let mode = rest
+                        .iter()
+                        .filter(|w| !w.starts_with("--") && **w != "{")
+                        .map(|w| w.trim_matches('"'))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    modes.push(Some(expand(&mode, &variables)));
+                }
+                [.., "{"] => modes.push(None),
+                ["}"] => {
+                    modes.pop();
+                }
+                ["bindsym", rest @ ..] => {
+                    let mut rest = rest.iter().skip_while(|w| w.starts_with("--"));
+                    let Some(combo) = rest.next() else {
+                        continue;
+                    };
+                    let command = expand(&rest.copied().collect::<Vec<_>>().join(" "), &variables);
+                    let combo = expand(combo, &variables);
+                    let parts: Vec<&str> = combo.split('+').collect();
+                    let (key, mods) = parts.split_last().unwrap_or((&"", &[]));
+                    bindings.push(Binding {
+                        mode: modes.iter().rev().flatten().next().cloned(),
+                        chord: parse_chord(mods, key),
+                        description: preceding
+                            .filter(|c| !c.is_empty())
+                            .unwrap_or_else(|| describe_command(&command)),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    build_decks(name, bindings)
+}
+
+/// Group bindings into raw-mode decks: `name`, then `name-<mode>` per submap or mode
+fn build_decks(name: &str, bindings: Vec<Binding>) -> Result<Vec<ImportedDeck>> {
+    let mut decks: Vec<(Option<String>, ImportedDeck)> = Vec::new();
+    for binding in bindings {
+        let index = match decks.iter().position(|(mode, _)| *mode == binding.mode) {
+            Some(index) => index,
+            None => {
+                let deck_name = match &binding.mode {
+                    Some(mode) => format!("{}-{}", name, deck_slug(mode)),
+                    None => name.to_string(),
+                };
+                decks.push((
+                    binding.mode.clone(),
+                    ImportedDeck::new(deck_name, KeyboardMode::Raw),
+                ));
+                decks.len() - 1
+            }
+        };
+        let deck = &mut decks[index].1;
+        match binding.chord {
+            Some(chord) => deck.add(Keybind(vec![chord]), &binding.description),
+            None => deck.skipped += 1,
+        }
+    }
+
+    if decks.is_empty() {
+        bail!("No key bindings found");
+    }
+    // Default bindings first
+    decks.sort_by_key(|(mode, _)| mode.is_some());
+    Ok(decks.into_iter().map(|(_, deck)| deck).collect())
+}
+
+/// A mode name usable in a file name: "Resize: hjkl" → "resize-hjkl"
+fn deck_slug(mode: &str) -> String {
+    mode.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Replace `$variables`, longest names first so `$mod` doesn't clobber `$mod2`
+fn expand(s: &str, variables: &[(String, String)]) -> String {
+    let mut sorted: Vec<&(String, String)> = variables.iter().collect();
+    sorted.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+    let mut out = s.to_string();
+    for (name, value) in sorted {
+        out = out.replace(&format!("${}", name), value);
+    }
+    out
+}
+
+/// Drop a trailing `# comment` (Hyprland writes a literal `#` as `##`)
+fn strip_comment(line: &str) -> String {
+    let mut out = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '#' {
+            if chars.peek() == Some(&'#') {
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Join lines ending in `\` with the next one, as Sway and i3 do
+fn join_continuations(content: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for line in content.lines() {
+        match line.trim_end().strip_suffix('\\') {
+            Some(start) => {
+                current.push_str(start);
+                current.push(' ');
+            }
+            None => {
+                current.push_str(line);
+                lines.push(std::mem::take(&mut current));
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Describe a Hyprland dispatcher, e.g. `movetoworkspace, 1` → "Move window to workspace 1"
+fn describe_dispatcher(dispatcher: &str, args: &str) -> String {
+    let direction = match args {
+        "l" | "left" => "left",
+        "r" | "right" => "right",
+        "u" | "t" | "up" => "up",
+        "d" | "b" | "down" => "down",
+        other => other,
+    };
+    let description = match dispatcher {
+        "exec" | "execr" => format!("Run {}", args),
+        "killactive" => "Close window".to_string(),
+        "exit" => "Exit Hyprland".to_string(),
+        "workspace" => format!("Switch to workspace {}", args),
+        "movetoworkspace" => format!("Move window to workspace {}", args),
+        "movetoworkspacesilent" => format!("Move window to workspace {} silently", args),
+        "togglespecialworkspace" => "Toggle special workspace".to_string(),
+        "togglefloating" => "Toggle floating".to_string(),
+        "fullscreen" => "Toggle fullscreen".to_string(),
+        "pseudo" => "Toggle pseudotiling".to_string(),
+        "togglesplit" => "Toggle split".to_string(),
+        "pin" => "Pin window".to_string(),
+        "movefocus" => format!("Focus {}", direction),
+        "movewindow" => format!("Move window {}", direction),
+        "swapwindow" => format!("Swap window {}", direction),
+        "resizeactive" => format!("Resize window {}", args),
+        "cyclenext" => "Focus next window".to_string(),
+        "focusmonitor" => format!("Focus monitor {}", args),
+        "submap" if args == "reset" => "Leave submap".to_string(),
+        "submap" => format!("Enter {} submap", args),
+        other => format!("{} {}", other, args),
+    };
+    description.trim().to_string()
+}
+
+/// Describe a Sway/i3 command: `exec foot` → "Run foot", `mode "resize"` → "Enter resize mode",
+/// otherwise the command capitalised
+fn describe_command(command: &str) -> String {
+    if let Some(program) = command.strip_prefix("exec ") {
+        let program = program
+            .split_whitespace()
+            .skip_while(|w| w.starts_with("--"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        return format!("Run {}", program);
+    }
+    if let Some(mode) = command.strip_prefix("mode ") {
+        return match mode.trim_matches('"') {
+            "default" => "Leave mode".to_string(),
+            mode => format!("Enter {} mode", mode),
+        };
+    }
+    let mut chars = command.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Build a chord from modifier names and an XKB key name
+fn parse_chord(mods: &[&str], key: &str) -> Option<Chord> {
+    let mut modifiers = KeyModifiers::NONE;
+    for modifier in mods {
+        modifiers |= match modifier.to_lowercase().as_str() {
+            "super" | "win" | "logo" | "mod4" | "meta" => KeyModifiers::SUPER,
+            "shift" => KeyModifiers::SHIFT,
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" | "mod1" => KeyModifiers::ALT,
+            _ => return None,
+        };
+    }
+
+    let code = match key.to_lowercase().as_str() {
+        "return" | "enter" => KeyCode::Enter,
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "escape" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "prior" | "page_up" => KeyCode::PageUp,
+        "next" | "page_down" => KeyCode::PageDown,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "print" => KeyCode::PrintScreen,
+        "pause" => KeyCode::Pause,
+        "menu" => KeyCode::Menu,
+        "comma" => KeyCode::Char(','),
+        "period" => KeyCode::Char('.'),
+        "minus" => KeyCode::Char('-'),
+        "equal" => KeyCode::Char('='),
+        "plus" => KeyCode::Char('+'),
+        "slash" => KeyCode::Char('/'),
+        "backslash" => KeyCode::Char('\\'),
+        "semicolon" => KeyCode::Char(';'),
+        "apostrophe" => KeyCode::Char('\''),
+        "grave" => KeyCode::Char('`'),
+        "bracketleft" => KeyCode::Char('['),
+        "bracketright" => KeyCode::Char(']'),
+        k if k.len() > 1 && k.starts_with('f') && k[1..].chars().all(|c| c.is_ascii_digit()) => {
+            KeyCode::F(k[1..].parse().ok()?)
+        }
+        _ if key.chars().count() == 1 => {
+            let c = key.chars().next()?;
+            // Match the deck style: Super+Q, but a bare h stays h
+            KeyCode::Char(if modifiers.is_empty() {
+                c.to_ascii_lowercase()
+            } else {
+                c.to_ascii_uppercase()
+            })
+        }
+        // Mouse buttons, keycodes and XF86 media keys can't be practised
+        _ => return None,
+    };
+
+    Some(Chord(KeyEvent::new(code, modifiers)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(deck: &ImportedDeck) -> Vec<(String, &str)> {
+        deck.cards
+            .iter()
+            .map(|c| {
+                (
+                    KeyboardMode::Raw.format_keybinds(&c.keybinds),
+                    c.description.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    fn input(content: &str) -> Vec<(String, String)> {
+        vec![("test".to_string(), content.to_string())]
+    }
+
+    #[test]
+    fn test_import_hyprland() {
+        let config = r#"
+$mainMod = SUPER
+$terminal = kitty
+
+# Open a terminal
+bind = $mainMod, Return, exec, $terminal
+bind = $mainMod, Q, killactive, # close it
+bind = $mainMod SHIFT, 1, movetoworkspace, 1
+bind = SUPER_SHIFT, 2, movetoworkspace, 2
+bind = $mainMod, left, movefocus, l
+bind = $mainMod, H, movefocus, l
+bindd = $mainMod, F, Make it big, fullscreen, 0
+bindm = $mainMod, mouse:272, movewindow
+bind = , XF86AudioMute, exec, wpctl set-mute @DEFAULT_AUDIO_SINK@ toggle
+
+bind = $mainMod, R, submap, resize
+submap = resize
+binde = , right, resizeactive, 10 0
+bind = , escape, submap, reset
+submap = reset
+"#;
+        let decks = import_hyprland("hyprland", &input(config)).unwrap();
+        assert_eq!(decks.len(), 2);
+        assert_eq!(decks[0].name, "hyprland");
+        assert_eq!(
+            cards(&decks[0]),
+            vec![
+                ("Super+Enter".to_string(), "Open a terminal"),
+                ("Super+Q".to_string(), "Close window"),
+                ("Shift+Super+1".to_string(), "Move window to workspace 1"),
+                ("Shift+Super+2".to_string(), "Move window to workspace 2"),
+                ("Super+Left | Super+H".to_string(), "Focus left"),
+                ("Super+F".to_string(), "Make it big"),
+                ("Super+R".to_string(), "Enter resize submap"),
+            ]
+        );
+        assert_eq!(decks[0].skipped, 1);
+
+        assert_eq!(decks[1].name, "hyprland-resize");
+        assert_eq!(
+            cards(&decks[1]),
+            vec![
+                ("Right".to_string(), "Resize window 10 0"),
+                ("Esc".to_string(), "Leave submap"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import_sway() {
+        let config = r#"
+set $mod Mod4
+set $left h
+set $term foot
+
+# Start a terminal
+bindsym $mod+Return exec $term
+bindsym --to-code $mod+Shift+1 move container to workspace number 1
+bindsym $mod+$left focus left
+bindsym $mod+Left focus left
+bindsym $mod+Shift+e exec swaynag -t warning \
+    -m 'Exit sway?' -B 'Yes' 'swaymsg exit'
+bindsym XF86AudioMute exec pactl set-sink-mute @DEFAULT_SINK@ toggle
+bindcode 10 workspace 1
+
+mode "resize" {
+    bindsym $left resize shrink width 10px
+    bindsym Escape mode "default"
+}
+bindsym $mod+r mode "resize"
+"#;
+        let decks = import_sway("sway", &input(config)).unwrap();
+        assert_eq!(decks.len(), 2);
+        assert_eq!(
+            cards(&decks[0]),
+            vec![
+                ("Super+Enter".to_string(), "Start a terminal"),
+                (
+                    "Shift+Super+1".to_string(),
+                    "Move container to workspace number 1"
+                ),
+                ("Super+H | Super+Left".to_string(), "Focus left"),
+                (
+                    "Shift+Super+E".to_string(),
+                    "Run swaynag -t warning -m 'Exit sway?' -B 'Yes' 'swaymsg exit'"
+                ),
+                ("Super+R".to_string(), "Enter resize mode"),
+            ]
+        );
+        assert_eq!(decks[0].skipped, 1);
+
+        assert_eq!(decks[1].name, "sway-resize");
+        assert_eq!(
+            cards(&decks[1]),
+            vec![
+                ("h".to_string(), "Resize shrink width 10px"),
+                ("Esc".to_string(), "Leave mode"),
+            ]
+        );
+    }
+}
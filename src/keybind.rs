@@ -1,4 +1,5 @@
 use crate::deck::KeyboardMode;
+use crate::notation::Notation;
 use anyhow::{Result, bail};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use std::fmt;
@@ -7,17 +8,45 @@ use std::fmt;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Chord(pub KeyEvent);
 
+/// Separates alternative keybinds for the same card in a deck file
+pub const ALTERNATIVE_SEPARATOR: &str = "|";
+
+/// Part of a command-mode answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandPart {
+    Literal(String),
+    /// `{branch}` or `<file>` as written: any non-empty argument is accepted
+    Placeholder(String),
+}
+
 /// A sequence of chords (e.g., "Ctrl+K Ctrl+C" or "g g")
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Keybind(pub Vec<Chord>);
 
 impl Chord {
-    /// Parse a single chord from a string like "Ctrl+S" or "Alt+Left" or "g"
+    /// Parse a single chord in any notation, e.g. "Ctrl+S", "<C-s>", "C-s" or "⌘S"
     pub fn parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let mut chords = Keybind::parse(s)?.0;
+        if chords.len() != 1 {
+            bail!("Expected a single chord: {}", s);
+        }
+        Ok(chords.remove(0))
+    }
+
+    /// Parse a single chord from a string like "Ctrl+S" or "Alt+Left" or "g",
+    /// or a control character in caret notation ("^X")
+    pub(crate) fn parse_standard(s: &str) -> Result<Self> {
         let s = s.trim();
         if s.is_empty() {
             bail!("Empty chord");
         }
+        if let Some(c) = s.strip_prefix('^')
+            && let [c] = c.as_bytes()
+            && c.is_ascii_alphabetic()
+        {
+            return Ok(char_chord(*c as char, KeyModifiers::CONTROL));
+        }
 
         let parts: Vec<&str> = s.split('+').collect();
         let mut modifiers = KeyModifiers::NONE;
@@ -27,9 +56,9 @@ impl Chord {
             let part_lower = part.to_lowercase();
             match part_lower.as_str() {
                 "ctrl" | "control" => modifiers |= KeyModifiers::CONTROL,
-                "alt" => modifiers |= KeyModifiers::ALT,
+                "alt" | "option" | "opt" => modifiers |= KeyModifiers::ALT,
                 "shift" => modifiers |= KeyModifiers::SHIFT,
-                "super" => modifiers |= KeyModifiers::SUPER,
+                "super" | "cmd" | "command" => modifiers |= KeyModifiers::SUPER,
                 "meta" => modifiers |= KeyModifiers::META,
                 "hyper" => modifiers |= KeyModifiers::HYPER,
                 _ => {
@@ -42,14 +71,17 @@ impl Chord {
         }
 
         let key_str = key_part.ok_or_else(|| anyhow::anyhow!("No key in chord: {}", s))?;
-        let code = parse_key_code(key_str)?;
-
-        Ok(Chord(KeyEvent::new(code, modifiers)))
+        match parse_key_code(key_str)? {
+            KeyCode::Char(c) => Ok(char_chord(c, modifiers)),
+            code => Ok(Chord(KeyEvent::new(code, modifiers))),
+        }
     }
 
     /// Check if this chord matches a key event
     /// Handles both keyboard modes:
-    /// - Chars mode: exact character match (case-sensitive), including with modifiers
+    /// - Chars mode: exact character match (case-sensitive), including with modifiers.
+    ///   A letter under Ctrl is compared in its stored form, so Ctrl+X is Ctrl+x and
+    ///   only Ctrl+Shift+X matches the shifted key
     /// - Raw mode: Shift+g stays as Shift+g, so we check if uppercase matches;
     ///   modified keys use case-insensitive char (terminals report Ctrl+s for Ctrl+S)
     pub fn matches(&self, event: &KeyEvent, mode: KeyboardMode) -> bool {
@@ -71,6 +103,11 @@ impl Chord {
                     }
                     false
                 } else if mode == KeyboardMode::Chars || mode == KeyboardMode::Command {
+                    if self.0.modifiers.contains(KeyModifiers::CONTROL)
+                        && expected.is_ascii_alphabetic()
+                    {
+                        return key_event_to_chord(event) == *self;
+                    }
                     // Chars mode: require modifier match and case-sensitive char
                     self.0.modifiers == event.modifiers && *expected == *actual
                 } else {
@@ -109,7 +146,13 @@ impl fmt::Display for Chord {
             parts.push("Hyper".to_string());
         }
 
-        let key_str = format_key_code(&self.0.code);
+        let key_str = match self.0.code {
+            // Ctrl+x is always written Ctrl+X; Ctrl+Shift+X is the shifted key
+            KeyCode::Char(c) if self.0.modifiers.contains(KeyModifiers::CONTROL) => {
+                format_key_code(&KeyCode::Char(c.to_ascii_uppercase()))
+            }
+            ref code => format_key_code(code),
+        };
         parts.push(key_str);
 
         let result = parts.join("+");
@@ -119,21 +162,70 @@ impl fmt::Display for Chord {
 
 impl Keybind {
     /// Parse a keybind string with space-separated chords
-    /// e.g., "Ctrl+K Ctrl+C" or "g g"
+    /// e.g., "Ctrl+K Ctrl+C" or "g g", detecting the notation of each chord
     pub fn parse(s: &str) -> Result<Self> {
+        Self::parse_with(s, None)
+    }
+
+    /// Parse a keybind written in the given notation, or detect it chord by chord
+    /// when `None` (so "C-x C-s", "<C-x><C-s>" and "Ctrl+X Ctrl+S" all work)
+    pub fn parse_with(s: &str, notation: Option<Notation>) -> Result<Self> {
         let s = s.trim();
         if s.is_empty() {
             bail!("Empty keybind");
         }
 
-        let chords: Result<Vec<Chord>> = s.split_whitespace().map(Chord::parse).collect();
+        let mut chords = Vec::new();
+        for token in s.split_whitespace() {
+            let notation = notation.unwrap_or_else(|| Notation::detect(token));
+            chords.extend(notation.parse_token(token)?);
+        }
 
-        Ok(Keybind(chords?))
+        Ok(Keybind(chords))
+    }
+
+    /// Parse one or more alternative keybinds separated by a standalone `|`
+    /// e.g., "Ctrl+Y | Ctrl+Shift+Z". A `|` at the very start or end is the pipe
+    /// key itself, so "|" and "d |" still parse as single keybinds.
+    pub fn parse_alternatives(s: &str, notation: Option<Notation>) -> Result<Vec<Self>> {
+        let tokens: Vec<&str> = s.split_whitespace().collect();
+        if tokens.is_empty() {
+            bail!("Empty keybind");
+        }
+
+        let mut groups = vec![Vec::new()];
+        for (i, token) in tokens.iter().enumerate() {
+            if *token == ALTERNATIVE_SEPARATOR && i > 0 && i + 1 < tokens.len() {
+                groups.push(Vec::new());
+            } else if let Some(group) = groups.last_mut() {
+                group.push(*token);
+            }
+        }
+
+        groups
+            .iter()
+            .map(|group| {
+                if group.is_empty() {
+                    bail!("Empty alternative in keybind: {}", s.trim());
+                }
+                Keybind::parse_with(&group.join(" "), notation)
+            })
+            .collect()
+    }
+
+    /// Format alternatives the way `parse_alternatives` reads them
+    pub fn format_alternatives(keybinds: &[Keybind]) -> String {
+        keybinds
+            .iter()
+            .map(|kb| kb.to_string())
+            .collect::<Vec<_>>()
+            .join(&format!(" {} ", ALTERNATIVE_SEPARATOR))
     }
 
     /// Parse a command string where each character becomes its own chord,
     /// with an implicit Enter chord appended to require submission.
     /// e.g., "ls -la" → [l, s, ' ', -, l, a, Enter]
+    /// Placeholders (`{branch}`, `<file>`) are kept as typed; see `command_pattern`.
     pub fn parse_command(s: &str) -> Result<Self> {
         let s = s.trim();
         if s.is_empty() {
@@ -173,6 +265,82 @@ impl fmt::Display for Keybind {
     }
 }
 
+/// Check that a command's placeholders can be told apart when matching
+pub fn check_placeholders(command: &str) -> Result<()> {
+    let parts = command_parts(command);
+    if parts.windows(2).any(|pair| {
+        matches!(
+            pair,
+            [CommandPart::Placeholder(_), CommandPart::Placeholder(_)]
+        )
+    }) {
+        bail!("Placeholders need something between them: {}", command);
+    }
+    Ok(())
+}
+
+/// A command as a pattern to match: split by `command_parts` in a deck with
+/// `# placeholders: on`, or all literal text otherwise, so braces and angle
+/// brackets in a command such as `awk '{print}'` are typed as written
+pub fn command_pattern(command: &str, placeholders: bool) -> Vec<CommandPart> {
+    if placeholders {
+        command_parts(command)
+    } else {
+        vec![CommandPart::Literal(command.to_string())]
+    }
+}
+
+/// Split a command into literal text and placeholders. `{name}` and `<name>` are
+/// placeholders when the name is made of letters, digits, `_`, `-` and `.`, so
+/// `${HOME}`, `{}`, `{a,b}` and `< file` stay literal.
+pub fn command_parts(command: &str) -> Vec<CommandPart> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut rest = command;
+
+    while let Some(c) = rest.chars().next() {
+        let close = match c {
+            '{' if !literal.ends_with('$') => Some('}'),
+            '<' => Some('>'),
+            _ => None,
+        };
+        if let Some(end) = close.and_then(|close| rest.find(close))
+            && end > 1
+            && rest[1..end]
+                .chars()
+                .all(|c| c.is_alphanumeric() || "_-.".contains(c))
+        {
+            if !literal.is_empty() {
+                parts.push(CommandPart::Literal(std::mem::take(&mut literal)));
+            }
+            parts.push(CommandPart::Placeholder(rest[..=end].to_string()));
+            rest = &rest[end + 1..];
+        } else {
+            literal.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    if !literal.is_empty() {
+        parts.push(CommandPart::Literal(literal));
+    }
+    parts
+}
+
+/// A character chord in one form whatever notation it was written in: a letter
+/// under Ctrl is the unshifted key (`<C-x>`, `C-x`, `^X` and `Ctrl+X` are all
+/// Ctrl+x, shown as Ctrl+X) unless Shift is given (Ctrl+Shift+X)
+pub(crate) fn char_chord(c: char, modifiers: KeyModifiers) -> Chord {
+    let c = if !modifiers.contains(KeyModifiers::CONTROL) {
+        c
+    } else if modifiers.contains(KeyModifiers::SHIFT) {
+        c.to_ascii_uppercase()
+    } else {
+        c.to_ascii_lowercase()
+    };
+    Chord(KeyEvent::new(KeyCode::Char(c), modifiers))
+}
+
 /// Parse a key code string to a KeyCode
 fn parse_key_code(s: &str) -> Result<KeyCode> {
     // Single character
@@ -185,6 +353,9 @@ fn parse_key_code(s: &str) -> Result<KeyCode> {
     let lower = s.to_lowercase();
     let code = match lower.as_str() {
         "backslash" => KeyCode::Char('\\'),
+        "pipe" => KeyCode::Char('|'),
+        "hash" => KeyCode::Char('#'),
+        "plus" => KeyCode::Char('+'),
         "backspace" | "back" => KeyCode::Backspace,
         "enter" | "return" => KeyCode::Enter,
         "left" => KeyCode::Left,
@@ -221,7 +392,7 @@ fn parse_key_code(s: &str) -> Result<KeyCode> {
 }
 
 /// Format a KeyCode to a display string
-fn format_key_code(code: &KeyCode) -> String {
+pub(crate) fn format_key_code(code: &KeyCode) -> String {
     match code {
         KeyCode::Backspace => "Backspace".to_string(),
         KeyCode::Enter => "Enter".to_string(),
@@ -246,6 +417,10 @@ fn format_key_code(code: &KeyCode) -> String {
         KeyCode::Menu => "Menu".to_string(),
         KeyCode::F(n) => format!("F{}", n),
         KeyCode::Char(' ') => "Space".to_string(),
+        // A standalone `|` separates alternatives, so the key is written by name
+        KeyCode::Char('|') => "Pipe".to_string(),
+        // `+` joins modifiers to the key
+        KeyCode::Char('+') => "Plus".to_string(),
         KeyCode::Char(c) => c.to_string(),
         KeyCode::Null => "Null".to_string(),
         KeyCode::KeypadBegin => "KeypadBegin".to_string(),
@@ -254,9 +429,23 @@ fn format_key_code(code: &KeyCode) -> String {
     }
 }
 
-/// Convert a KeyEvent to a Chord (for display purposes)
+/// Convert a KeyEvent to a Chord, for display and for logging typed chords.
+/// Ctrl+letter is put in its stored form (see `char_chord`). Chars mode reports
+/// Ctrl+Shift+r as Ctrl with `R`, so an uppercase letter under Ctrl is shifted.
 pub(crate) fn key_event_to_chord(event: &KeyEvent) -> Chord {
-    Chord(KeyEvent::new(event.code, event.modifiers))
+    match event.code {
+        KeyCode::Char(c)
+            if event.modifiers.contains(KeyModifiers::CONTROL) && c.is_ascii_alphabetic() =>
+        {
+            let modifiers = if c.is_ascii_uppercase() {
+                event.modifiers | KeyModifiers::SHIFT
+            } else {
+                event.modifiers
+            };
+            char_chord(c, modifiers)
+        }
+        code => Chord(KeyEvent::new(code, event.modifiers)),
+    }
 }
 
 #[cfg(test)]
@@ -273,8 +462,9 @@ mod tests {
     #[test]
     fn test_parse_ctrl_char() {
         let chord = Chord::parse("Ctrl+S").unwrap();
-        assert_eq!(chord.0.code, KeyCode::Char('S'));
+        assert_eq!(chord.0.code, KeyCode::Char('s'));
         assert_eq!(chord.0.modifiers, KeyModifiers::CONTROL);
+        assert_eq!(chord.to_string(), "Ctrl+S");
     }
 
     #[test]
@@ -311,8 +501,8 @@ mod tests {
     fn test_parse_keybind_multi() {
         let kb = Keybind::parse("Ctrl+K Ctrl+C").unwrap();
         assert_eq!(kb.len(), 2);
-        assert_eq!(kb.0[0].0.code, KeyCode::Char('K'));
-        assert_eq!(kb.0[1].0.code, KeyCode::Char('C'));
+        assert_eq!(kb.0[0].0.code, KeyCode::Char('k'));
+        assert_eq!(kb.0[1].0.code, KeyCode::Char('c'));
     }
 
     #[test]
@@ -323,6 +513,38 @@ mod tests {
         assert_eq!(kb.0[1].0.code, KeyCode::Char('g'));
     }
 
+    #[test]
+    fn test_parse_alternatives() {
+        let alternatives = Keybind::parse_alternatives("Ctrl+Y | Ctrl+Shift+Z", None).unwrap();
+        assert_eq!(alternatives.len(), 2);
+        assert_eq!(alternatives[0].to_string(), "Ctrl+Y");
+        assert_eq!(alternatives[1].to_string(), "Ctrl+Shift+Z");
+        assert_eq!(
+            Keybind::format_alternatives(&alternatives),
+            "Ctrl+Y | Ctrl+Shift+Z"
+        );
+
+        let single = Keybind::parse_alternatives("Ctrl+K Ctrl+C", None).unwrap();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].len(), 2);
+
+        assert!(Keybind::parse_alternatives("a | | b", None).is_err());
+    }
+
+    #[test]
+    fn test_parse_alternatives_literal_pipe() {
+        let pipe = Keybind::parse_alternatives("|", None).unwrap();
+        assert_eq!(pipe.len(), 1);
+        assert_eq!(pipe[0].0[0].0.code, KeyCode::Char('|'));
+
+        let trailing = Keybind::parse_alternatives("d |", None).unwrap();
+        assert_eq!(trailing.len(), 1);
+        assert_eq!(trailing[0].len(), 2);
+
+        let named = Keybind::parse_alternatives("Ctrl+pipe", None).unwrap();
+        assert_eq!(named[0].0[0].0.code, KeyCode::Char('|'));
+    }
+
     #[test]
     fn test_chord_display() {
         let chord = Chord::parse("Ctrl+Shift+K").unwrap();
@@ -365,19 +587,47 @@ mod tests {
 
     #[test]
     fn test_chord_matches_chars_mode_case_sensitive() {
-        // In chars mode, Ctrl+R should NOT match Ctrl+r
-        let chord = Chord::parse("Ctrl+R").unwrap();
+        // In chars mode, Ctrl+Shift+R (reported as Ctrl with R) should NOT match Ctrl+r
+        let chord = Chord::parse("Ctrl+Shift+R").unwrap();
         let event_lower = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
         assert!(!chord.matches(&event_lower, KeyboardMode::Chars));
 
-        // Ctrl+R should match Ctrl+R exactly
+        // Ctrl+Shift+R should match Ctrl+R exactly
         let event_upper = KeyEvent::new(KeyCode::Char('R'), KeyModifiers::CONTROL);
         assert!(chord.matches(&event_upper, KeyboardMode::Chars));
 
-        // Ctrl+r in deck should match Ctrl+r
+        // Ctrl+r in deck is the unshifted key, whichever case it's written in
         let chord_lower = Chord::parse("Ctrl+r").unwrap();
+        assert_eq!(chord_lower, Chord::parse("Ctrl+R").unwrap());
         assert!(chord_lower.matches(&event_lower, KeyboardMode::Chars));
         assert!(!chord_lower.matches(&event_upper, KeyboardMode::Chars));
+
+        // Other modifiers keep the character's case
+        let chord_alt = Chord::parse("Alt+R").unwrap();
+        let alt_lower = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::ALT);
+        let alt_upper = KeyEvent::new(KeyCode::Char('R'), KeyModifiers::ALT);
+        assert!(!chord_alt.matches(&alt_lower, KeyboardMode::Chars));
+        assert!(chord_alt.matches(&alt_upper, KeyboardMode::Chars));
+    }
+
+    #[test]
+    fn test_ctrl_letter_stored_form() {
+        for s in ["Ctrl+X", "Ctrl+x", "C-x", "<C-x>", "<C-X>", "^X", "⌃x"] {
+            assert_eq!(Chord::parse(s).unwrap().to_string(), "Ctrl+X", "{}", s);
+        }
+        for s in ["Ctrl+Shift+x", "<C-S-x>"] {
+            assert_eq!(
+                Chord::parse(s).unwrap().to_string(),
+                "Ctrl+Shift+X",
+                "{}",
+                s
+            );
+        }
+
+        // Typed chords are logged in the same form
+        let typed = |c| key_event_to_chord(&KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL));
+        assert_eq!(typed('x').to_string(), "Ctrl+X");
+        assert_eq!(typed('X').to_string(), "Ctrl+Shift+X");
     }
 
     #[test]
@@ -396,6 +646,50 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_command_parts() {
+        let literal = |s: &str| CommandPart::Literal(s.to_string());
+        let placeholder = |s: &str| CommandPart::Placeholder(s.to_string());
+        assert_eq!(
+            command_parts("git checkout -b {branch}"),
+            vec![literal("git checkout -b "), placeholder("{branch}")]
+        );
+        assert_eq!(
+            command_parts("cp <src> <...>"),
+            vec![
+                literal("cp "),
+                placeholder("<src>"),
+                literal(" "),
+                placeholder("<...>")
+            ]
+        );
+        for s in [
+            "echo ${HOME}",
+            "find . -exec rm {} \\;",
+            "cp {a,b}",
+            "sort < in > out",
+        ] {
+            assert_eq!(command_parts(s), vec![literal(s)]);
+        }
+
+        assert!(check_placeholders("echo {a}{b}").is_err());
+        assert!(check_placeholders("echo {a} {b}").is_ok());
+        assert!(Keybind::parse_command("echo {a}{b}").is_ok());
+
+        // Placeholders are only split out when the deck turns them on
+        let awk = "awk '{print}' <file>";
+        assert_eq!(command_pattern(awk, false), vec![literal(awk)]);
+        assert_eq!(
+            command_pattern(awk, true),
+            vec![
+                literal("awk '"),
+                placeholder("{print}"),
+                literal("' "),
+                placeholder("<file>")
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_command_empty() {
         assert!(Keybind::parse_command("").is_err());
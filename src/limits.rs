@@ -0,0 +1,221 @@
+use crate::deck::{Deck, list_decks};
+use crate::storage::{DayCounts, DeckStats};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Caps on new cards and reviews per day; 0 means no limit
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DailyLimits {
+    pub new_cards: usize,
+    pub reviews: usize,
+}
+
+/// A deck's place in a session: its own limits, what it has done today and what's due
+#[derive(Debug, Clone, Copy)]
+pub struct DeckQuota {
+    pub limits: DailyLimits,
+    pub done: DayCounts,
+    pub due: DayCounts,
+}
+
+/// How many of each deck's due new cards and reviews fit in this session. Each deck
+/// is held to its own limits, then the global limits (less what every deck has done
+/// today) are shared out between decks in turn, so one deck can't take them all.
+pub fn allowances(
+    decks: &[DeckQuota],
+    global: DailyLimits,
+    global_done: DayCounts,
+) -> Vec<DayCounts> {
+    let new_cards = share(
+        decks
+            .iter()
+            .map(|d| remaining(d.limits.new_cards, d.done.new_cards, d.due.new_cards))
+            .collect(),
+        remaining(global.new_cards, global_done.new_cards, usize::MAX),
+    );
+    let reviews = share(
+        decks
+            .iter()
+            .map(|d| remaining(d.limits.reviews, d.done.reviews, d.due.reviews))
+            .collect(),
+        remaining(global.reviews, global_done.reviews, usize::MAX),
+    );
+
+    new_cards
+        .into_iter()
+        .zip(reviews)
+        .map(|(new_cards, reviews)| DayCounts { new_cards, reviews })
+        .collect()
+}
+
+/// Cap each deck's due count at its share of today's limits, as if every deck were
+/// studied in one session, so the counts reach 0 once the day's cards are done
+pub fn cap_due_counts(
+    decks: &mut [DeckStats],
+    deck_limits: &HashMap<String, DailyLimits>,
+    global: DailyLimits,
+    today: &HashMap<String, DayCounts>,
+) {
+    let quotas: Vec<DeckQuota> = decks
+        .iter()
+        .map(|deck| DeckQuota {
+            limits: deck_limits.get(&deck.name).copied().unwrap_or_default(),
+            done: today.get(&deck.name).copied().unwrap_or_default(),
+            due: DayCounts {
+                new_cards: deck.new_due_cards.max(0) as usize,
+                reviews: (deck.due_cards - deck.new_due_cards).max(0) as usize,
+            },
+        })
+        .collect();
+    for (deck, allowed) in decks
+        .iter_mut()
+        .zip(allowances(&quotas, global, total(today.values())))
+    {
+        deck.new_due_cards = allowed.new_cards as i32;
+        deck.due_cards = (allowed.new_cards + allowed.reviews) as i32;
+    }
+}
+
+/// Each deck file's own limits, read without syncing the database.
+/// Deck files that fail to load get no limits of their own.
+pub fn read_deck_limits(decks_dir: &Path) -> HashMap<String, DailyLimits> {
+    list_decks(decks_dir)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|path| Deck::load(path).ok())
+        .map(|deck| (deck.name, deck.daily_limits))
+        .collect()
+}
+
+/// New cards and reviews added up across decks
+pub fn total<'a>(counts: impl IntoIterator<Item = &'a DayCounts>) -> DayCounts {
+    counts
+        .into_iter()
+        .fold(DayCounts::default(), |sum, c| DayCounts {
+            new_cards: sum.new_cards + c.new_cards,
+            reviews: sum.reviews + c.reviews,
+        })
+}
+
+/// What's left under a limit today, capped at what's wanted
+fn remaining(limit: usize, done: usize, wanted: usize) -> usize {
+    if limit == 0 {
+        wanted
+    } else {
+        limit.saturating_sub(done).min(wanted)
+    }
+}
+
+/// Share `total` out one at a time in turn, giving each no more than it wants
+fn share(wanted: Vec<usize>, total: usize) -> Vec<usize> {
+    if wanted.iter().sum::<usize>() <= total {
+        return wanted;
+    }
+    let mut given = vec![0; wanted.len()];
+    let mut left = total;
+    while left > 0 {
+        for (given, wanted) in given.iter_mut().zip(&wanted) {
+            if left > 0 && *given < *wanted {
+                *given += 1;
+                left -= 1;
+            }
+        }
+    }
+    given
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(new_cards: usize, reviews: usize) -> DayCounts {
+        DayCounts { new_cards, reviews }
+    }
+
+    fn quota(limits: (usize, usize), done: (usize, usize), due: (usize, usize)) -> DeckQuota {
+        DeckQuota {
+            limits: DailyLimits {
+                new_cards: limits.0,
+                reviews: limits.1,
+            },
+            done: counts(done.0, done.1),
+            due: counts(due.0, due.1),
+        }
+    }
+
+    #[test]
+    fn test_allowances() {
+        let unlimited = DailyLimits::default();
+        let decks = [
+            quota((0, 0), (0, 0), (200, 30)),
+            quota((5, 0), (2, 0), (40, 10)),
+        ];
+
+        // Deck limits only, less what was done earlier today
+        assert_eq!(
+            allowances(&decks, unlimited, counts(0, 0)),
+            vec![counts(200, 30), counts(3, 10)]
+        );
+
+        // Global limits are shared out in turn
+        let global = DailyLimits {
+            new_cards: 20,
+            reviews: 25,
+        };
+        assert_eq!(
+            allowances(&decks, global, counts(12, 0)),
+            vec![counts(5, 15), counts(3, 10)]
+        );
+
+        // Nothing left today
+        assert_eq!(
+            allowances(&decks, global, counts(20, 30)),
+            vec![counts(0, 0), counts(0, 0)]
+        );
+    }
+
+    #[test]
+    fn test_cap_due_counts() {
+        let deck = |name: &str, due: i32, new_due: i32| DeckStats {
+            name: name.to_string(),
+            total_cards: 200,
+            due_cards: due,
+            new_due_cards: new_due,
+            keyboard_mode: Default::default(),
+        };
+        let mut decks = vec![deck("git", 200, 200), deck("vim", 10, 4)];
+        let deck_limits = HashMap::from([(
+            "vim".to_string(),
+            DailyLimits {
+                new_cards: 3,
+                reviews: 0,
+            },
+        )]);
+        let global = DailyLimits {
+            new_cards: 20,
+            reviews: 0,
+        };
+        let today = HashMap::from([("git".to_string(), counts(10, 0))]);
+
+        cap_due_counts(&mut decks, &deck_limits, global, &today);
+        let due: Vec<(i32, i32)> = decks
+            .iter()
+            .map(|d| (d.due_cards, d.new_due_cards))
+            .collect();
+        assert_eq!(due, vec![(7, 7), (9, 3)]);
+
+        // Once the day's new cards are done, only reviews are left
+        let mut decks = vec![deck("git", 200, 200), deck("vim", 10, 4)];
+        let today = HashMap::from([("git".to_string(), counts(20, 0))]);
+        cap_due_counts(&mut decks, &deck_limits, global, &today);
+        assert_eq!(decks[0].due_cards, 0);
+        assert_eq!(decks[1].due_cards, 6);
+    }
+
+    #[test]
+    fn test_share() {
+        assert_eq!(share(vec![3, 1, 0], 10), vec![3, 1, 0]);
+        assert_eq!(share(vec![5, 1, 5], 6), vec![3, 1, 2]);
+        assert_eq!(share(vec![5, 5], 0), vec![0, 0]);
+    }
+}
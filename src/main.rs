@@ -1,14 +1,24 @@
 mod app;
+mod cli;
 mod config;
+mod dashboard;
 mod deck;
+mod import;
 mod keybind;
+mod limits;
 mod matcher;
+mod notation;
+mod optimizer;
 mod scheduler;
+mod speed;
+mod status;
 mod storage;
+mod study_day;
 mod ui;
 
 use anyhow::Result;
 use app::App;
+use cli::Command;
 use config::Config;
 use crossterm::event::{
     KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
@@ -17,9 +27,38 @@ use crossterm::execute;
 use std::io::stdout;
 
 fn main() -> Result<()> {
-    if std::env::args().any(|a| a == "--version" || a == "-V") {
-        println!("kbsr {}", env!("CARGO_PKG_VERSION"));
-        return Ok(());
+    match Command::parse(std::env::args().skip(1))? {
+        Command::Version => {
+            println!("kbsr {}", env!("CARGO_PKG_VERSION"));
+            Ok(())
+        }
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            Ok(())
+        }
+        Command::List => cli::list(&Config::load()?),
+        Command::Due { json } => cli::due(&Config::load()?, json),
+        Command::Stats { deck } => cli::stats(&Config::load()?, &deck),
+        Command::Status { format } => status::run(&Config::load()?, format),
+        Command::Optimize => optimizer::run(&Config::load()?),
+        Command::Import(options) => import::run(&Config::load()?, &options),
+        Command::Tui { deck } => run_tui(deck.as_deref()),
+    }
+}
+
+fn run_tui(study_deck: Option<&str>) -> Result<()> {
+    let config = Config::load()?;
+
+    // Check the deck exists before taking over the terminal
+    if let Some(name) = study_deck
+        && !config.decks_dir.join(format!("{}.tsv", name)).is_file()
+    {
+        anyhow::bail!(
+            "Unknown deck '{}' (no {}.tsv in {})",
+            name,
+            name,
+            config.decks_dir.display()
+        );
     }
 
     // Warn if running inside tmux - it may intercept keybindings
@@ -38,7 +77,6 @@ fn main() -> Result<()> {
         signal_hook::low_level::register(signal_hook::consts::SIGINT, || {})?;
     }
 
-    let config = Config::load()?;
     let app = App::new(config)?;
 
     let mut terminal = ratatui::init();
@@ -55,7 +93,7 @@ fn main() -> Result<()> {
     )
     .is_ok();
 
-    let result = app.run(&mut terminal);
+    let result = app.run(&mut terminal, study_deck);
 
     // Restore keyboard mode if we enabled enhanced mode
     if enhanced_keyboard {
@@ -1,12 +1,15 @@
 use crate::deck::KeyboardMode;
-use crate::keybind::{Chord, Keybind, key_event_to_chord};
-use crossterm::event::KeyEvent;
+use crate::keybind::{Chord, CommandPart, Keybind, command_pattern, key_event_to_chord};
+use crate::notation::Notation;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 /// State of the input matching
 #[derive(Debug, Clone)]
 pub enum MatchState {
     /// Currently matching, with successfully typed chords
     InProgress(Vec<Chord>),
+    /// Line editing: the typed line has a mistake that can still be corrected
+    Mistyped(Vec<Chord>),
     /// Successfully matched the entire keybind
     Complete(Vec<Chord>),
     /// Failed to match, with all typed chords (to display in red)
@@ -18,6 +21,7 @@ impl MatchState {
     pub fn typed_chords(&self) -> &[Chord] {
         match self {
             MatchState::InProgress(chords) => chords,
+            MatchState::Mistyped(chords) => chords,
             MatchState::Complete(chords) => chords,
             MatchState::Failed(chords) => chords,
         }
@@ -34,60 +38,248 @@ impl MatchState {
     }
 }
 
-/// Matcher for tracking input against expected keybind
+/// Matcher for tracking input against a card's accepted keybinds.
+/// All alternatives are tracked in parallel; typing any one of them completes the match.
 pub struct Matcher {
-    expected: Keybind,
+    expected: Vec<Keybind>,
     mode: KeyboardMode,
     typed: Vec<Chord>,
     failed: bool,
+    /// Command mode only: edit the line like a shell and judge it on Enter
+    line_editing: bool,
+    /// Times the line went wrong while editing, even if corrected afterwards
+    mistakes: usize,
+    /// Command mode only: compare the line as shell words on Enter (`# match: shell`)
+    shell_match: bool,
+    /// Command mode only: `{name}` and `<name>` take any argument (`# placeholders: on`)
+    placeholders: bool,
+    /// What was wrong with the last failed attempt, until typing resumes
+    hint: Option<String>,
+    /// Diagnosis of the last wrong chord, until typing resumes
+    mistake: Option<Mistake>,
+    /// Where the last key landed in the answer, or None for an editing key
+    position: Option<usize>,
 }
 
 impl Matcher {
-    /// Create a new matcher for the given keybind
-    pub fn new(expected: Keybind, mode: KeyboardMode) -> Self {
+    /// Create a new matcher for the given alternative keybinds
+    pub fn new(expected: Vec<Keybind>, mode: KeyboardMode) -> Self {
         Self {
             expected,
             mode,
             typed: Vec::new(),
             failed: false,
+            line_editing: false,
+            mistakes: 0,
+            shell_match: false,
+            placeholders: false,
+            hint: None,
+            mistake: None,
+            position: None,
         }
     }
 
+    /// Let Backspace, Ctrl+W and Ctrl+U edit a command-mode line, which is only
+    /// judged when Enter is pressed. Has no effect in other modes.
+    pub fn with_line_editing(mut self, enabled: bool) -> Self {
+        self.line_editing = enabled && self.mode == KeyboardMode::Command;
+        self
+    }
+
+    /// Judge a command-mode line on Enter by its shell words, so flags can come in any
+    /// order (`ls -al` for `ls -la`). Implies line editing, since nothing can be
+    /// judged before Enter. Has no effect in other modes.
+    pub fn with_shell_matching(mut self, enabled: bool) -> Self {
+        self.shell_match = enabled && self.mode == KeyboardMode::Command;
+        self.line_editing |= self.shell_match;
+        self
+    }
+
+    /// Accept any argument for a command's `{name}` and `<name>` placeholders.
+    /// Has no effect in other modes.
+    pub fn with_placeholders(mut self, enabled: bool) -> Self {
+        self.placeholders = enabled && self.mode == KeyboardMode::Command;
+        self
+    }
+
+    /// What was wrong with the last failed attempt, such as the wrong shell word
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+
+    /// Mistakes made while line editing, including ones that were corrected
+    pub fn mistakes(&self) -> usize {
+        self.mistakes
+    }
+
+    /// What kind of mistake the last chord was, if it went wrong
+    pub fn mistake(&self) -> Option<&Mistake> {
+        self.mistake.as_ref()
+    }
+
+    /// The position in the answer of the last key processed, or None if it edited the
+    /// line (Backspace, Ctrl+W, Ctrl+U) instead of adding a chord to it
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// The chord wanted in place of the last one typed, from the first alternative
+    /// that agrees with everything typed before it
+    pub fn expected_chord(&self) -> Option<&Chord> {
+        self.expected_alternative().map(|(kb, pos)| &kb.0[pos])
+    }
+
+    /// The first alternative that agrees with everything typed before the last
+    /// chord, and the position of the last chord in it
+    fn expected_alternative(&self) -> Option<(&Keybind, usize)> {
+        let (_, before) = self.typed.split_last()?;
+        self.expected
+            .iter()
+            .find(|kb| {
+                kb.len() > before.len()
+                    && before
+                        .iter()
+                        .zip(&kb.0)
+                        .all(|(typed, expected)| expected.matches(&typed.0, self.mode))
+            })
+            .map(|kb| (kb, before.len()))
+    }
+
+    /// Diagnose the last chord typed against the chord expected in its place
+    fn diagnose(&self) -> Option<Mistake> {
+        let (kb, pos) = self.expected_alternative()?;
+        let pressed = &self.typed[pos];
+        let previous = pos.checked_sub(1).map(|i| &self.typed[i]);
+        Some(Mistake::diagnose(
+            &kb.0[pos],
+            kb.0.get(pos + 1),
+            previous,
+            pressed,
+            self.mode,
+        ))
+    }
+
     /// Process a key event and return the new state
     pub fn process(&mut self, event: KeyEvent) -> MatchState {
-        let chord = key_event_to_chord(&event);
-
-        // If already failed, check if this is the start of a retry
+        // If already failed, this is the start of a retry
         if self.failed {
-            // User is starting over
             self.typed.clear();
             self.failed = false;
         }
+        self.hint = None;
+        self.mistake = None;
+        self.position = None;
 
-        // Add the typed chord
-        self.typed.push(chord.clone());
+        if self.line_editing {
+            return self.edit_line(event);
+        }
+
+        self.push(&event);
 
-        // Check if it matches the expected chord at this position
-        let position = self.typed.len() - 1;
-        if position >= self.expected.len() {
+        // Fail once no alternative still agrees with everything typed so far
+        if !self.expected.iter().any(|kb| self.is_prefix_of(kb)) {
             self.failed = true;
-            return MatchState::Failed(self.typed.clone());
+            self.mistake = self.diagnose();
         }
-        let expected_chord = &self.expected.0[position];
 
-        if !expected_chord.matches(&event, self.mode) {
-            // Wrong chord - fail
-            self.failed = true;
-            return MatchState::Failed(self.typed.clone());
+        self.state()
+    }
+
+    /// Line editing: apply an editing key or type a character, judging the line on Enter
+    fn edit_line(&mut self, event: KeyEvent) -> MatchState {
+        let ctrl = event.modifiers == KeyModifiers::CONTROL;
+        match event.code {
+            KeyCode::Backspace => {
+                self.typed.pop();
+            }
+            // Delete the word before the cursor, as a shell does
+            KeyCode::Char('w') if ctrl => {
+                while self.typed.last().is_some_and(is_space) {
+                    self.typed.pop();
+                }
+                while self.typed.last().is_some_and(|c| !is_space(c)) {
+                    self.typed.pop();
+                }
+            }
+            KeyCode::Char('u') if ctrl => self.typed.clear(),
+            KeyCode::Enter if self.typed.is_empty() => {}
+            KeyCode::Enter => {
+                self.push(&event);
+                if !self.expected.iter().any(|kb| self.is_match(kb)) {
+                    self.failed = true;
+                    if self.shell_match
+                        && let Some(kb) = self.expected.first()
+                        && let Some(line) = typed_line(&self.typed[..self.typed.len() - 1])
+                    {
+                        self.hint =
+                            shell_mismatch(&kb.as_command_string(), &line, self.placeholders);
+                    }
+                }
+            }
+            _ if self.shell_match => self.push(&event),
+            _ => {
+                let was_valid = self.is_valid_prefix();
+                self.push(&event);
+                if was_valid && !self.is_valid_prefix() {
+                    self.mistakes += 1;
+                    self.mistake = self.diagnose();
+                }
+            }
         }
 
-        // Correct chord - check if complete
-        if self.typed.len() == self.expected.len() {
-            return MatchState::Complete(self.typed.clone());
+        self.state()
+    }
+
+    /// Add a key to the answer, noting where it went
+    fn push(&mut self, event: &KeyEvent) {
+        self.position = Some(self.typed.len());
+        self.typed.push(key_event_to_chord(event));
+    }
+
+    /// Whether some alternative still agrees with everything typed so far
+    fn is_valid_prefix(&self) -> bool {
+        self.expected.iter().any(|kb| self.is_prefix_of(kb))
+    }
+
+    /// Whether the typed chords match the start of the given keybind.
+    /// Commands are matched as text, so placeholders can take any argument;
+    /// once Enter is typed the whole command has to match.
+    fn is_prefix_of(&self, keybind: &Keybind) -> bool {
+        if self.mode == KeyboardMode::Command {
+            return match self.typed.split_last() {
+                Some((last, line)) if last.0.code == KeyCode::Enter && self.shell_match => {
+                    typed_line(line).is_some_and(|line| {
+                        shell_mismatch(&keybind.as_command_string(), &line, self.placeholders)
+                            .is_none()
+                    })
+                }
+                Some((last, line)) if last.0.code == KeyCode::Enter => {
+                    command_matches(keybind, line, self.placeholders, true)
+                }
+                // Shell matching can't judge a line until it's finished
+                _ if self.shell_match => true,
+                _ => command_matches(keybind, &self.typed, self.placeholders, false),
+            };
         }
 
-        // Still in progress
-        MatchState::InProgress(self.typed.clone())
+        self.typed.len() <= keybind.len()
+            && self
+                .typed
+                .iter()
+                .zip(&keybind.0)
+                .all(|(typed, expected)| expected.matches(&typed.0, self.mode))
+    }
+
+    /// Whether the typed chords are the whole of the given keybind
+    fn is_match(&self, keybind: &Keybind) -> bool {
+        if self.mode == KeyboardMode::Command {
+            self.typed
+                .last()
+                .is_some_and(|c| c.0.code == KeyCode::Enter)
+                && self.is_prefix_of(keybind)
+        } else {
+            keybind.len() == self.typed.len() && self.is_prefix_of(keybind)
+        }
     }
 
     /// Reset the matcher (for retry after failure)
@@ -100,18 +292,518 @@ impl Matcher {
     pub fn state(&self) -> MatchState {
         if self.failed {
             MatchState::Failed(self.typed.clone())
-        } else if self.typed.len() == self.expected.len() && !self.typed.is_empty() {
+        } else if !self.typed.is_empty() && self.expected.iter().any(|kb| self.is_match(kb)) {
             MatchState::Complete(self.typed.clone())
+        } else if self.line_editing && !self.is_valid_prefix() {
+            MatchState::Mistyped(self.typed.clone())
         } else {
             MatchState::InProgress(self.typed.clone())
         }
     }
 }
 
+/// Characters and their shifted counterparts on a US keyboard
+const SHIFTED_PAIRS: &[(char, char)] = &[
+    ('`', '~'),
+    ('1', '!'),
+    ('2', '@'),
+    ('3', '#'),
+    ('4', '$'),
+    ('5', '%'),
+    ('6', '^'),
+    ('7', '&'),
+    ('8', '*'),
+    ('9', '('),
+    ('0', ')'),
+    ('-', '_'),
+    ('=', '+'),
+    ('[', '{'),
+    (']', '}'),
+    ('\\', '|'),
+    (';', ':'),
+    ('\'', '"'),
+    (',', '<'),
+    ('.', '>'),
+    ('/', '?'),
+];
+
+/// Modifiers named in hints, in the order chords are written
+const MODIFIER_NAMES: &[(KeyModifiers, &str)] = &[
+    (KeyModifiers::CONTROL, "Ctrl"),
+    (KeyModifiers::ALT, "Alt"),
+    (KeyModifiers::SHIFT, "Shift"),
+    (KeyModifiers::SUPER, "Super"),
+    (KeyModifiers::META, "Meta"),
+    (KeyModifiers::HYPER, "Hyper"),
+];
+
+/// The kind of a wrong chord, aggregated per card in storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MistakeKind {
+    /// Right key, but a modifier left out
+    MissingModifier,
+    /// Right key, with a modifier too many
+    ExtraModifier,
+    /// Right key, with one modifier in place of another
+    WrongModifier,
+    /// A different key
+    WrongKey,
+    /// The next chord typed before this one
+    Swapped,
+    /// The previous chord typed again
+    ExtraKey,
+    /// Shifted and unshifted forms mixed up (`G` for `g`, `!` for `Shift+1`)
+    ShiftCase,
+}
+
+impl MistakeKind {
+    pub const ALL: [MistakeKind; 7] = [
+        MistakeKind::MissingModifier,
+        MistakeKind::ExtraModifier,
+        MistakeKind::WrongModifier,
+        MistakeKind::WrongKey,
+        MistakeKind::Swapped,
+        MistakeKind::ExtraKey,
+        MistakeKind::ShiftCase,
+    ];
+
+    /// Name stored in the database
+    pub fn name(&self) -> &'static str {
+        match self {
+            MistakeKind::MissingModifier => "missing_modifier",
+            MistakeKind::ExtraModifier => "extra_modifier",
+            MistakeKind::WrongModifier => "wrong_modifier",
+            MistakeKind::WrongKey => "wrong_key",
+            MistakeKind::Swapped => "swapped",
+            MistakeKind::ExtraKey => "extra_key",
+            MistakeKind::ShiftCase => "shift_case",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Short description for the stats screen
+    pub fn label(&self) -> &'static str {
+        match self {
+            MistakeKind::MissingModifier => "missing modifier",
+            MistakeKind::ExtraModifier => "extra modifier",
+            MistakeKind::WrongModifier => "wrong modifier",
+            MistakeKind::WrongKey => "wrong key",
+            MistakeKind::Swapped => "wrong order",
+            MistakeKind::ExtraKey => "extra key",
+            MistakeKind::ShiftCase => "Shift/case",
+        }
+    }
+}
+
+/// A diagnosed wrong chord: what was pressed and what was wanted in its place
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mistake {
+    pub kind: MistakeKind,
+    pub pressed: Chord,
+    pub expected: Chord,
+    mode: KeyboardMode,
+}
+
+impl Mistake {
+    /// Work out why `pressed` didn't match `expected`, given the chords either side
+    fn diagnose(
+        expected: &Chord,
+        next: Option<&Chord>,
+        previous: Option<&Chord>,
+        pressed: &Chord,
+        mode: KeyboardMode,
+    ) -> Self {
+        let kind = if next.is_some_and(|next| next.matches(&pressed.0, mode)) {
+            MistakeKind::Swapped
+        } else if previous.is_some_and(|previous| previous == pressed) {
+            MistakeKind::ExtraKey
+        } else if is_shift_confusion(expected, pressed, mode) {
+            MistakeKind::ShiftCase
+        } else if same_key(expected, pressed) {
+            let wanted = expected.0.modifiers;
+            let got = pressed.0.modifiers;
+            if got.contains(wanted) {
+                MistakeKind::ExtraModifier
+            } else if wanted.contains(got) {
+                MistakeKind::MissingModifier
+            } else {
+                MistakeKind::WrongModifier
+            }
+        } else {
+            MistakeKind::WrongKey
+        };
+
+        Mistake {
+            kind,
+            pressed: pressed.clone(),
+            expected: expected.clone(),
+            mode,
+        }
+    }
+
+    /// A hint naming the mistake, with chords written in the given notation.
+    /// None for a plain wrong key, where there's nothing more to say.
+    pub fn describe(&self, notation: Notation) -> Option<String> {
+        let wanted = self.expected.0.modifiers;
+        let got = self.pressed.0.modifiers;
+        let pressed = notation.format_chord(&self.pressed);
+        let expected = notation.format_chord(&self.expected);
+        let hint = match self.kind {
+            MistakeKind::MissingModifier => {
+                format!("You left out {}", modifier_names(wanted - got))
+            }
+            MistakeKind::ExtraModifier => {
+                format!("You pressed {} as well", modifier_names(got - wanted))
+            }
+            MistakeKind::WrongModifier => format!(
+                "You pressed {} instead of {}",
+                modifier_names(got - wanted),
+                modifier_names(wanted - got)
+            ),
+            MistakeKind::WrongKey if wanted.is_empty() || wanted != got => return None,
+            MistakeKind::WrongKey => "Right modifiers, wrong key".to_string(),
+            MistakeKind::Swapped => format!("{} comes after {}", pressed, expected),
+            MistakeKind::ExtraKey => format!("You pressed {} again", pressed),
+            MistakeKind::ShiftCase => match self.mode {
+                KeyboardMode::Raw => format!(
+                    "This deck wants the keys pressed, {} not {}",
+                    expected, pressed
+                ),
+                KeyboardMode::Chars | KeyboardMode::Command => format!(
+                    "This deck wants the character typed, {} not {}",
+                    expected, pressed
+                ),
+            },
+        };
+        Some(hint)
+    }
+}
+
+/// Whether two chords are on the same key (letters in either case)
+fn same_key(a: &Chord, b: &Chord) -> bool {
+    match (a.0.code, b.0.code) {
+        (KeyCode::Char(x), KeyCode::Char(y)) => x.eq_ignore_ascii_case(&y),
+        (x, y) => x == y,
+    }
+}
+
+/// Whether `pressed` is `expected` with its shifted and unshifted forms mixed up:
+/// a character typed for the keys that make it, or the other way round. In raw
+/// mode Shift is a modifier like any other, so letters only count when unmodified.
+fn is_shift_confusion(expected: &Chord, pressed: &Chord, mode: KeyboardMode) -> bool {
+    let (KeyCode::Char(x), KeyCode::Char(y)) = (expected.0.code, pressed.0.code) else {
+        return false;
+    };
+    let other_modifiers = |chord: &Chord| chord.0.modifiers - KeyModifiers::SHIFT;
+    if x == y || other_modifiers(expected) != other_modifiers(pressed) {
+        return false;
+    }
+    let shifted_pair = SHIFTED_PAIRS
+        .iter()
+        .any(|&(a, b)| (a, b) == (x, y) || (b, a) == (x, y));
+    let case_pair = x.eq_ignore_ascii_case(&y);
+    match mode {
+        KeyboardMode::Raw => shifted_pair || (case_pair && other_modifiers(expected).is_empty()),
+        KeyboardMode::Chars | KeyboardMode::Command => shifted_pair || case_pair,
+    }
+}
+
+/// Modifier names joined with `+`, such as `Ctrl+Alt`
+fn modifier_names(modifiers: KeyModifiers) -> String {
+    MODIFIER_NAMES
+        .iter()
+        .filter(|(modifier, _)| modifiers.contains(*modifier))
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+fn is_space(chord: &Chord) -> bool {
+    chord.0.code == KeyCode::Char(' ')
+}
+
+/// The text of a typed command line, or None if it has keys that aren't characters
+fn typed_line(typed: &[Chord]) -> Option<String> {
+    typed
+        .iter()
+        .map(|chord| match chord.0.code {
+            KeyCode::Char(c) if (chord.0.modifiers - KeyModifiers::SHIFT).is_empty() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether a typed line matches (the start of, unless `full`) a command, with any
+/// placeholders in it if they're turned on
+fn command_matches(keybind: &Keybind, typed: &[Chord], placeholders: bool, full: bool) -> bool {
+    typed_line(typed).is_some_and(|line| {
+        let line: Vec<char> = line.chars().collect();
+        let parts = command_pattern(&keybind.as_command_string(), placeholders);
+        match_parts(&parts, &line, full)
+    })
+}
+
+fn match_parts(parts: &[CommandPart], line: &[char], full: bool) -> bool {
+    let Some((part, rest)) = parts.split_first() else {
+        return line.is_empty();
+    };
+    if line.is_empty() {
+        return !full;
+    }
+
+    match part {
+        CommandPart::Literal(text) => {
+            let text: Vec<char> = text.chars().collect();
+            if line.len() < text.len() {
+                !full && text.starts_with(line)
+            } else {
+                line.starts_with(&text) && match_parts(rest, &line[text.len()..], full)
+            }
+        }
+        // Try every length of argument, since the text after it might also appear inside it
+        CommandPart::Placeholder(_) => (1..=line.len()).any(|n| {
+            let (argument, after) = line.split_at(n);
+            match argument_state(argument) {
+                None => false,
+                Some(complete) => {
+                    (after.is_empty() && !full) || (complete && match_parts(rest, after, full))
+                }
+            }
+        }),
+    }
+}
+
+/// Short flags that take a value, by program, so a literal value such as the `22`
+/// in `ssh -p 22` moves with its flag. Other flags' values need a placeholder.
+const VALUE_FLAGS: &[(&str, &str)] = &[
+    ("ssh", "BbcDEeFIiJLlmOoPpQRSWw"),
+    ("scp", "cFiJloPS"),
+    ("git", "bCcmn"),
+    ("grep", "ABCefm"),
+    ("docker", "efhpuvw"),
+    ("kubectl", "cflno"),
+    ("curl", "dHoTuX"),
+    ("head", "cn"),
+    ("tail", "cn"),
+    ("tar", "Cf"),
+    ("cut", "cdf"),
+    ("sort", "kt"),
+    ("xargs", "In"),
+    ("ssh-keygen", "bCft"),
+];
+
+/// Whether a program's short flag is known to take a value
+fn known_value_flag(program: &str, letter: char) -> bool {
+    let program = program.rsplit('/').next().unwrap_or(program);
+    VALUE_FLAGS
+        .iter()
+        .any(|(name, letters)| *name == program && letters.contains(letter))
+}
+
+/// A command split into shell words by kind
+#[derive(Debug, Default)]
+struct ShellArgs {
+    /// Command words and arguments, in order (everything after `--` too)
+    positional: Vec<String>,
+    /// Each short flag letter
+    short: Vec<ShortFlag>,
+    /// `--long` flags, with any `=value`
+    long: Vec<String>,
+}
+
+/// A short flag letter, the word it came from (`l` from `-la`) and the value it takes
+#[derive(Debug, Clone)]
+struct ShortFlag {
+    letter: char,
+    word: String,
+    value: Option<String>,
+}
+
+impl ShellArgs {
+    /// Split a command into words. A word after a short flag is taken as that flag's
+    /// value when `takes_value(program, flags, letter, next)` says the flag has one.
+    fn parse(line: &str, takes_value: impl Fn(&str, &str, char, &str) -> bool) -> Self {
+        let mut args = ShellArgs::default();
+        let mut options_ended = false;
+        let mut words = shell_words(line).into_iter().peekable();
+        while let Some(word) = words.next() {
+            if options_ended {
+                args.positional.push(word);
+            } else if word == "--" {
+                options_ended = true;
+                args.positional.push(word);
+            } else if word.starts_with("--") {
+                args.long.push(word);
+            } else if is_short_flags(&word) {
+                let last = word.chars().last().unwrap_or_default();
+                let program = args.positional.first().map_or("", String::as_str);
+                let value = words.next_if(|next| {
+                    !next.starts_with('-') && takes_value(program, &word, last, next)
+                });
+                for letter in word[1..].chars() {
+                    args.short.push(ShortFlag {
+                        letter,
+                        word: word.clone(),
+                        value: if letter == last { value.clone() } else { None },
+                    });
+                }
+            } else {
+                args.positional.push(word);
+            }
+        }
+        args
+    }
+}
+
+/// `-l`, `-la`: one or more short flags in a word
+fn is_short_flags(word: &str) -> bool {
+    word.len() > 1 && word.starts_with('-') && word[1..].chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Compare a typed command with the expected one as a shell would see it: command
+/// words and other arguments in order, short flags as a set and long flags in any
+/// order. The word after a lone short flag in the expected command is that flag's
+/// value and moves with it when it's a placeholder (`-p {port}`) or the flag is known
+/// to take one (`ssh -p 22`); typed flags take values the same way, and flags are
+/// compared with their values. Any other word is an ordinary argument.
+/// Returns what's wrong with the typed command, or None if it matches.
+fn shell_mismatch(expected: &str, typed: &str, placeholders: bool) -> Option<String> {
+    let expected = ShellArgs::parse(expected, |program, word, letter, next| {
+        word.len() == 2
+            && (known_value_flag(program, letter)
+                || command_pattern(next, placeholders)
+                    .iter()
+                    .any(|part| matches!(part, CommandPart::Placeholder(_))))
+    });
+    let typed = ShellArgs::parse(typed, |_, _, letter, _| {
+        expected
+            .short
+            .iter()
+            .any(|f| f.letter == letter && f.value.is_some())
+    });
+    let word_matches = |pattern: &str, word: &str| {
+        matches_word(
+            &command_pattern(pattern, placeholders),
+            &word.chars().collect::<Vec<_>>(),
+        )
+    };
+
+    // Flags with the same value in both; a repeated flag without a value is fine
+    for flag in &typed.short {
+        let value_matches = |e: &ShortFlag| match (&e.value, &flag.value) {
+            (Some(pattern), Some(value)) => word_matches(pattern, value),
+            (None, None) => true,
+            _ => false,
+        };
+        if expected
+            .short
+            .iter()
+            .any(|e| e.letter == flag.letter && value_matches(e))
+        {
+            continue;
+        }
+        return Some(if !expected.short.iter().any(|e| e.letter == flag.letter) {
+            format!("Unexpected flag -{} in {}", flag.letter, flag.word)
+        } else if let Some(value) = &flag.value {
+            format!("Wrong argument: {}", value)
+        } else {
+            "Missing an argument".to_string()
+        });
+    }
+    for (i, word) in typed.positional.iter().enumerate() {
+        match expected.positional.get(i) {
+            Some(pattern) if word_matches(pattern, word) => {}
+            Some(_) => return Some(format!("Wrong argument: {}", word)),
+            None => return Some(format!("Unexpected argument: {}", word)),
+        }
+    }
+    if typed.positional.len() < expected.positional.len() {
+        return Some("Missing an argument".to_string());
+    }
+
+    let mut long = expected.long.clone();
+    for word in &typed.long {
+        match long.iter().position(|pattern| word_matches(pattern, word)) {
+            Some(i) => {
+                long.remove(i);
+            }
+            None => return Some(format!("Unexpected flag: {}", word)),
+        }
+    }
+    if !long.is_empty()
+        || expected
+            .short
+            .iter()
+            .any(|e| !typed.short.iter().any(|f| f.letter == e.letter))
+    {
+        return Some("Missing a flag".to_string());
+    }
+    None
+}
+
+/// Split a line into words as a shell would, removing quotes and backslash escapes
+fn shell_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.get_or_insert_default().push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                word.get_or_insert_default();
+            }
+            None if c == '\\' => {
+                let escaped = chars.next().unwrap_or('\\');
+                word.get_or_insert_default().push(escaped);
+            }
+            None if c.is_whitespace() => words.extend(word.take()),
+            None => word.get_or_insert_default().push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// Whether a whole word matches a pattern whose placeholders take any non-empty text
+fn matches_word(parts: &[CommandPart], word: &[char]) -> bool {
+    let Some((part, rest)) = parts.split_first() else {
+        return word.is_empty();
+    };
+    match part {
+        CommandPart::Literal(text) => {
+            let text: Vec<char> = text.chars().collect();
+            word.starts_with(&text) && matches_word(rest, &word[text.len()..])
+        }
+        CommandPart::Placeholder(_) => (1..=word.len()).any(|n| matches_word(rest, &word[n..])),
+    }
+}
+
+/// Whether `argument` is a whole shell word (`Some(true)`), the start of one
+/// such as an unclosed quote (`Some(false)`), or can't be one (`None`)
+fn argument_state(argument: &[char]) -> Option<bool> {
+    let mut quote = None;
+    let mut content = false;
+    for &c in argument {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => content = true,
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c.is_whitespace() => return None,
+            None => content = true,
+        }
+    }
+    Some(quote.is_none() && content)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crossterm::event::{KeyCode, KeyModifiers};
 
     fn make_event(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
         KeyEvent::new(code, modifiers)
@@ -120,25 +812,25 @@ mod tests {
     #[test]
     fn test_single_chord_match() {
         let kb = Keybind::parse("Ctrl+S").unwrap();
-        let mut matcher = Matcher::new(kb, KeyboardMode::Raw);
+        let mut matcher = Matcher::new(vec![kb], KeyboardMode::Raw);
 
-        let state = matcher.process(make_event(KeyCode::Char('S'), KeyModifiers::CONTROL));
+        let state = matcher.process(make_event(KeyCode::Char('s'), KeyModifiers::CONTROL));
         assert!(state.is_complete());
     }
 
     #[test]
     fn test_single_chord_fail() {
         let kb = Keybind::parse("Ctrl+S").unwrap();
-        let mut matcher = Matcher::new(kb, KeyboardMode::Raw);
+        let mut matcher = Matcher::new(vec![kb], KeyboardMode::Raw);
 
-        let state = matcher.process(make_event(KeyCode::Char('X'), KeyModifiers::CONTROL));
+        let state = matcher.process(make_event(KeyCode::Char('x'), KeyModifiers::CONTROL));
         assert!(state.is_failed());
     }
 
     #[test]
     fn test_multi_chord_progress() {
         let kb = Keybind::parse("g g").unwrap();
-        let mut matcher = Matcher::new(kb, KeyboardMode::Chars);
+        let mut matcher = Matcher::new(vec![kb], KeyboardMode::Chars);
 
         let state = matcher.process(make_event(KeyCode::Char('g'), KeyModifiers::NONE));
         assert!(matches!(state, MatchState::InProgress(_)));
@@ -150,20 +842,24 @@ mod tests {
     #[test]
     fn test_multi_chord_fail_mid() {
         let kb = Keybind::parse("Ctrl+K Ctrl+C").unwrap();
-        let mut matcher = Matcher::new(kb, KeyboardMode::Raw);
+        let mut matcher = Matcher::new(vec![kb], KeyboardMode::Raw);
 
-        let state = matcher.process(make_event(KeyCode::Char('K'), KeyModifiers::CONTROL));
+        let state = matcher.process(make_event(KeyCode::Char('k'), KeyModifiers::CONTROL));
         assert!(matches!(state, MatchState::InProgress(_)));
 
-        let state = matcher.process(make_event(KeyCode::Char('X'), KeyModifiers::CONTROL));
+        let state = matcher.process(make_event(KeyCode::Char('x'), KeyModifiers::CONTROL));
         assert!(state.is_failed());
         assert_eq!(state.typed_chords().len(), 2);
+        assert_eq!(
+            matcher.expected_chord(),
+            Some(&Chord::parse("Ctrl+C").unwrap())
+        );
     }
 
     #[test]
     fn test_reset_after_fail() {
         let kb = Keybind::parse("g g").unwrap();
-        let mut matcher = Matcher::new(kb, KeyboardMode::Chars);
+        let mut matcher = Matcher::new(vec![kb], KeyboardMode::Chars);
 
         // Fail first
         let _ = matcher.process(make_event(KeyCode::Char('x'), KeyModifiers::NONE));
@@ -174,4 +870,375 @@ mod tests {
         let state = matcher.process(make_event(KeyCode::Char('g'), KeyModifiers::NONE));
         assert!(matches!(state, MatchState::InProgress(_)));
     }
+
+    #[test]
+    fn test_alternatives_any_completes() {
+        let alternatives = vec![
+            Keybind::parse("Ctrl+Y").unwrap(),
+            Keybind::parse("Ctrl+Shift+Z").unwrap(),
+        ];
+
+        let mut matcher = Matcher::new(alternatives.clone(), KeyboardMode::Raw);
+        let state = matcher.process(make_event(KeyCode::Char('y'), KeyModifiers::CONTROL));
+        assert!(state.is_complete());
+
+        let mut matcher = Matcher::new(alternatives, KeyboardMode::Raw);
+        let state = matcher.process(make_event(
+            KeyCode::Char('Z'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        ));
+        assert!(state.is_complete());
+    }
+
+    #[test]
+    fn test_alternatives_tracked_in_parallel() {
+        let alternatives = vec![
+            Keybind::parse("g g").unwrap(),
+            Keybind::parse("g h").unwrap(),
+            Keybind::parse("Ctrl+Home").unwrap(),
+        ];
+        let mut matcher = Matcher::new(alternatives, KeyboardMode::Chars);
+
+        let state = matcher.process(make_event(KeyCode::Char('g'), KeyModifiers::NONE));
+        assert!(matches!(state, MatchState::InProgress(_)));
+        let state = matcher.process(make_event(KeyCode::Char('h'), KeyModifiers::NONE));
+        assert!(state.is_complete());
+
+        matcher.reset();
+        let _ = matcher.process(make_event(KeyCode::Char('g'), KeyModifiers::NONE));
+        let state = matcher.process(make_event(KeyCode::Char('x'), KeyModifiers::NONE));
+        assert!(state.is_failed());
+        assert_eq!(matcher.expected_chord(), Some(&Chord::parse("g").unwrap()));
+    }
+
+    /// The mistake diagnosed for typing `typed` (chords in standard notation)
+    fn mistake_for(expected: &str, mode: KeyboardMode, typed: &str) -> Mistake {
+        let mut matcher = Matcher::new(vec![Keybind::parse(expected).unwrap()], mode);
+        for chord in Keybind::parse(typed).unwrap().0 {
+            matcher.process(chord.0);
+        }
+        assert!(matcher.state().is_failed());
+        matcher.mistake().cloned().unwrap()
+    }
+
+    #[test]
+    fn test_mistakes() {
+        let hint = |expected: &str, mode: KeyboardMode, typed: &str| {
+            let mistake = mistake_for(expected, mode, typed);
+            (mistake.kind, mistake.describe(Notation::Standard))
+        };
+        let raw = KeyboardMode::Raw;
+        let chars = KeyboardMode::Chars;
+
+        assert_eq!(
+            hint("Super+1", raw, "Alt+1"),
+            (
+                MistakeKind::WrongModifier,
+                Some("You pressed Alt instead of Super".to_string())
+            )
+        );
+        assert_eq!(
+            hint("Ctrl+Shift+K", raw, "Ctrl+K"),
+            (
+                MistakeKind::MissingModifier,
+                Some("You left out Shift".to_string())
+            )
+        );
+        assert_eq!(
+            hint("Ctrl+K", raw, "Ctrl+Alt+K"),
+            (
+                MistakeKind::ExtraModifier,
+                Some("You pressed Alt as well".to_string())
+            )
+        );
+        assert_eq!(
+            hint("Ctrl+K Ctrl+C", raw, "Ctrl+C"),
+            (
+                MistakeKind::Swapped,
+                Some("Ctrl+C comes after Ctrl+K".to_string())
+            )
+        );
+        assert_eq!(
+            hint("d i w", chars, "d d"),
+            (
+                MistakeKind::ExtraKey,
+                Some("You pressed d again".to_string())
+            )
+        );
+        assert_eq!(
+            hint("Ctrl+K", raw, "Ctrl+J"),
+            (
+                MistakeKind::WrongKey,
+                Some("Right modifiers, wrong key".to_string())
+            )
+        );
+        assert_eq!(hint("g g", chars, "g x"), (MistakeKind::WrongKey, None));
+
+        // Shift and case, judged by what the deck's mode wants
+        assert_eq!(
+            hint("G", chars, "g"),
+            (
+                MistakeKind::ShiftCase,
+                Some("This deck wants the character typed, G not g".to_string())
+            )
+        );
+        assert_eq!(
+            hint("Shift+1", raw, "!"),
+            (
+                MistakeKind::ShiftCase,
+                Some("This deck wants the keys pressed, Shift+1 not !".to_string())
+            )
+        );
+        assert_eq!(
+            mistake_for("Alt+k", raw, "Alt+Shift+K").kind,
+            MistakeKind::ExtraModifier
+        );
+
+        // Typing on clears the diagnosis
+        let mut matcher = Matcher::new(vec![Keybind::parse("g g").unwrap()], chars);
+        matcher.process(make_event(KeyCode::Char('x'), KeyModifiers::NONE));
+        assert!(matcher.mistake().is_some());
+        matcher.reset();
+        matcher.process(make_event(KeyCode::Char('g'), KeyModifiers::NONE));
+        assert_eq!(matcher.mistake(), None);
+    }
+
+    fn type_str(matcher: &mut Matcher, s: &str) -> MatchState {
+        let mut state = matcher.state();
+        for c in s.chars() {
+            state = matcher.process(make_event(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        state
+    }
+
+    #[test]
+    fn test_line_editing() {
+        let kb = Keybind::parse_command("docker compose up -d").unwrap();
+        let mut matcher = Matcher::new(vec![kb], KeyboardMode::Command).with_line_editing(true);
+
+        // A typo doesn't fail the line, and can be corrected
+        assert!(matches!(
+            type_str(&mut matcher, "docker compse"),
+            MatchState::Mistyped(_)
+        ));
+        let backspace = make_event(KeyCode::Backspace, KeyModifiers::NONE);
+        for _ in 0..3 {
+            matcher.process(backspace);
+            assert_eq!(matcher.position(), None);
+        }
+        assert!(matches!(
+            type_str(&mut matcher, "pose up"),
+            MatchState::InProgress(_)
+        ));
+        // Typed keys land where the line had got to after editing
+        assert_eq!(matcher.position(), Some("docker compose up".len() - 1));
+
+        // Ctrl+W deletes the last word, Ctrl+U the whole line
+        matcher.process(make_event(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(
+            matcher.state().typed_chords().len(),
+            "docker compose ".len()
+        );
+        matcher.process(make_event(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        assert!(matcher.state().typed_chords().is_empty());
+        assert_eq!(matcher.position(), None);
+
+        let enter = make_event(KeyCode::Enter, KeyModifiers::NONE);
+        type_str(&mut matcher, "docker compose up -d");
+        assert!(matcher.process(enter).is_complete());
+        assert_eq!(matcher.mistakes(), 1);
+    }
+
+    #[test]
+    fn test_line_editing_judged_on_enter() {
+        let kb = Keybind::parse_command("ls -la").unwrap();
+        let mut matcher = Matcher::new(vec![kb], KeyboardMode::Command).with_line_editing(true);
+        let enter = make_event(KeyCode::Enter, KeyModifiers::NONE);
+
+        // Enter on an empty line is ignored
+        assert!(matches!(matcher.process(enter), MatchState::InProgress(_)));
+        type_str(&mut matcher, "ls -l");
+        assert!(matcher.process(enter).is_failed());
+
+        // Without line editing the first wrong key fails
+        let kb = Keybind::parse_command("ls -la").unwrap();
+        let mut matcher = Matcher::new(vec![kb], KeyboardMode::Command);
+        assert!(type_str(&mut matcher, "lx").is_failed());
+    }
+
+    #[test]
+    fn test_command_placeholders() {
+        let kb = Keybind::parse_command("git checkout -b {branch}").unwrap();
+        let enter = make_event(KeyCode::Enter, KeyModifiers::NONE);
+
+        let mut matcher =
+            Matcher::new(vec![kb.clone()], KeyboardMode::Command).with_placeholders(true);
+        assert!(matches!(
+            type_str(&mut matcher, "git checkout -b feature/x"),
+            MatchState::InProgress(_)
+        ));
+        assert!(matcher.process(enter).is_complete());
+
+        // Quoted arguments can contain spaces
+        let kb_msg = Keybind::parse_command("git commit -m <message>").unwrap();
+        let mut matcher = Matcher::new(vec![kb_msg], KeyboardMode::Command).with_placeholders(true);
+        type_str(&mut matcher, "git commit -m \"fix the bug\"");
+        assert!(matcher.process(enter).is_complete());
+
+        // The argument can't be empty, or be two words
+        let mut matcher =
+            Matcher::new(vec![kb.clone()], KeyboardMode::Command).with_placeholders(true);
+        type_str(&mut matcher, "git checkout -b ");
+        assert!(matcher.process(enter).is_failed());
+        let mut matcher =
+            Matcher::new(vec![kb.clone()], KeyboardMode::Command).with_placeholders(true);
+        assert!(type_str(&mut matcher, "git checkout -b a b").is_failed());
+
+        // Without `# placeholders: on` the braces are typed as written
+        let mut matcher = Matcher::new(vec![kb], KeyboardMode::Command);
+        assert!(type_str(&mut matcher, "git checkout -b f").is_failed());
+        let awk = Keybind::parse_command("awk '{print}' file").unwrap();
+        let mut matcher = Matcher::new(vec![awk], KeyboardMode::Command);
+        type_str(&mut matcher, "awk '{print}' file");
+        assert!(matcher.process(enter).is_complete());
+        let find = Keybind::parse_command("find . -name <file> -exec rm {} \\;").unwrap();
+        let mut matcher = Matcher::new(vec![find], KeyboardMode::Command);
+        type_str(&mut matcher, "find . -name <file> -exec rm {} \\;");
+        assert!(matcher.process(enter).is_complete());
+    }
+
+    #[test]
+    fn test_shell_mismatch() {
+        assert_eq!(shell_mismatch("ls -la", "ls -al", true), None);
+        assert_eq!(shell_mismatch("ls -la", "ls -l -a", true), None);
+        assert_eq!(
+            shell_mismatch("tar -xzf {archive}", "tar -zxf backup.tgz", true),
+            None
+        );
+        assert_eq!(
+            shell_mismatch(
+                "git log --oneline --graph -n 5",
+                "git log --graph -n 5 --oneline",
+                true
+            ),
+            None
+        );
+        assert_eq!(
+            shell_mismatch(
+                "git commit -m <message>",
+                "git commit -m 'fix the bug'",
+                true
+            ),
+            None
+        );
+
+        assert_eq!(
+            shell_mismatch("ls -la", "ls -lx", true).as_deref(),
+            Some("Unexpected flag -x in -lx")
+        );
+        assert_eq!(
+            shell_mismatch("ls -la", "ls -l", true).as_deref(),
+            Some("Missing a flag")
+        );
+        assert_eq!(
+            shell_mismatch("cp -r src dest", "cp -r dest src", true).as_deref(),
+            Some("Wrong argument: dest")
+        );
+        assert_eq!(
+            shell_mismatch("git log --oneline", "git log --online", true).as_deref(),
+            Some("Unexpected flag: --online")
+        );
+        assert_eq!(
+            shell_mismatch("docker compose up -d", "docker compose -d", true).as_deref(),
+            Some("Missing an argument")
+        );
+
+        // A lone flag's placeholder value moves with it
+        assert_eq!(
+            shell_mismatch("ssh -i {key} -p {port}", "ssh -p 22 -i key", true),
+            None
+        );
+        assert_eq!(
+            shell_mismatch(
+                "ssh -i {key} -p 22 {host}",
+                "ssh -p 22 host -i id_rsa",
+                true
+            ),
+            None
+        );
+        assert_eq!(
+            shell_mismatch("ssh -i {key} -p 22", "ssh -p 22 -i", true).as_deref(),
+            Some("Missing an argument")
+        );
+        assert_eq!(
+            shell_mismatch("ssh -i {key} -p {port}", "ssh -ip key 22", true).as_deref(),
+            Some("Missing an argument")
+        );
+        assert_eq!(shell_mismatch("ls -la dir", "ls -al dir", true), None);
+
+        // Any other word after a flag is an ordinary argument
+        assert_eq!(shell_mismatch("ls -l dir", "ls dir -l", true), None);
+        assert_eq!(shell_mismatch("rm -r build", "rm build -r", true), None);
+        assert_eq!(
+            shell_mismatch("grep -i foo file", "grep foo -i file", true),
+            None
+        );
+        assert_eq!(shell_mismatch("ls -l -a dir", "ls -la dir", true), None);
+        assert_eq!(shell_mismatch("ls -l -a dir", "ls -al dir", true), None);
+        assert_eq!(
+            shell_mismatch("awk '{print}' {file}", "awk '{print}' {file}", false),
+            None
+        );
+        assert_eq!(
+            shell_mismatch("awk '{print}' {file}", "awk '{print}' data.txt", false).as_deref(),
+            Some("Wrong argument: data.txt")
+        );
+
+        // Flags known to take a value keep a literal value with them
+        assert_eq!(
+            shell_mismatch("ssh -i key -p 22", "ssh -p 22 -i key", true),
+            None
+        );
+        assert_eq!(
+            shell_mismatch("ssh -p 22 -i key", "ssh -i key -p 22", true),
+            None
+        );
+        assert_eq!(
+            shell_mismatch("ssh -p 22 -i key", "ssh -i key 22 -p", true).as_deref(),
+            Some("Missing an argument")
+        );
+        assert_eq!(
+            shell_mismatch("ssh -i key -p 22", "ssh -p key -i 22", true).as_deref(),
+            Some("Wrong argument: key")
+        );
+        assert_eq!(
+            shell_mismatch("/usr/bin/ssh -p 22 host", "/usr/bin/ssh host -p 22", true),
+            None
+        );
+    }
+
+    #[test]
+    fn test_shell_matching() {
+        let kb = Keybind::parse_command("ls -la").unwrap();
+        let enter = make_event(KeyCode::Enter, KeyModifiers::NONE);
+
+        let mut matcher =
+            Matcher::new(vec![kb.clone()], KeyboardMode::Command).with_shell_matching(true);
+        assert!(matches!(
+            type_str(&mut matcher, "ls -al"),
+            MatchState::InProgress(_)
+        ));
+        assert!(matcher.process(enter).is_complete());
+
+        let mut matcher = Matcher::new(vec![kb], KeyboardMode::Command).with_shell_matching(true);
+        type_str(&mut matcher, "ls -ax");
+        assert!(matcher.process(enter).is_failed());
+        assert_eq!(matcher.hint(), Some("Unexpected flag -x in -ax"));
+
+        // The hint stays up through the retry until typing resumes
+        matcher.reset();
+        assert!(matcher.hint().is_some());
+        type_str(&mut matcher, "l");
+        assert_eq!(matcher.hint(), None);
+    }
 }
@@ -0,0 +1,478 @@
+use crate::keybind::{Chord, Keybind, char_chord, format_key_code};
+use anyhow::{Result, bail};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+/// macOS modifier symbols, in the order Apple prints them
+const MAC_MODIFIERS: &[(char, KeyModifiers)] = &[
+    ('⌃', KeyModifiers::CONTROL),
+    ('⌥', KeyModifiers::ALT),
+    ('⇧', KeyModifiers::SHIFT),
+    ('⌘', KeyModifiers::SUPER),
+];
+
+/// macOS key symbols
+const MAC_KEYS: &[(char, KeyCode)] = &[
+    ('↩', KeyCode::Enter),
+    ('⏎', KeyCode::Enter),
+    ('⌤', KeyCode::Enter),
+    ('⌫', KeyCode::Backspace),
+    ('⌦', KeyCode::Delete),
+    ('⎋', KeyCode::Esc),
+    ('⇥', KeyCode::Tab),
+    ('⇤', KeyCode::BackTab),
+    ('␣', KeyCode::Char(' ')),
+    ('↑', KeyCode::Up),
+    ('↓', KeyCode::Down),
+    ('←', KeyCode::Left),
+    ('→', KeyCode::Right),
+    ('↖', KeyCode::Home),
+    ('↘', KeyCode::End),
+    ('⇞', KeyCode::PageUp),
+    ('⇟', KeyCode::PageDown),
+];
+
+/// A way of writing keybinds. Chords parse to the same `KeyEvent` whichever
+/// notation they're written in, so the stored (canonical) keybind doesn't change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Notation {
+    /// kbsr's own `Ctrl+Shift+K`, with `Cmd` and `Option` accepted as modifiers
+    #[default]
+    Standard,
+    /// Vim's `<C-S-k>`, where `gg` is two keys
+    Vim,
+    /// `C-x C-s`, `M-x`, `s-<return>`
+    Emacs,
+    /// `⌘⇧P`, `⌃⌥←`
+    #[serde(alias = "macos")]
+    Mac,
+}
+
+impl Notation {
+    /// Parse a notation name as used in the `# notation:` directive
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "standard" | "kbsr" | "default" => Some(Notation::Standard),
+            "vim" | "nvim" | "neovim" => Some(Notation::Vim),
+            "emacs" => Some(Notation::Emacs),
+            "mac" | "macos" | "apple" => Some(Notation::Mac),
+            _ => None,
+        }
+    }
+
+    /// Format a chord for display, e.g. Ctrl+Shift+K as `⌃⇧K`, `C-S-k` or `<C-S-k>`
+    pub fn format_chord(&self, chord: &Chord) -> String {
+        match self {
+            Notation::Standard => chord.to_string(),
+            Notation::Vim => format_vim(chord),
+            Notation::Emacs => format_emacs(chord),
+            Notation::Mac => format_mac(chord),
+        }
+    }
+
+    /// Format a keybind for display. Vim runs its chords together (`<C-w>v`, `gg`),
+    /// the others separate them with spaces.
+    pub fn format_keybind(&self, keybind: &Keybind) -> String {
+        let separator = if *self == Notation::Vim { "" } else { " " };
+        keybind
+            .0
+            .iter()
+            .map(|chord| self.format_chord(chord))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    /// Guess the notation of a single space-separated token
+    pub fn detect(token: &str) -> Self {
+        let bytes = token.as_bytes();
+        if token
+            .chars()
+            .any(|c| MAC_MODIFIERS.iter().any(|(s, _)| *s == c) || mac_key(c).is_some())
+        {
+            Notation::Mac
+        } else if bytes.len() > 2 && b"CMSsHA".contains(&bytes[0]) && bytes[1] == b'-'
+            || matches!(token, "RET" | "SPC")
+        {
+            Notation::Emacs
+        } else if token.starts_with('<') && token.find('>').is_some_and(|end| end > 1) {
+            Notation::Vim
+        } else {
+            Notation::Standard
+        }
+    }
+
+    /// Parse one space-separated token into chords. Only Vim notation can
+    /// produce more than one chord from a token (`<C-w>v`, `gg`).
+    pub fn parse_token(&self, token: &str) -> Result<Vec<Chord>> {
+        match self {
+            Notation::Standard => Ok(vec![Chord::parse_standard(token)?]),
+            Notation::Vim => parse_vim(token),
+            Notation::Emacs => Ok(vec![parse_emacs(token)?]),
+            Notation::Mac => Ok(vec![parse_mac(token)?]),
+        }
+    }
+}
+
+/// The character to show for a chord, lowercased where the notation writes
+/// the modifier instead (`C-k` rather than `C-K`)
+fn display_char(chord: &Chord, c: char) -> char {
+    if chord
+        .0
+        .modifiers
+        .intersects(KeyModifiers::CONTROL | KeyModifiers::SHIFT)
+    {
+        c.to_ascii_lowercase()
+    } else {
+        c
+    }
+}
+
+fn format_vim(chord: &Chord) -> String {
+    let modifiers = chord.0.modifiers;
+    let key = match chord.0.code {
+        KeyCode::Char('<') => "lt".to_string(),
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) if modifiers.is_empty() => return c.to_string(),
+        KeyCode::Char(c) => display_char(chord, c).to_string(),
+        KeyCode::Enter => "CR".to_string(),
+        KeyCode::Backspace => "BS".to_string(),
+        KeyCode::Delete => "Del".to_string(),
+        KeyCode::BackTab => "S-Tab".to_string(),
+        code => format_key_code(&code),
+    };
+
+    let mut prefix = String::new();
+    for (modifier, name) in [
+        (KeyModifiers::CONTROL, "C-"),
+        (KeyModifiers::ALT, "M-"),
+        (KeyModifiers::SHIFT, "S-"),
+        (KeyModifiers::SUPER, "D-"),
+        (KeyModifiers::META, "T-"),
+        (KeyModifiers::HYPER, "H-"),
+    ] {
+        if modifiers.contains(modifier) {
+            prefix.push_str(name);
+        }
+    }
+    format!("<{}{}>", prefix, key)
+}
+
+fn format_emacs(chord: &Chord) -> String {
+    let key = match chord.0.code {
+        KeyCode::Char(' ') => "SPC".to_string(),
+        KeyCode::Char(c) => display_char(chord, c).to_string(),
+        KeyCode::Enter => "RET".to_string(),
+        KeyCode::Tab => "TAB".to_string(),
+        KeyCode::Esc => "ESC".to_string(),
+        KeyCode::Backspace => "DEL".to_string(),
+        KeyCode::PageUp => "<prior>".to_string(),
+        KeyCode::PageDown => "<next>".to_string(),
+        code => format!("<{}>", format_key_code(&code).to_lowercase()),
+    };
+
+    let mut prefix = String::new();
+    for (modifier, name) in [
+        (KeyModifiers::CONTROL, "C-"),
+        (KeyModifiers::ALT | KeyModifiers::META, "M-"),
+        (KeyModifiers::SHIFT, "S-"),
+        (KeyModifiers::SUPER, "s-"),
+        (KeyModifiers::HYPER, "H-"),
+    ] {
+        if chord.0.modifiers.intersects(modifier) {
+            prefix.push_str(name);
+        }
+    }
+    prefix + &key
+}
+
+fn format_mac(chord: &Chord) -> String {
+    let modifiers = chord.0.modifiers;
+    let key = match chord.0.code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        // Apple writes shortcut letters in uppercase
+        KeyCode::Char(c) if !modifiers.is_empty() => c.to_uppercase().to_string(),
+        code => match MAC_KEYS
+            .iter()
+            .find(|(_, key)| *key == code && *key != KeyCode::Char(' '))
+        {
+            Some((symbol, _)) => symbol.to_string(),
+            None => format_key_code(&code),
+        },
+    };
+
+    let mut prefix = String::new();
+    for (modifier, name) in [
+        (KeyModifiers::META, "Meta+"),
+        (KeyModifiers::HYPER, "Hyper+"),
+    ] {
+        if modifiers.contains(modifier) {
+            prefix.push_str(name);
+        }
+    }
+    for (symbol, modifier) in MAC_MODIFIERS {
+        if modifiers.contains(*modifier) {
+            prefix.push(*symbol);
+        }
+    }
+    prefix + &key
+}
+
+/// `<C-w>v`, `<Space>ff`, `<F5>`: bracketed keys and single characters
+fn parse_vim(token: &str) -> Result<Vec<Chord>> {
+    let mut chords = Vec::new();
+    let mut rest = token;
+    while let Some(c) = rest.chars().next() {
+        if c == '<'
+            && let Some(end) = rest.find('>')
+            && end > 1
+        {
+            let name = &rest[1..end];
+            match parse_vim_key(name) {
+                Some(chord) => chords.push(chord),
+                None if name.chars().all(|c| c.is_alphanumeric() || c == '-') => {
+                    bail!("Unknown key: <{}>", name)
+                }
+                // `<<` and the like: a literal `<`
+                None => {
+                    chords.push(char_chord(c, KeyModifiers::NONE));
+                    rest = &rest[1..];
+                    continue;
+                }
+            }
+            rest = &rest[end + 1..];
+        } else {
+            chords.push(char_chord(c, KeyModifiers::NONE));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    Ok(chords)
+}
+
+/// Parse the inside of a Vim `<...>`: `C-w`, `M-S-x`, `CR`, `F5`, `lt`
+pub(crate) fn parse_vim_key(name: &str) -> Option<Chord> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut key = name;
+    while key.len() > 2 && key.as_bytes()[1] == b'-' {
+        modifiers |= match key.as_bytes()[0].to_ascii_uppercase() {
+            b'C' => KeyModifiers::CONTROL,
+            b'S' => KeyModifiers::SHIFT,
+            b'M' | b'A' => KeyModifiers::ALT,
+            b'D' | b'T' => KeyModifiers::SUPER,
+            _ => return None,
+        };
+        key = &key[2..];
+    }
+
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        // `<x>` is just x, so only modified characters are notation
+        return (!modifiers.is_empty()).then(|| char_chord(c, modifiers));
+    }
+    let code = bracket_key(key)?;
+    Some(Chord(KeyEvent::new(code, modifiers)))
+}
+
+/// `C-x`, `M-S-<left>`, `s-RET`: `C-` Ctrl, `M-`/`A-` Alt, `S-` Shift, `s-` Super, `H-` Hyper
+fn parse_emacs(token: &str) -> Result<Chord> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut key = token;
+    while key.len() > 2 && key.as_bytes()[1] == b'-' {
+        modifiers |= match key.as_bytes()[0] {
+            b'C' => KeyModifiers::CONTROL,
+            b'M' | b'A' => KeyModifiers::ALT,
+            b'S' => KeyModifiers::SHIFT,
+            b's' => KeyModifiers::SUPER,
+            b'H' => KeyModifiers::HYPER,
+            _ => bail!("Unknown Emacs modifier in chord: {}", token),
+        };
+        key = &key[2..];
+    }
+
+    let code = match key {
+        "RET" => KeyCode::Enter,
+        "SPC" => KeyCode::Char(' '),
+        "TAB" => KeyCode::Tab,
+        "ESC" => KeyCode::Esc,
+        // Emacs' DEL is the key above Enter
+        "DEL" => KeyCode::Backspace,
+        k if k.len() > 2 && k.starts_with('<') && k.ends_with('>') => {
+            let name = &k[1..k.len() - 1];
+            bracket_key(name).ok_or_else(|| anyhow::anyhow!("Unknown key: {}", k))?
+        }
+        k if k.chars().count() == 1 => {
+            return Ok(char_chord(k.chars().next().unwrap_or(' '), modifiers));
+        }
+        k => parse_key_name(k)?,
+    };
+    Ok(Chord(KeyEvent::new(code, modifiers)))
+}
+
+/// `⌘⇧P`, `⌥←`, `⌘+K`: modifier symbols followed by a key
+fn parse_mac(token: &str) -> Result<Chord> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut key = token;
+    while let Some(c) = key.chars().next() {
+        let Some((_, modifier)) = MAC_MODIFIERS.iter().find(|(s, _)| *s == c) else {
+            break;
+        };
+        modifiers |= *modifier;
+        key = &key[c.len_utf8()..];
+        // Allow `⌘+K` as well as `⌘K`, but keep a lone `+` as the key
+        if key.len() > 1 {
+            key = key.strip_prefix('+').unwrap_or(key);
+        }
+    }
+
+    let mut chars = key.chars();
+    let code = match (chars.next(), chars.next()) {
+        (None, _) => bail!("No key in chord: {}", token),
+        (Some(c), None) => match mac_key(c) {
+            Some(code) => code,
+            None => return Ok(char_chord(c, modifiers)),
+        },
+        _ => parse_key_name(key)?,
+    };
+    Ok(Chord(KeyEvent::new(code, modifiers)))
+}
+
+fn mac_key(c: char) -> Option<KeyCode> {
+    MAC_KEYS
+        .iter()
+        .find(|(s, _)| *s == c)
+        .map(|(_, code)| *code)
+}
+
+/// A named key as kbsr writes it (`Left`, `PageUp`, `F5`)
+fn parse_key_name(name: &str) -> Result<KeyCode> {
+    Ok(Chord::parse_standard(name)?.0.code)
+}
+
+/// Key names used inside angle brackets by Vim (`<CR>`, `<BS>`) and Emacs (`<return>`, `<prior>`)
+fn bracket_key(name: &str) -> Option<KeyCode> {
+    let code = match name.to_lowercase().as_str() {
+        "cr" | "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "bs" | "backspace" => KeyCode::Backspace,
+        "del" | "delete" | "deletechar" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" | "prior" => KeyCode::PageUp,
+        "pagedown" | "next" => KeyCode::PageDown,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "lt" => KeyCode::Char('<'),
+        "gt" => KeyCode::Char('>'),
+        "bar" => KeyCode::Char('|'),
+        "bslash" => KeyCode::Char('\\'),
+        k if k.len() > 1 && k.starts_with('f') && k[1..].chars().all(|c| c.is_ascii_digit()) => {
+            KeyCode::F(k[1..].parse().ok()?)
+        }
+        _ => return None,
+    };
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keybind::Keybind;
+
+    fn canonical(s: &str) -> String {
+        Keybind::parse(s).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Notation::detect("Ctrl+S"), Notation::Standard);
+        assert_eq!(Notation::detect("<"), Notation::Standard);
+        assert_eq!(Notation::detect("<C-x>"), Notation::Vim);
+        assert_eq!(Notation::detect("C-x"), Notation::Emacs);
+        assert_eq!(Notation::detect("RET"), Notation::Emacs);
+        assert_eq!(Notation::detect("⌘⇧P"), Notation::Mac);
+        assert_eq!(Notation::detect("Cmd+Option+P"), Notation::Standard);
+    }
+
+    #[test]
+    fn test_parse_auto_detected() {
+        assert_eq!(canonical("<C-x>"), "Ctrl+X");
+        assert_eq!(canonical("<C-w>v"), "Ctrl+W v");
+        assert_eq!(canonical("<M-S-Left>"), "Alt+Shift+Left");
+        assert_eq!(canonical("C-x C-s"), "Ctrl+X Ctrl+S");
+        assert_eq!(canonical("M-x"), "Alt+x");
+        assert_eq!(canonical("C-M-<return>"), "Ctrl+Alt+Enter");
+        assert_eq!(canonical("s-RET"), "Super+Enter");
+        assert_eq!(canonical("C--"), "Ctrl+-");
+        assert_eq!(canonical("⌘⇧P"), "Shift+Super+P");
+        assert_eq!(canonical("⌥←"), "Alt+Left");
+        assert_eq!(canonical("⌘+K ⌘+C"), "Super+K Super+C");
+        assert_eq!(canonical("Cmd+Option+P"), "Alt+Super+P");
+
+        // Both notations parse to the same chord as the standard one
+        assert_eq!(
+            Keybind::parse("C-x C-s").unwrap(),
+            Keybind::parse("Ctrl+x Ctrl+s").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let format = |s: &str, n: Notation| n.format_keybind(&Keybind::parse(s).unwrap());
+        assert_eq!(format("Ctrl+Shift+K", Notation::Mac), "⌃⇧K");
+        assert_eq!(format("Ctrl+Shift+K", Notation::Emacs), "C-S-k");
+        assert_eq!(format("Ctrl+Shift+K", Notation::Vim), "<C-S-k>");
+        assert_eq!(format("Ctrl+Shift+K", Notation::Standard), "Ctrl+Shift+K");
+
+        assert_eq!(format("Ctrl+w v", Notation::Vim), "<C-w>v");
+        assert_eq!(format("g g", Notation::Vim), "gg");
+        assert_eq!(format("Space <", Notation::Vim), "<Space><lt>");
+        assert_eq!(format("Ctrl+x Ctrl+s", Notation::Emacs), "C-x C-s");
+        assert_eq!(
+            format("Alt+Enter Ctrl+PageUp", Notation::Emacs),
+            "M-RET C-<prior>"
+        );
+        assert_eq!(format("Super+Alt+Left", Notation::Mac), "⌥⌘←");
+        assert_eq!(format("G $", Notation::Mac), "G $");
+
+        // Each display notation parses back to the same keybind
+        for s in [
+            "Ctrl+Shift+K",
+            "Ctrl+w v",
+            "Alt+Enter",
+            "Super+Tab",
+            "Ctrl+Alt+Delete",
+        ] {
+            let keybind = Keybind::parse(s).unwrap();
+            for n in [Notation::Vim, Notation::Emacs, Notation::Mac] {
+                let written = n.format_keybind(&keybind);
+                let parsed = Keybind::parse_with(&written, Some(n)).unwrap();
+                assert_eq!(
+                    parsed.to_string().to_lowercase(),
+                    s.to_lowercase(),
+                    "{}",
+                    written
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_explicit_notation() {
+        let parse = |s: &str, n: Notation| Keybind::parse_with(s, Some(n)).unwrap().to_string();
+        assert_eq!(parse("gg", Notation::Vim), "g g");
+        assert_eq!(parse("<Space>ff", Notation::Vim), "Space f f");
+        assert_eq!(parse("<<", Notation::Vim), "< <");
+        assert_eq!(parse("C-x 4 f", Notation::Emacs), "Ctrl+X 4 f");
+        assert_eq!(parse("M-DEL", Notation::Emacs), "Alt+Backspace");
+        assert_eq!(parse("C-<prior>", Notation::Emacs), "Ctrl+PageUp");
+
+        assert!(Keybind::parse_with("<leader>f", Some(Notation::Vim)).is_err());
+        assert!(Keybind::parse_with("Q-x", Some(Notation::Emacs)).is_err());
+        assert!(Keybind::parse_with("⌘", Some(Notation::Mac)).is_err());
+    }
+}
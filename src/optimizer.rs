@@ -0,0 +1,215 @@
+use crate::config::Config;
+use crate::storage::{Review, Storage};
+use crate::study_day::StudyDay;
+use anyhow::{Result, bail};
+use fsrs::{
+    ComputeParametersInput, DEFAULT_PARAMETERS, FSRS, FSRSItem, FSRSReview, ModelEvaluation,
+};
+
+/// Outcome of fitting FSRS parameters to the review history
+pub struct OptimizeReport {
+    pub review_count: usize,
+    pub item_count: usize,
+    /// Evaluation of the parameters in use before optimizing
+    pub before: ModelEvaluation,
+    /// Evaluation of the newly fitted parameters
+    pub after: ModelEvaluation,
+    pub parameters: Vec<f32>,
+    /// Whether the new parameters were stored (only if they beat the old ones)
+    pub saved: bool,
+}
+
+/// Build FSRS training items from reviews sorted by card, then time.
+/// Each review after a card's first becomes an item holding that card's history
+/// up to and including it. Items without a review on a later day are dropped,
+/// since FSRS can't learn long-term memory from same-day repetitions alone.
+/// Days between reviews are counted in study days, as the scheduler counts them.
+pub fn build_training_items(reviews: &[Review], day: StudyDay) -> Vec<FSRSItem> {
+    let mut items = Vec::new();
+
+    for card_reviews in reviews.chunk_by(|a, b| a.card_id == b.card_id) {
+        let mut history: Vec<FSRSReview> = Vec::new();
+        let mut previous: Option<&Review> = None;
+
+        for review in card_reviews {
+            if !(1..=4).contains(&review.rating) {
+                continue;
+            }
+            let delta_t = match previous {
+                Some(prev) => day.days_between(prev.reviewed_at, review.reviewed_at),
+                None => 0,
+            };
+            history.push(FSRSReview {
+                rating: review.rating as u32,
+                delta_t,
+            });
+            previous = Some(review);
+
+            if history.len() > 1 {
+                let item = FSRSItem {
+                    reviews: history.clone(),
+                };
+                if item.long_term_review_cnt() > 0 {
+                    items.push(item);
+                }
+            }
+        }
+    }
+
+    items
+}
+
+/// Fit FSRS parameters to the stored review history.
+/// The new parameters are saved only if they predict the history better
+/// (lower log loss) than the ones currently in use.
+pub fn optimize(storage: &Storage) -> Result<OptimizeReport> {
+    let reviews = storage.get_all_reviews()?;
+    let items = build_training_items(&reviews, storage.study_day());
+
+    if items.is_empty() {
+        bail!(
+            "Not enough review history to optimize ({} reviews). Cards need reviews on more than one day.",
+            reviews.len()
+        );
+    }
+
+    let current = storage
+        .get_fsrs_parameters()?
+        .unwrap_or_else(|| DEFAULT_PARAMETERS.to_vec());
+    let before = FSRS::new(Some(&current))?.evaluate(items.clone(), |_| true)?;
+
+    let parameters = FSRS::new(None)?.compute_parameters(ComputeParametersInput {
+        train_set: items.clone(),
+        ..Default::default()
+    })?;
+    let item_count = items.len();
+    let after = FSRS::new(Some(&parameters))?.evaluate(items, |_| true)?;
+
+    let saved = after.log_loss < before.log_loss;
+    if saved {
+        storage.set_fsrs_parameters(&parameters)?;
+    }
+
+    Ok(OptimizeReport {
+        review_count: reviews.len(),
+        item_count,
+        before,
+        after,
+        parameters,
+        saved,
+    })
+}
+
+/// Run `kbsr optimize`: fit parameters and print a before/after report
+pub fn run(config: &Config) -> Result<()> {
+    config.ensure_dirs()?;
+    let storage = Storage::open(&config.db_path)?.with_study_day(config.study_day());
+
+    println!("Optimizing FSRS parameters from review history...");
+    let report = optimize(&storage)?;
+
+    println!(
+        "Trained on {} reviews ({} items)",
+        report.review_count, report.item_count
+    );
+    println!();
+    println!("{:<10}{:>10}{:>10}", "", "Log loss", "RMSE");
+    println!(
+        "{:<10}{:>10.4}{:>10.4}",
+        "Before", report.before.log_loss, report.before.rmse_bins
+    );
+    println!(
+        "{:<10}{:>10.4}{:>10.4}",
+        "After", report.after.log_loss, report.after.rmse_bins
+    );
+    println!();
+
+    if report.saved {
+        let parameters: Vec<String> = report
+            .parameters
+            .iter()
+            .map(|p| format!("{:.4}", p))
+            .collect();
+        println!("Saved parameters: {}", parameters.join(", "));
+    } else {
+        println!("New parameters did not improve on the current ones; keeping current parameters.");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Duration, Utc};
+
+    fn review(card_id: i64, rating: i32, reviewed_at: DateTime<Utc>) -> Review {
+        Review {
+            id: 0,
+            card_id,
+            rating,
+            response_time_ms: 1000,
+            attempts: 1,
+            reviewed_at,
+        }
+    }
+
+    #[test]
+    fn test_build_training_items() {
+        let day = StudyDay::default();
+        let start = day.start_of(day.today() - Duration::days(10)) + Duration::hours(1);
+        let reviews = vec![
+            review(1, 3, start),
+            review(1, 3, start + Duration::days(2)),
+            review(1, 1, start + Duration::days(5)),
+            review(2, 4, start),
+        ];
+
+        let items = build_training_items(&reviews, day);
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0].reviews,
+            vec![
+                FSRSReview {
+                    rating: 3,
+                    delta_t: 0
+                },
+                FSRSReview {
+                    rating: 3,
+                    delta_t: 2
+                },
+            ]
+        );
+        assert_eq!(items[1].reviews.len(), 3);
+        assert_eq!(items[1].reviews[2].delta_t, 3);
+        assert_eq!(items[1].reviews[2].rating, 1);
+    }
+
+    #[test]
+    fn test_build_training_items_skips_same_day_only() {
+        let day = StudyDay::default();
+        let start = day.today_start() + Duration::hours(1);
+        let reviews = vec![
+            review(1, 3, start),
+            review(1, 4, start + Duration::hours(2)),
+        ];
+
+        assert!(build_training_items(&reviews, day).is_empty());
+    }
+
+    #[test]
+    fn test_build_training_items_counts_study_days() {
+        // Late in one study day and early in the next is a day apart,
+        // though less than 24 hours have passed
+        let day = StudyDay::new(4);
+        let start = day.start_of(day.today() - Duration::days(10));
+        let reviews = vec![
+            review(1, 3, start + Duration::hours(22)),
+            review(1, 3, start + Duration::hours(26)),
+        ];
+
+        let items = build_training_items(&reviews, day);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].reviews[1].delta_t, 1);
+    }
+}
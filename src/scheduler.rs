@@ -1,5 +1,6 @@
+use crate::study_day::StudyDay;
 use anyhow::Result;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Utc};
 use fsrs::{DEFAULT_PARAMETERS, FSRS, MemoryState, NextStates};
 
 /// Rating derived from response time and attempts
@@ -58,20 +59,26 @@ pub struct Scheduler {
     desired_retention: f32,
     interval_modifier: f32,
     max_interval_days: f32,
+    day: StudyDay,
 }
 
 impl Scheduler {
-    /// Create a new scheduler with desired retention rate (0.0 - 1.0)
+    /// Create a new scheduler with desired retention rate (0.0 - 1.0).
+    /// Uses personalised FSRS parameters when given, otherwise the FSRS defaults.
+    /// Intervals of a day or more are due from the start of a study day.
     pub fn new(
+        parameters: Option<&[f32]>,
         desired_retention: f32,
         interval_modifier: f32,
         max_interval_days: f32,
+        day: StudyDay,
     ) -> Result<Self> {
         Ok(Self {
-            fsrs: FSRS::new(Some(&DEFAULT_PARAMETERS))?,
+            fsrs: FSRS::new(Some(parameters.unwrap_or(&DEFAULT_PARAMETERS)))?,
             desired_retention,
             interval_modifier,
             max_interval_days,
+            day,
         })
     }
 
@@ -82,11 +89,9 @@ impl Scheduler {
         memory_state: Option<MemoryState>,
         last_review: Option<DateTime<Utc>>,
     ) -> Result<NextStates> {
+        // Counted in study days, as due dates are and as the optimizer trains on
         let elapsed_days: u32 = match last_review {
-            Some(last) => {
-                let duration = Utc::now().signed_duration_since(last);
-                duration.num_days().max(0) as u32
-            }
+            Some(last) => self.day.days_between(last, Utc::now()),
             None => 0,
         };
 
@@ -117,8 +122,7 @@ impl Scheduler {
         // Calculate due date from interval, applying modifier and cap
         let interval_days =
             (item_state.interval * self.interval_modifier).min(self.max_interval_days);
-        let due_date =
-            Utc::now() + Duration::seconds((interval_days * 86400.0) as i64) + Duration::hours(1);
+        let due_date = self.day.due_date(Utc::now(), interval_days);
 
         Ok((item_state.memory, due_date))
     }
@@ -211,7 +215,7 @@ mod tests {
 
     #[test]
     fn test_schedule_new_card() {
-        let scheduler = Scheduler::new(0.9, 0.12, 30.0).unwrap();
+        let scheduler = Scheduler::new(None, 0.9, 0.12, 30.0, StudyDay::default()).unwrap();
         let (memory, due) = scheduler.schedule(None, None, Rating::Good).unwrap();
 
         assert!(memory.stability > 0.0);
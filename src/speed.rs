@@ -0,0 +1,253 @@
+use crate::deck::KeyboardMode;
+use crate::notation::Notation;
+use crate::storage::{Storage, TimedReview};
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Most recent reviews used to fit the model, so it follows the user as they speed up
+pub const HISTORY_LIMIT: usize = 500;
+
+/// Reviews of a kind needed before its model replaces the fixed thresholds
+pub const MIN_SAMPLES: usize = 20;
+
+/// Share of the slowest answers (by time per chord) left out of the fit.
+/// These are mostly hesitations over recall, not typing speed.
+const TRIM_SLOWEST: f64 = 0.1;
+
+/// Answers faster than this share of the expected time are rated Easy
+const EASY_RATIO: f64 = 0.8;
+
+/// Answers at least this share of the expected time are rated Hard
+const HARD_RATIO: f64 = 1.6;
+
+/// Expected response time as a linear function of answer length:
+/// a fixed reaction time plus a cost per chord (or keystroke, for commands)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeedModel {
+    pub reaction_ms: f64,
+    pub per_chord_ms: f64,
+}
+
+impl SpeedModel {
+    /// Least-squares fit to (chords, response ms) samples, or None with too few of them
+    pub fn fit(samples: &[(usize, u64)]) -> Option<Self> {
+        if samples.len() < MIN_SAMPLES {
+            return None;
+        }
+
+        let mut samples: Vec<(f64, f64)> = samples
+            .iter()
+            .map(|&(chords, ms)| (chords.max(1) as f64, ms as f64))
+            .collect();
+        samples.sort_by(|a, b| (a.1 / a.0).total_cmp(&(b.1 / b.0)));
+        let keep = samples.len() - (samples.len() as f64 * TRIM_SLOWEST) as usize;
+        samples.truncate(keep);
+
+        let n = samples.len() as f64;
+        let mean_x = samples.iter().map(|s| s.0).sum::<f64>() / n;
+        let mean_y = samples.iter().map(|s| s.1).sum::<f64>() / n;
+        let var_x: f64 = samples.iter().map(|s| (s.0 - mean_x).powi(2)).sum();
+        let cov: f64 = samples
+            .iter()
+            .map(|s| (s.0 - mean_x) * (s.1 - mean_y))
+            .sum();
+
+        // Every answer the same length: there's no telling reaction from typing time
+        if var_x == 0.0 {
+            return Some(SpeedModel {
+                reaction_ms: 0.0,
+                per_chord_ms: mean_y / mean_x,
+            });
+        }
+
+        let per_chord_ms = cov / var_x;
+        let reaction_ms = mean_y - per_chord_ms * mean_x;
+        let model = if per_chord_ms < 0.0 {
+            SpeedModel {
+                reaction_ms: mean_y,
+                per_chord_ms: 0.0,
+            }
+        } else if reaction_ms < 0.0 {
+            // Refit through the origin
+            let xy: f64 = samples.iter().map(|s| s.0 * s.1).sum();
+            let xx: f64 = samples.iter().map(|s| s.0 * s.0).sum();
+            SpeedModel {
+                reaction_ms: 0.0,
+                per_chord_ms: xy / xx,
+            }
+        } else {
+            SpeedModel {
+                reaction_ms,
+                per_chord_ms,
+            }
+        };
+        Some(model)
+    }
+
+    /// Expected response time for an answer of this many chords
+    pub fn expected_ms(&self, chords: usize) -> f64 {
+        self.reaction_ms + self.per_chord_ms * chords.max(1) as f64
+    }
+
+    /// Easy and Hard thresholds for an answer of this many chords
+    pub fn thresholds(&self, chords: usize) -> (u64, u64) {
+        let expected = self.expected_ms(chords);
+        (
+            (expected * EASY_RATIO).round() as u64,
+            (expected * HARD_RATIO).round() as u64,
+        )
+    }
+}
+
+/// Speed models fitted separately for chords (raw and chars decks) and
+/// keystrokes (command decks), since typing a word is much quicker per key
+#[derive(Debug, Clone, Default)]
+pub struct SpeedProfile {
+    chords: Option<SpeedModel>,
+    keystrokes: Option<SpeedModel>,
+}
+
+impl SpeedProfile {
+    /// Fit both models to the recent review history.
+    /// Reviews from decks with no deck file are left out, as their mode is unknown.
+    pub fn load(storage: &Storage, keyboard_modes: &HashMap<String, KeyboardMode>) -> Result<Self> {
+        let history = storage.get_timing_history(HISTORY_LIMIT)?;
+        Ok(Self::from_history(&history, keyboard_modes))
+    }
+
+    fn from_history(
+        history: &[TimedReview],
+        keyboard_modes: &HashMap<String, KeyboardMode>,
+    ) -> Self {
+        let mut chords = Vec::new();
+        let mut keystrokes = Vec::new();
+        for review in history {
+            let Some(&mode) = keyboard_modes.get(&review.deck) else {
+                continue;
+            };
+            let Some(count) = chord_count(review, mode) else {
+                continue;
+            };
+            let sample = (count, review.response_time_ms.max(0) as u64);
+            if mode == KeyboardMode::Command {
+                keystrokes.push(sample);
+            } else {
+                chords.push(sample);
+            }
+        }
+
+        SpeedProfile {
+            chords: SpeedModel::fit(&chords),
+            keystrokes: SpeedModel::fit(&keystrokes),
+        }
+    }
+
+    /// Easy and Hard thresholds for an answer in a deck of this mode,
+    /// or None if there isn't enough history for that kind of answer yet
+    pub fn thresholds(&self, mode: KeyboardMode, chords: usize) -> Option<(u64, u64)> {
+        let model = match mode {
+            KeyboardMode::Command => self.keystrokes,
+            KeyboardMode::Raw | KeyboardMode::Chars => self.chords,
+        };
+        model.map(|m| m.thresholds(chords))
+    }
+}
+
+/// Chords typed in a review. Older reviews didn't record it, so it's taken from
+/// the card, using the shortest alternative.
+fn chord_count(review: &TimedReview, mode: KeyboardMode) -> Option<usize> {
+    if let Some(chords) = review.chords {
+        return usize::try_from(chords).ok();
+    }
+    mode.parse_keybinds(&review.keybind, Some(Notation::Standard))
+        .ok()?
+        .iter()
+        .map(|kb| kb.0.len())
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Samples lying exactly on reaction + per_chord * chords
+    fn linear(reaction: u64, per_chord: u64, lengths: &[usize]) -> Vec<(usize, u64)> {
+        lengths
+            .iter()
+            .cycle()
+            .take(MIN_SAMPLES * 2)
+            .map(|&n| (n, reaction + per_chord * n as u64))
+            .collect()
+    }
+
+    #[test]
+    fn test_fit() {
+        let model = SpeedModel::fit(&linear(800, 300, &[1, 2, 3, 5])).unwrap();
+        assert!((model.reaction_ms - 800.0).abs() < 1.0);
+        assert!((model.per_chord_ms - 300.0).abs() < 1.0);
+        assert_eq!(model.thresholds(2), (1120, 2240));
+
+        // Too little history
+        assert_eq!(SpeedModel::fit(&linear(800, 300, &[1, 2])[..5]), None);
+
+        // Same-length answers put everything on the chord
+        let model = SpeedModel::fit(&linear(600, 400, &[2])).unwrap();
+        assert_eq!(model.reaction_ms, 0.0);
+        assert!((model.per_chord_ms - 700.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_fit_ignores_slowest() {
+        let mut samples = linear(800, 300, &[1, 2, 3]);
+        samples.push((1, 30000));
+        samples.push((2, 25000));
+        let model = SpeedModel::fit(&samples).unwrap();
+        assert!((model.reaction_ms - 800.0).abs() < 1.0);
+        assert!((model.per_chord_ms - 300.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_fit_clamps_negative() {
+        // Longer answers no faster: a flat expected time
+        let samples: Vec<(usize, u64)> = linear(0, 0, &[1, 4])
+            .into_iter()
+            .map(|(n, _)| (n, if n == 1 { 2000 } else { 1800 }))
+            .collect();
+        let model = SpeedModel::fit(&samples).unwrap();
+        assert_eq!(model.per_chord_ms, 0.0);
+        assert!(model.reaction_ms > 1800.0);
+    }
+
+    #[test]
+    fn test_profile() {
+        let modes = HashMap::from([
+            ("vim".to_string(), KeyboardMode::Chars),
+            ("shell".to_string(), KeyboardMode::Command),
+        ]);
+        let review = |deck: &str, keybind: &str, chords: Option<i64>, ms: i64| TimedReview {
+            deck: deck.to_string(),
+            keybind: keybind.to_string(),
+            chords,
+            response_time_ms: ms,
+        };
+        let mut history = Vec::new();
+        for _ in 0..MIN_SAMPLES {
+            // Legacy rows take their length from the card: 2 chords, and 2 chars + Enter
+            history.push(review("vim", "g g", None, 1400));
+            history.push(review("vim", "d d | Shift+D", Some(1), 1000));
+            history.push(review("shell", "ls", None, 900));
+            history.push(review("gone", "Ctrl+S", Some(1), 100));
+        }
+
+        let profile = SpeedProfile::from_history(&history, &modes);
+        assert_eq!(profile.thresholds(KeyboardMode::Raw, 3), Some((1440, 2880)));
+        assert_eq!(
+            profile.thresholds(KeyboardMode::Command, 3),
+            Some((720, 1440))
+        );
+
+        history.retain(|r| r.deck != "shell");
+        let profile = SpeedProfile::from_history(&history, &modes);
+        assert_eq!(profile.thresholds(KeyboardMode::Command, 3), None);
+    }
+}
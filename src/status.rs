@@ -0,0 +1,177 @@
+use crate::config::Config;
+use crate::limits::{cap_due_counts, read_deck_limits};
+use crate::storage::{DeckStats, Storage};
+use anyhow::{Result, bail};
+use std::collections::HashMap;
+
+/// Output format for `kbsr status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatusFormat {
+    /// Waybar custom module JSON (`return-type: json`)
+    #[default]
+    Waybar,
+    /// A single line of plain text
+    Text,
+    /// i3blocks/polybar line protocol: full text, short text, colour
+    I3blocks,
+}
+
+impl StatusFormat {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "waybar" | "json" => Ok(StatusFormat::Waybar),
+            "text" | "plain" | "polybar" => Ok(StatusFormat::Text),
+            "i3blocks" | "i3" => Ok(StatusFormat::I3blocks),
+            other => bail!(
+                "Unknown status format '{}'. Use 'waybar', 'text', or 'i3blocks'.",
+                other
+            ),
+        }
+    }
+}
+
+/// Colour used by i3blocks when cards are overdue
+const OVERDUE_COLOR: &str = "#E5C07B";
+
+/// Due counts read for a status poll
+pub struct Status {
+    /// Decks with cards due today, in name order
+    pub decks: Vec<DeckStats>,
+    /// Cards due today across all decks
+    pub due_today: i32,
+    /// Cards due right now across all decks
+    pub due_now: i32,
+}
+
+impl Status {
+    /// Read due counts without syncing decks, creating backups, or writing to the database
+    pub fn read(config: &Config) -> Result<Self> {
+        if !config.db_path.exists() {
+            return Ok(Status {
+                decks: Vec::new(),
+                due_today: 0,
+                due_now: 0,
+            });
+        }
+
+        let storage = Storage::open_read_only(&config.db_path)?.with_study_day(config.study_day());
+        let mut decks = storage.get_deck_stats(&HashMap::new())?;
+        cap_due_counts(
+            &mut decks,
+            &read_deck_limits(&config.decks_dir),
+            config.daily_limits(),
+            &storage.get_today_counts()?,
+        );
+        decks.retain(|d| d.due_cards > 0);
+
+        let due_today = decks.iter().map(|d| d.due_cards).sum();
+        Ok(Status {
+            due_today,
+            due_now: storage.count_due_now()?.min(due_today),
+            decks,
+        })
+    }
+
+    /// CSS class / state name: "overdue" if anything is due now, "due" if due later today, else "none"
+    pub fn class(&self) -> &'static str {
+        if self.due_now > 0 {
+            "overdue"
+        } else if self.due_today > 0 {
+            "due"
+        } else {
+            "none"
+        }
+    }
+
+    fn tooltip(&self) -> String {
+        if self.decks.is_empty() {
+            return "No cards due today".to_string();
+        }
+        self.decks
+            .iter()
+            .map(|d| format!("{}: {}", d.name, d.due_cards))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Render the status in the given format
+    pub fn render(&self, format: StatusFormat) -> String {
+        match format {
+            StatusFormat::Waybar => serde_json::json!({
+                "text": self.due_today.to_string(),
+                "tooltip": self.tooltip(),
+                "class": self.class(),
+            })
+            .to_string(),
+            StatusFormat::Text => format!("{} due", self.due_today),
+            StatusFormat::I3blocks => {
+                let mut lines = vec![
+                    format!("{} due", self.due_today),
+                    self.due_today.to_string(),
+                ];
+                if self.due_now > 0 {
+                    lines.push(OVERDUE_COLOR.to_string());
+                }
+                lines.join("\n")
+            }
+        }
+    }
+}
+
+/// `kbsr status`: print due counts for a status bar
+pub fn run(config: &Config, format: StatusFormat) -> Result<()> {
+    println!("{}", Status::read(config)?.render(format));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::KeyboardMode;
+
+    fn status(due_now: i32) -> Status {
+        let deck = |name: &str, due: i32| DeckStats {
+            name: name.to_string(),
+            total_cards: 10,
+            due_cards: due,
+            new_due_cards: 0,
+            keyboard_mode: KeyboardMode::Raw,
+        };
+        Status {
+            decks: vec![deck("git", 2), deck("vim", 3)],
+            due_today: 5,
+            due_now,
+        }
+    }
+
+    #[test]
+    fn test_render_waybar() {
+        let output: serde_json::Value =
+            serde_json::from_str(&status(1).render(StatusFormat::Waybar)).unwrap();
+        assert_eq!(output["text"], "5");
+        assert_eq!(output["tooltip"], "git: 2\nvim: 3");
+        assert_eq!(output["class"], "overdue");
+    }
+
+    #[test]
+    fn test_render_text_and_i3blocks() {
+        assert_eq!(status(0).render(StatusFormat::Text), "5 due");
+        assert_eq!(status(0).render(StatusFormat::I3blocks), "5 due\n5");
+        assert_eq!(
+            status(2).render(StatusFormat::I3blocks),
+            format!("5 due\n5\n{}", OVERDUE_COLOR)
+        );
+    }
+
+    #[test]
+    fn test_class() {
+        assert_eq!(status(1).class(), "overdue");
+        assert_eq!(status(0).class(), "due");
+        let empty = Status {
+            decks: Vec::new(),
+            due_today: 0,
+            due_now: 0,
+        };
+        assert_eq!(empty.class(), "none");
+    }
+}
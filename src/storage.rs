@@ -1,7 +1,8 @@
+use crate::study_day::{DueBy, StudyDay};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Local, Utc};
-use rusqlite::{Connection, params};
-use std::collections::HashSet;
+use rusqlite::{Connection, OpenFlags, params};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 /// Stored card state in the database
@@ -13,11 +14,15 @@ pub struct StoredCard {
     pub description: String,
     pub stability: Option<f32>,
     pub difficulty: Option<f32>,
-    #[allow(dead_code)] // Used in DB queries, will be used for UI stats
     pub due_date: Option<DateTime<Utc>>,
     pub last_review: Option<DateTime<Utc>>,
-    #[allow(dead_code)] // Used in DB, will be used for stats display
+    #[allow(dead_code)] // Kept in step with the reviews table; only tests read it
     pub review_count: i32,
+    /// Section from the deck's `# section:` directive
+    pub section: Option<String>,
+    pub tags: Vec<String>,
+    /// Reverse (recognition) card: show the keybind, pick the description
+    pub reverse: bool,
 }
 
 use crate::deck::KeyboardMode;
@@ -28,9 +33,37 @@ pub struct DeckStats {
     pub name: String,
     pub total_cards: i32,
     pub due_cards: i32,
+    /// Due cards that have never been reviewed
+    pub new_due_cards: i32,
     pub keyboard_mode: KeyboardMode,
 }
 
+/// Cards studied in a deck today, for the daily limits
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DayCounts {
+    /// Cards reviewed for the first time
+    pub new_cards: usize,
+    /// Reviews of cards seen before
+    pub reviews: usize,
+}
+
+/// Review history summary for a single deck
+#[derive(Debug, Clone)]
+pub struct DeckDetail {
+    pub total_cards: i32,
+    pub due_cards: i32,
+    /// Cards that have never been reviewed
+    pub new_cards: i32,
+    pub total_reviews: i32,
+    /// Share of reviews not rated Again (None if no reviews)
+    pub retention: Option<f64>,
+    pub avg_response_ms: Option<f64>,
+    /// Mean FSRS stability (days) across reviewed cards
+    pub avg_stability: Option<f64>,
+    /// Earliest due date among scheduled cards
+    pub next_due: Option<DateTime<Utc>>,
+}
+
 fn row_to_stored_card(row: &rusqlite::Row) -> rusqlite::Result<StoredCard> {
     Ok(StoredCard {
         id: row.get(0)?,
@@ -46,42 +79,327 @@ fn row_to_stored_card(row: &rusqlite::Row) -> rusqlite::Result<StoredCard> {
             .get::<_, Option<String>>(7)?
             .and_then(|s| s.parse().ok()),
         review_count: row.get(8)?,
+        section: row.get(9)?,
+        tags: split_tags(&row.get::<_, String>(10)?),
+        reverse: row.get(11)?,
     })
 }
 
-fn end_of_today_utc() -> String {
-    let today = Local::now().date_naive();
-    let end_of_today = today
-        .succ_opt()
-        .unwrap_or(today)
-        .and_hms_opt(0, 0, 0)
-        .unwrap();
-    end_of_today
-        .and_local_timezone(Local)
-        .unwrap()
-        .with_timezone(&Utc)
-        .to_rfc3339()
+/// Tags are stored space-separated (tags never contain whitespace)
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split_whitespace().map(str::to_string).collect()
+}
+
+/// A named subset of a deck's cards: a section or a tag
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CardGroup {
+    Section(String),
+    Tag(String),
+}
+
+impl CardGroup {
+    /// Whether a card belongs to this group
+    pub fn contains(&self, card: &StoredCard) -> bool {
+        match self {
+            CardGroup::Section(name) => card.section.as_ref() == Some(name),
+            CardGroup::Tag(tag) => card.tags.contains(tag),
+        }
+    }
+}
+
+/// Card counts for a section or tag within a deck
+#[derive(Debug, Clone)]
+pub struct GroupStats {
+    pub group: CardGroup,
+    pub total_cards: i32,
+    pub due_cards: i32,
+}
+
+/// Count one card towards a group, adding the group on first sight
+fn tally_group(groups: &mut Vec<GroupStats>, group: CardGroup, due: bool) {
+    let idx = match groups.iter().position(|g| g.group == group) {
+        Some(idx) => idx,
+        None => {
+            groups.push(GroupStats {
+                group,
+                total_cards: 0,
+                due_cards: 0,
+            });
+            groups.len() - 1
+        }
+    };
+    groups[idx].total_cards += 1;
+    groups[idx].due_cards += due as i32;
 }
 
 /// A review record (for FSRS parameter training)
 #[derive(Debug, Clone)]
-#[allow(dead_code)] // Struct used for future FSRS parameter optimization
 pub struct Review {
+    #[allow(dead_code)] // Not read: reviews are grouped by card_id and ordered by time
     pub id: i64,
     pub card_id: i64,
     pub rating: i32,
     pub response_time_ms: i64,
+    #[allow(dead_code)] // Not read: the rating already accounts for attempts
     pub attempts: i32,
     pub reviewed_at: DateTime<Utc>,
 }
 
+/// A first-try typed answer, for fitting the adaptive rating model
+#[derive(Debug, Clone)]
+pub struct TimedReview {
+    pub deck: String,
+    pub keybind: String,
+    /// Chords typed, or None for reviews recorded before chord counts were kept
+    pub chords: Option<i64>,
+    pub response_time_ms: i64,
+}
+
+/// A key pressed while answering a card, for hesitation and mistake stats
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyPress {
+    /// Index in the answer of the chord this key was typed as
+    pub position: usize,
+    /// The chord pressed, in standard notation
+    pub chord: String,
+    /// The chord the answer needed instead, for a wrong key
+    pub expected: Option<String>,
+    /// Time since the card was shown
+    pub elapsed_ms: u64,
+    pub correct: bool,
+}
+
+/// A recorded key press with the review it belongs to
+#[derive(Debug, Clone)]
+pub struct ReviewKey {
+    pub review_id: i64,
+    pub card_id: i64,
+    /// Whether the card was answered correctly on the first try
+    pub first_try: bool,
+    pub key: KeyPress,
+}
+
+/// How often a card has been mistyped in one particular way
+#[derive(Debug, Clone, PartialEq)]
+pub struct MistakeCount {
+    pub card_id: i64,
+    /// The kind of mistake, as named by `MistakeKind::name`
+    pub kind: String,
+    pub count: i64,
+}
+
+fn row_to_review(row: &rusqlite::Row) -> rusqlite::Result<Review> {
+    Ok(Review {
+        id: row.get(0)?,
+        card_id: row.get(1)?,
+        rating: row.get(2)?,
+        response_time_ms: row.get(3)?,
+        attempts: row.get(4)?,
+        reviewed_at: row.get::<_, String>(5)?.parse().map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+    })
+}
+
+/// Columns of the cards table. `reverse_of` is set on reverse (recognition) cards
+/// and holds the id of the forward card they were made from.
+const CARDS_COLUMNS: &str = "
+    id INTEGER PRIMARY KEY,
+    deck TEXT NOT NULL,
+    keybind TEXT NOT NULL,
+    description TEXT NOT NULL,
+    stability REAL,
+    difficulty REAL,
+    due_date TEXT,
+    last_review TEXT,
+    review_count INTEGER DEFAULT 0,
+    card_key TEXT,
+    section TEXT,
+    tags TEXT NOT NULL DEFAULT '',
+    reverse_of INTEGER
+";
+
+/// Settings key holding the comma-separated FSRS parameters fitted by `kbsr optimize`
+const FSRS_PARAMETERS_KEY: &str = "fsrs_parameters";
+
 pub struct Storage {
     conn: Connection,
+    day: StudyDay,
+}
+
+/// A card from a deck file to sync into the database
+pub struct SyncCard {
+    pub keybind: String,
+    pub description: String,
+    /// Stable identity from the deck's `id:` column
+    pub card_key: Option<String>,
+    /// Reset progress if the keybind or description changed
+    pub reset: bool,
+    pub section: Option<String>,
+    pub tags: Vec<String>,
+    /// Also keep a reverse (recognition) card for this card
+    pub reverse: bool,
 }
 
 pub struct DeckSyncInput {
     pub deck_name: String,
-    pub keybinds: Vec<(String, String)>,
+    pub cards: Vec<SyncCard>,
+}
+
+/// A card already in the database, as seen by `sync_decks`
+struct ExistingCard {
+    id: i64,
+    keybind: String,
+    description: String,
+    card_key: Option<String>,
+}
+
+/// How a deck card was matched to an existing database card
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CardMatch {
+    /// Index into the existing cards
+    existing: usize,
+    /// Whether the card's memory state carries over
+    keep_progress: bool,
+}
+
+/// Minimum description similarity to keep progress when only the description changed
+const REWORD_SIMILARITY: f64 = 0.6;
+
+/// Minimum description similarity to treat a changed keybind as the same card
+const RENAME_SIMILARITY: f64 = 0.8;
+
+/// Similarity of two descriptions from 0.0 to 1.0 (normalized Levenshtein distance,
+/// ignoring case and surrounding whitespace)
+fn description_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.trim().to_lowercase().chars().collect();
+    let b: Vec<char> = b.trim().to_lowercase().chars().collect();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+
+    1.0 - row[b.len()] as f64 / max_len as f64
+}
+
+/// Claim (similarity, card, existing) candidate pairs greedily, most similar first
+fn claim_matches(
+    mut candidates: Vec<(f64, usize, usize)>,
+    matches: &mut [Option<CardMatch>],
+    used: &mut [bool],
+    keep_progress: bool,
+) {
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+    for (_, i, e) in candidates {
+        if matches[i].is_none() && !used[e] {
+            matches[i] = Some(CardMatch {
+                existing: e,
+                keep_progress,
+            });
+            used[e] = true;
+        }
+    }
+}
+
+/// Match deck cards to existing database cards, in order of confidence:
+/// 1. Same `id:` key
+/// 2. Same keybind with a similar description (a rewording), or changed keybind
+///    with a near-identical description (a rename)
+/// 3. Same keybind with a different description (a new meaning, so progress resets)
+///
+/// Cards with different explicit ids are never matched to each other.
+fn match_cards(cards: &[&SyncCard], existing: &[ExistingCard]) -> Vec<Option<CardMatch>> {
+    let mut matches: Vec<Option<CardMatch>> = vec![None; cards.len()];
+    let mut used = vec![false; existing.len()];
+
+    let compatible_keys = |card: &SyncCard, stored: &ExistingCard| {
+        card.card_key.is_none() || stored.card_key.is_none()
+    };
+
+    for (i, card) in cards.iter().enumerate() {
+        let Some(key) = &card.card_key else {
+            continue;
+        };
+        if let Some(e) = existing
+            .iter()
+            .position(|stored| stored.card_key.as_ref() == Some(key))
+        {
+            matches[i] = Some(CardMatch {
+                existing: e,
+                keep_progress: true,
+            });
+            used[e] = true;
+        }
+    }
+
+    // Unchanged cards are the common case, so claim them before any fuzzy matching
+    let unchanged: Vec<_> = cards
+        .iter()
+        .enumerate()
+        .filter_map(|(i, card)| {
+            existing
+                .iter()
+                .position(|stored| {
+                    stored.keybind == card.keybind
+                        && stored.description == card.description
+                        && compatible_keys(card, stored)
+                })
+                .map(|e| (1.0, i, e))
+        })
+        .collect();
+    claim_matches(unchanged, &mut matches, &mut used, true);
+
+    let mut pairs = Vec::new();
+    for (i, card) in cards.iter().enumerate() {
+        if matches[i].is_some() {
+            continue;
+        }
+        for (e, stored) in existing.iter().enumerate() {
+            if !used[e] && compatible_keys(card, stored) {
+                let similarity = description_similarity(&card.description, &stored.description);
+                pairs.push((similarity, i, e, stored.keybind == card.keybind));
+            }
+        }
+    }
+
+    // Rewordings and renames compete on similarity, so swapping two keybinds
+    // follows each description to its new keybind
+    let similar: Vec<_> = pairs
+        .iter()
+        .filter(|p| {
+            p.0 >= if p.3 {
+                REWORD_SIMILARITY
+            } else {
+                RENAME_SIMILARITY
+            }
+        })
+        .map(|p| (p.0, p.1, p.2))
+        .collect();
+    claim_matches(similar, &mut matches, &mut used, true);
+
+    let replaced: Vec<_> = pairs
+        .iter()
+        .filter(|p| p.3)
+        .map(|p| (p.0, p.1, p.2))
+        .collect();
+    claim_matches(replaced, &mut matches, &mut used, false);
+
+    matches
 }
 
 impl Storage {
@@ -92,28 +410,45 @@ impl Storage {
 
         conn.pragma_update(None, "foreign_keys", "ON")?;
 
-        let storage = Storage { conn };
+        let storage = Storage {
+            conn,
+            day: StudyDay::default(),
+        };
         storage.init_schema()?;
 
         Ok(storage)
     }
 
+    /// Open an existing database without modifying it: no schema setup, no writes.
+    /// Used by status-bar polling, which must never create, migrate or back up the database.
+    pub fn open_read_only(path: &Path) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .with_context(|| format!("Failed to open database: {}", path.display()))?;
+
+        Ok(Storage {
+            conn,
+            day: StudyDay::default(),
+        })
+    }
+
+    /// Count due cards and today's reviews by this study day rather than calendar days
+    pub fn with_study_day(mut self, day: StudyDay) -> Self {
+        self.day = day;
+        self
+    }
+
+    pub fn study_day(&self) -> StudyDay {
+        self.day
+    }
+
     /// Initialize database schema
     fn init_schema(&self) -> Result<()> {
-        self.conn.execute_batch(
+        self.conn.execute_batch(&format!(
             "
-            CREATE TABLE IF NOT EXISTS cards (
-                id INTEGER PRIMARY KEY,
-                deck TEXT NOT NULL,
-                keybind TEXT NOT NULL,
-                description TEXT NOT NULL,
-                stability REAL,
-                difficulty REAL,
-                due_date TEXT,
-                last_review TEXT,
-                review_count INTEGER DEFAULT 0,
-                UNIQUE(deck, keybind)
-            );
+            CREATE TABLE IF NOT EXISTS cards ({});
 
             CREATE TABLE IF NOT EXISTS reviews (
                 id INTEGER PRIMARY KEY,
@@ -122,24 +457,116 @@ impl Storage {
                 response_time_ms INTEGER,
                 attempts INTEGER,
                 reviewed_at TEXT NOT NULL,
+                practice INTEGER NOT NULL DEFAULT 0,
                 FOREIGN KEY (card_id) REFERENCES cards(id)
             );
 
-            CREATE INDEX IF NOT EXISTS idx_cards_deck ON cards(deck);
-            CREATE INDEX IF NOT EXISTS idx_cards_due ON cards(due_date);
-            CREATE INDEX IF NOT EXISTS idx_reviews_card ON reviews(card_id);
+            CREATE TABLE IF NOT EXISTS review_keys (
+                id INTEGER PRIMARY KEY,
+                review_id INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                chord TEXT NOT NULL,
+                expected TEXT,
+                elapsed_ms INTEGER NOT NULL,
+                correct INTEGER NOT NULL,
+                FOREIGN KEY (review_id) REFERENCES reviews(id) ON DELETE CASCADE
+            );
+
+            CREATE TABLE IF NOT EXISTS card_mistakes (
+                card_id INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                count INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (card_id, kind),
+                FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE
+            );
 
             CREATE TABLE IF NOT EXISTS settings (
                 key TEXT PRIMARY KEY,
                 value TEXT NOT NULL
             );
             ",
+            CARDS_COLUMNS
+        ))?;
+
+        self.add_column_if_missing("cards", "card_key", "TEXT")?;
+        self.add_column_if_missing("reviews", "practice", "INTEGER NOT NULL DEFAULT 0")?;
+        self.add_column_if_missing("cards", "section", "TEXT")?;
+        self.add_column_if_missing("cards", "tags", "TEXT NOT NULL DEFAULT ''")?;
+        self.add_column_if_missing("reviews", "chords", "INTEGER")?;
+        self.rebuild_cards_for_reverse()?;
+
+        // A reverse card shares its forward card's keybind and id, so uniqueness
+        // only applies to forward cards
+        self.conn.execute_batch(
+            "
+            CREATE INDEX IF NOT EXISTS idx_cards_deck ON cards(deck);
+            CREATE INDEX IF NOT EXISTS idx_cards_due ON cards(due_date);
+            CREATE INDEX IF NOT EXISTS idx_reviews_card ON reviews(card_id);
+            CREATE INDEX IF NOT EXISTS idx_review_keys_review ON review_keys(review_id);
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_cards_keybind
+                ON cards(deck, keybind) WHERE reverse_of IS NULL;
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_cards_key
+                ON cards(deck, card_key) WHERE reverse_of IS NULL;
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_cards_reverse ON cards(reverse_of);
+            ",
         )?;
 
         Ok(())
     }
 
-    /// Sync all decks in a single transaction: upsert cards, delete removed cards, delete orphaned decks.
+    /// Rebuild the cards table of databases created before reverse cards: their
+    /// table-level UNIQUE(deck, keybind) would stop a reverse card sharing its keybind
+    fn rebuild_cards_for_reverse(&self) -> Result<()> {
+        let has_reverse = self
+            .conn
+            .prepare("SELECT 1 FROM pragma_table_info('cards') WHERE name = 'reverse_of'")?
+            .exists([])?;
+        if has_reverse {
+            return Ok(());
+        }
+
+        self.conn.pragma_update(None, "foreign_keys", "OFF")?;
+        self.conn.execute_batch(&format!(
+            "
+            BEGIN;
+            CREATE TABLE cards_new ({});
+            INSERT INTO cards_new (id, deck, keybind, description, stability, difficulty,
+                                   due_date, last_review, review_count, card_key, section, tags)
+                SELECT id, deck, keybind, description, stability, difficulty,
+                       due_date, last_review, review_count, card_key, section, tags
+                FROM cards;
+            DROP TABLE cards;
+            ALTER TABLE cards_new RENAME TO cards;
+            COMMIT;
+            ",
+            CARDS_COLUMNS
+        ))?;
+        self.conn.pragma_update(None, "foreign_keys", "ON")?;
+
+        Ok(())
+    }
+
+    /// Add a column to an existing table (for databases created by older versions)
+    fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        if !self.has_column(table, column)? {
+            self.conn.execute_batch(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Whether a table has a column. A read-only database may predate a migration.
+    fn has_column(&self, table: &str, column: &str) -> Result<bool> {
+        Ok(self
+            .conn
+            .prepare("SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2")?
+            .exists(params![table, column])?)
+    }
+
+    /// Sync all decks in a single transaction: match deck cards to stored cards (by id,
+    /// keybind, or a similar description), upsert them, delete removed cards, delete orphaned decks.
     pub fn sync_decks(
         &mut self,
         decks: Vec<DeckSyncInput>,
@@ -148,39 +575,149 @@ impl Storage {
         let tx = self.conn.transaction()?;
 
         for deck in &decks {
-            let mut deck_keybinds = HashSet::new();
-
-            for (keybind, description) in &deck.keybinds {
-                deck_keybinds.insert(keybind.clone());
-                tx.execute(
-                    "INSERT INTO cards (deck, keybind, description)
-                     VALUES (?1, ?2, ?3)
-                     ON CONFLICT(deck, keybind) DO UPDATE SET
-                        description = ?3,
-                        stability = CASE WHEN description != ?3 THEN NULL ELSE stability END,
-                        difficulty = CASE WHEN description != ?3 THEN NULL ELSE difficulty END,
-                        due_date = CASE WHEN description != ?3 THEN NULL ELSE due_date END,
-                        last_review = CASE WHEN description != ?3 THEN NULL ELSE last_review END,
-                        review_count = CASE WHEN description != ?3 THEN 0 ELSE review_count END",
-                    params![deck.deck_name, keybind, description],
-                )?;
+            let mut stmt = tx.prepare(
+                "SELECT id, keybind, description, card_key FROM cards
+                 WHERE deck = ?1 AND reverse_of IS NULL ORDER BY id",
+            )?;
+            let existing: Vec<ExistingCard> = stmt
+                .query_map(params![deck.deck_name], |row| {
+                    Ok(ExistingCard {
+                        id: row.get(0)?,
+                        keybind: row.get(1)?,
+                        description: row.get(2)?,
+                        card_key: row.get(3)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            drop(stmt);
+
+            // Duplicate keybinds in a deck collapse into one card (first wins)
+            let mut seen = HashSet::new();
+            let cards: Vec<&SyncCard> = deck
+                .cards
+                .iter()
+                .filter(|card| seen.insert(card.keybind.as_str()))
+                .collect();
+            let matches = match_cards(&cards, &existing);
+
+            let matched: HashSet<usize> = matches.iter().flatten().map(|m| m.existing).collect();
+            for (e, stored) in existing.iter().enumerate() {
+                if !matched.contains(&e) {
+                    tx.execute(
+                        "DELETE FROM reviews WHERE card_id IN
+                            (SELECT id FROM cards WHERE id = ?1 OR reverse_of = ?1)",
+                        params![stored.id],
+                    )?;
+                    tx.execute(
+                        "DELETE FROM cards WHERE id = ?1 OR reverse_of = ?1",
+                        params![stored.id],
+                    )?;
+                }
             }
 
-            let mut stmt = tx.prepare("SELECT keybind FROM cards WHERE deck = ?1")?;
-            let existing: HashSet<String> = stmt
-                .query_map(params![deck.deck_name], |row| row.get(0))?
-                .collect::<Result<HashSet<_>, _>>()?;
-            drop(stmt);
+            // Move renamed keybinds out of the way first, so swapping two keybinds
+            // doesn't trip the UNIQUE(deck, keybind) constraint
+            for (card, m) in cards.iter().zip(&matches) {
+                if let Some(m) = m
+                    && existing[m.existing].keybind != card.keybind
+                {
+                    tx.execute(
+                        "UPDATE cards SET keybind = ':renaming:' || id WHERE id = ?1",
+                        params![existing[m.existing].id],
+                    )?;
+                }
+            }
+
+            for (card, m) in cards.iter().zip(&matches) {
+                let (id, keep_progress) = match m {
+                    None => {
+                        tx.execute(
+                            "INSERT INTO cards (deck, keybind, description, card_key, section, tags)
+                             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                            params![
+                                deck.deck_name,
+                                card.keybind,
+                                card.description,
+                                card.card_key,
+                                card.section,
+                                card.tags.join(" ")
+                            ],
+                        )?;
+                        (tx.last_insert_rowid(), true)
+                    }
+                    Some(m) => {
+                        let stored = &existing[m.existing];
+                        let changed = stored.keybind != card.keybind
+                            || stored.description != card.description;
 
-            for keybind in existing.difference(&deck_keybinds) {
-                tx.execute(
-                    "DELETE FROM reviews WHERE card_id IN (SELECT id FROM cards WHERE deck = ?1 AND keybind = ?2)",
-                    params![deck.deck_name, keybind],
-                )?;
-                tx.execute(
-                    "DELETE FROM cards WHERE deck = ?1 AND keybind = ?2",
-                    params![deck.deck_name, keybind],
-                )?;
+                        tx.execute(
+                            "UPDATE cards SET keybind = ?1, description = ?2, card_key = ?3,
+                                section = ?4, tags = ?5
+                             WHERE id = ?6",
+                            params![
+                                card.keybind,
+                                card.description,
+                                card.card_key,
+                                card.section,
+                                card.tags.join(" "),
+                                stored.id
+                            ],
+                        )?;
+                        (stored.id, m.keep_progress && !(card.reset && changed))
+                    }
+                };
+
+                // The reverse card follows its forward card's identity
+                if card.reverse {
+                    tx.execute(
+                        "INSERT INTO cards (deck, keybind, description, section, tags, reverse_of)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                         ON CONFLICT(reverse_of) DO UPDATE SET
+                            keybind = excluded.keybind,
+                            description = excluded.description,
+                            section = excluded.section,
+                            tags = excluded.tags",
+                        params![
+                            deck.deck_name,
+                            card.keybind,
+                            card.description,
+                            card.section,
+                            card.tags.join(" "),
+                            id
+                        ],
+                    )?;
+                } else {
+                    tx.execute(
+                        "DELETE FROM reviews WHERE card_id IN
+                            (SELECT id FROM cards WHERE reverse_of = ?1)",
+                        params![id],
+                    )?;
+                    tx.execute("DELETE FROM cards WHERE reverse_of = ?1", params![id])?;
+                }
+
+                if !keep_progress {
+                    // The old meaning's history would skew training, timing and "new" counts
+                    tx.execute(
+                        "DELETE FROM reviews WHERE card_id IN
+                            (SELECT id FROM cards WHERE id = ?1 OR reverse_of = ?1)",
+                        params![id],
+                    )?;
+                    tx.execute(
+                        "DELETE FROM card_mistakes WHERE card_id IN
+                            (SELECT id FROM cards WHERE id = ?1 OR reverse_of = ?1)",
+                        params![id],
+                    )?;
+                    tx.execute(
+                        "UPDATE cards SET
+                            stability = NULL,
+                            difficulty = NULL,
+                            due_date = NULL,
+                            last_review = NULL,
+                            review_count = 0
+                         WHERE id = ?1 OR reverse_of = ?1",
+                        params![id],
+                    )?;
+                }
             }
         }
 
@@ -202,20 +739,65 @@ impl Storage {
         Ok(())
     }
 
-    /// Get due cards for a deck (due now or never reviewed)
-    pub fn get_due_cards(&self, deck: &str) -> Result<Vec<StoredCard>> {
-        let now = Utc::now().to_rfc3339();
+    /// Get due cards for a deck (due before the cutoff or never reviewed), new cards first in deck order
+    pub fn get_due_cards(&self, deck: &str, due: DueBy) -> Result<Vec<StoredCard>> {
+        let cutoff = self.day.cutoff(due).to_rfc3339();
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, deck, keybind, description, stability, difficulty,
+                    due_date, last_review, review_count, section, tags, reverse_of IS NOT NULL
+             FROM cards
+             WHERE deck = ?1 AND (due_date IS NULL OR julianday(due_date) < julianday(?2))
+             ORDER BY julianday(due_date) ASC NULLS FIRST, id ASC",
+        )?;
+
+        let cards = stmt
+            .query_map(params![deck, cutoff], row_to_stored_card)?
+            .collect::<Result<Vec<_>, _>>()?;
 
+        Ok(cards)
+    }
+
+    /// Get every card in every deck, ordered by deck
+    pub fn get_all_cards(&self) -> Result<Vec<StoredCard>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, deck, keybind, description, stability, difficulty,
-                    due_date, last_review, review_count
+                    due_date, last_review, review_count, section, tags, reverse_of IS NOT NULL
+             FROM cards ORDER BY deck, id",
+        )?;
+
+        let cards = stmt
+            .query_map([], row_to_stored_card)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(cards)
+    }
+
+    /// Get the descriptions of a deck's forward cards, for multiple-choice answers
+    pub fn get_descriptions(&self, deck: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT description FROM cards WHERE deck = ?1 AND reverse_of IS NULL ORDER BY id",
+        )?;
+
+        let descriptions = stmt
+            .query_map(params![deck], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(descriptions)
+    }
+
+    /// Get every card in a deck for a cram session, least stable first (new cards first of all)
+    pub fn get_cram_cards(&self, deck: &str) -> Result<Vec<StoredCard>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, deck, keybind, description, stability, difficulty,
+                    due_date, last_review, review_count, section, tags, reverse_of IS NOT NULL
              FROM cards
-             WHERE deck = ?1 AND (due_date IS NULL OR due_date <= ?2)
-             ORDER BY due_date ASC NULLS FIRST",
+             WHERE deck = ?1
+             ORDER BY stability ASC NULLS FIRST, id ASC",
         )?;
 
         let cards = stmt
-            .query_map(params![deck, now], row_to_stored_card)?
+            .query_map(params![deck], row_to_stored_card)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(cards)
@@ -246,46 +828,112 @@ impl Storage {
         Ok(())
     }
 
-    /// Record a review
+    /// Record a review.
+    /// Practice reviews (from cram sessions) are kept for stats but excluded from FSRS training.
     pub fn record_review(
         &self,
         card_id: i64,
         rating: i32,
         response_time_ms: i64,
         attempts: i32,
+        chords: usize,
+        practice: bool,
     ) -> Result<i64> {
         let now = Utc::now().to_rfc3339();
 
         self.conn.execute(
-            "INSERT INTO reviews (card_id, rating, response_time_ms, attempts, reviewed_at)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![card_id, rating, response_time_ms, attempts, now],
+            "INSERT INTO reviews (card_id, rating, response_time_ms, attempts, reviewed_at,
+                                  practice, chords)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                card_id,
+                rating,
+                response_time_ms,
+                attempts,
+                now,
+                practice,
+                chords as i64
+            ],
         )?;
 
         Ok(self.conn.last_insert_rowid())
     }
 
-    /// Get all decks with card counts (due = due by end of today)
+    /// Record the keys pressed during a review, in the order they were pressed
+    pub fn record_review_keys(&self, review_id: i64, keys: &[KeyPress]) -> Result<()> {
+        let mut stmt = self.conn.prepare_cached(
+            "INSERT INTO review_keys (review_id, position, chord, expected, elapsed_ms, correct)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for key in keys {
+            stmt.execute(params![
+                review_id,
+                key.position as i64,
+                key.chord,
+                key.expected,
+                key.elapsed_ms as i64,
+                key.correct
+            ])?;
+        }
+
+        Ok(())
+    }
+
+    /// Count a mistake of the given kind against a card
+    pub fn record_mistake(&self, card_id: i64, kind: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO card_mistakes (card_id, kind, count) VALUES (?1, ?2, 1)
+             ON CONFLICT (card_id, kind) DO UPDATE SET count = count + 1",
+            params![card_id, kind],
+        )?;
+
+        Ok(())
+    }
+
+    /// Get the mistake counts of every card, most frequent first
+    pub fn get_mistake_counts(&self) -> Result<Vec<MistakeCount>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT card_id, kind, count FROM card_mistakes
+             ORDER BY count DESC, card_id ASC, kind ASC",
+        )?;
+
+        let counts = stmt
+            .query_map([], |row| {
+                Ok(MistakeCount {
+                    card_id: row.get(0)?,
+                    kind: row.get(1)?,
+                    count: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(counts)
+    }
+
+    /// Get all decks with card counts (due = due by the end of the study day)
     /// keyboard_modes maps deck name to its KeyboardMode (from TSV files)
     pub fn get_deck_stats(
         &self,
-        keyboard_modes: &std::collections::HashMap<String, KeyboardMode>,
+        keyboard_modes: &HashMap<String, KeyboardMode>,
     ) -> Result<Vec<DeckStats>> {
         let mut stmt = self.conn.prepare(
-            "SELECT deck, COUNT(*), SUM(CASE WHEN due_date IS NULL OR due_date <= ?1 THEN 1 ELSE 0 END)
+            "SELECT deck, COUNT(*),
+                    SUM(CASE WHEN due_date IS NULL OR julianday(due_date) < julianday(?1)
+                        THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN last_review IS NULL AND (due_date IS NULL
+                        OR julianday(due_date) < julianday(?1)) THEN 1 ELSE 0 END)
              FROM cards GROUP BY deck ORDER BY deck",
         )?;
 
-        let end_of_today_utc = end_of_today_utc();
-
         let stats = stmt
-            .query_map(params![end_of_today_utc], |row| {
+            .query_map(params![self.day.today_end().to_rfc3339()], |row| {
                 let name: String = row.get(0)?;
                 let keyboard_mode = keyboard_modes.get(&name).copied().unwrap_or_default();
                 Ok(DeckStats {
                     name,
                     total_cards: row.get(1)?,
                     due_cards: row.get(2)?,
+                    new_due_cards: row.get(3)?,
                     keyboard_mode,
                 })
             })?
@@ -294,8 +942,155 @@ impl Storage {
         Ok(stats)
     }
 
+    /// Count today's new cards and reviews per deck. They're counted from the review
+    /// history, so the daily limits carry over between sessions and restarts.
+    /// Only cards seen before today count as reviews, so relearning a card introduced
+    /// today doesn't use up the review allowance too.
+    /// Practice reviews don't count; a database from before practice reviews has none.
+    pub fn get_today_counts(&self) -> Result<HashMap<String, DayCounts>> {
+        let has_practice = self.has_column("reviews", "practice")?;
+        let not_practice = |alias: &str| {
+            if has_practice {
+                format!("{}.practice = 0", alias)
+            } else {
+                "1".to_string()
+            }
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT c.deck,
+                    SUM(CASE WHEN EXISTS (SELECT 1 FROM reviews p
+                                          WHERE p.card_id = r.card_id AND {0}
+                                            AND p.id < r.id)
+                        THEN 0 ELSE 1 END),
+                    SUM(CASE WHEN EXISTS (SELECT 1 FROM reviews p
+                                          WHERE p.card_id = r.card_id AND {0}
+                                            AND julianday(p.reviewed_at) < julianday(?1))
+                        THEN 1 ELSE 0 END)
+             FROM reviews r JOIN cards c ON c.id = r.card_id
+             WHERE {1} AND julianday(r.reviewed_at) >= julianday(?1)
+             GROUP BY c.deck",
+            not_practice("p"),
+            not_practice("r")
+        ))?;
+
+        let counts = stmt
+            .query_map(params![self.day.today_start().to_rfc3339()], |row| {
+                Ok((
+                    row.get(0)?,
+                    DayCounts {
+                        new_cards: row.get::<_, i64>(1)? as usize,
+                        reviews: row.get::<_, i64>(2)? as usize,
+                    },
+                ))
+            })?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok(counts)
+    }
+
+    /// Count cards across all decks that are due right now (never-reviewed cards included)
+    pub fn count_due_now(&self) -> Result<i32> {
+        let count = self.conn.query_row(
+            "SELECT COUNT(*) FROM cards
+             WHERE due_date IS NULL OR julianday(due_date) < julianday(?1)",
+            params![Utc::now().to_rfc3339()],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    /// Get card counts for each section (in deck order) and tag (alphabetical) in a deck
+    pub fn get_group_stats(&self, deck: &str) -> Result<Vec<GroupStats>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT section, tags, due_date IS NULL OR julianday(due_date) < julianday(?2)
+             FROM cards WHERE deck = ?1 ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map(params![deck, self.day.today_end().to_rfc3339()], |row| {
+                Ok((
+                    row.get::<_, Option<String>>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, bool>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut sections: Vec<GroupStats> = Vec::new();
+        let mut tags: Vec<GroupStats> = Vec::new();
+        for (section, card_tags, due) in rows {
+            if let Some(section) = section {
+                tally_group(&mut sections, CardGroup::Section(section), due);
+            }
+            for tag in split_tags(&card_tags) {
+                tally_group(&mut tags, CardGroup::Tag(tag), due);
+            }
+        }
+
+        tags.sort_by(|a, b| match (&a.group, &b.group) {
+            (CardGroup::Tag(a), CardGroup::Tag(b)) => a.cmp(b),
+            _ => std::cmp::Ordering::Equal,
+        });
+        sections.extend(tags);
+        Ok(sections)
+    }
+
+    /// Get detailed stats for one deck, or None if the deck has no cards
+    pub fn get_deck_detail(&self, deck: &str) -> Result<Option<DeckDetail>> {
+        let (total_cards, due_cards, new_cards, avg_stability, next_due): (
+            i32,
+            Option<i32>,
+            Option<i32>,
+            Option<f64>,
+            Option<String>,
+        ) = self.conn.query_row(
+            "SELECT COUNT(*),
+                    SUM(CASE WHEN due_date IS NULL OR julianday(due_date) < julianday(?2)
+                        THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN last_review IS NULL THEN 1 ELSE 0 END),
+                    AVG(stability),
+                    (SELECT due_date FROM cards WHERE deck = ?1 AND due_date IS NOT NULL
+                     ORDER BY julianday(due_date) LIMIT 1)
+             FROM cards WHERE deck = ?1",
+            params![deck, self.day.today_end().to_rfc3339()],
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            },
+        )?;
+
+        if total_cards == 0 {
+            return Ok(None);
+        }
+
+        let (total_reviews, retention, avg_response_ms): (i32, Option<f64>, Option<f64>) =
+            self.conn.query_row(
+                "SELECT COUNT(*),
+                        AVG(CASE WHEN r.rating > 1 THEN 1.0 ELSE 0.0 END),
+                        AVG(r.response_time_ms)
+                 FROM reviews r JOIN cards c ON c.id = r.card_id
+                 WHERE c.deck = ?1 AND r.practice = 0",
+                params![deck],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?;
+
+        Ok(Some(DeckDetail {
+            total_cards,
+            due_cards: due_cards.unwrap_or(0),
+            new_cards: new_cards.unwrap_or(0),
+            total_reviews,
+            retention,
+            avg_response_ms,
+            avg_stability,
+            next_due: next_due.and_then(|s| s.parse().ok()),
+        }))
+    }
+
     /// Get reviews for a card.
-    /// Reserved for future FSRS parameter training from user review history.
     #[allow(dead_code)]
     pub fn get_reviews_for_card(&self, card_id: i64) -> Result<Vec<Review>> {
         let mut stmt = self.conn.prepare(
@@ -304,20 +1099,77 @@ impl Storage {
         )?;
 
         let reviews = stmt
-            .query_map(params![card_id], |row| {
-                Ok(Review {
-                    id: row.get(0)?,
+            .query_map(params![card_id], row_to_review)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(reviews)
+    }
+
+    /// Get every scheduled (non-practice) review, grouped by card and in chronological order.
+    /// Used to build the training set for FSRS parameter optimization.
+    pub fn get_all_reviews(&self) -> Result<Vec<Review>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, card_id, rating, response_time_ms, attempts, reviewed_at
+             FROM reviews WHERE practice = 0 ORDER BY card_id ASC, reviewed_at ASC",
+        )?;
+
+        let reviews = stmt
+            .query_map([], row_to_review)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(reviews)
+    }
+
+    /// Get the keys pressed in every scheduled (non-practice) review, grouped by
+    /// review in the order they were pressed
+    pub fn get_review_keys(&self) -> Result<Vec<ReviewKey>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT k.review_id, r.card_id, r.attempts = 1 AND r.rating > 1,
+                    k.position, k.chord, k.expected, k.elapsed_ms, k.correct
+             FROM review_keys k JOIN reviews r ON r.id = k.review_id
+             WHERE r.practice = 0
+             ORDER BY k.review_id ASC, k.id ASC",
+        )?;
+
+        let keys = stmt
+            .query_map([], |row| {
+                Ok(ReviewKey {
+                    review_id: row.get(0)?,
                     card_id: row.get(1)?,
-                    rating: row.get(2)?,
+                    first_try: row.get(2)?,
+                    key: KeyPress {
+                        position: row.get::<_, i64>(3)? as usize,
+                        chord: row.get(4)?,
+                        expected: row.get(5)?,
+                        elapsed_ms: row.get::<_, i64>(6)? as u64,
+                        correct: row.get(7)?,
+                    },
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(keys)
+    }
+
+    /// Get the most recent forward-card reviews answered correctly on the first try,
+    /// practice included. These measure typing speed rather than recall.
+    pub fn get_timing_history(&self, limit: usize) -> Result<Vec<TimedReview>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.deck, c.keybind, r.chords, r.response_time_ms
+             FROM reviews r JOIN cards c ON c.id = r.card_id
+             WHERE c.reverse_of IS NULL AND r.attempts = 1 AND r.rating > 1
+               AND r.response_time_ms IS NOT NULL
+             ORDER BY r.id DESC
+             LIMIT ?1",
+        )?;
+
+        let reviews = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(TimedReview {
+                    deck: row.get(0)?,
+                    keybind: row.get(1)?,
+                    chords: row.get(2)?,
                     response_time_ms: row.get(3)?,
-                    attempts: row.get(4)?,
-                    reviewed_at: row.get::<_, String>(5)?.parse().map_err(|e| {
-                        rusqlite::Error::FromSqlConversionFailure(
-                            5,
-                            rusqlite::types::Type::Text,
-                            Box::new(e),
-                        )
-                    })?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -325,6 +1177,29 @@ impl Storage {
         Ok(reviews)
     }
 
+    /// Get the personalised FSRS parameters, if `kbsr optimize` has stored any
+    pub fn get_fsrs_parameters(&self) -> Result<Option<Vec<f32>>> {
+        let Some(value) = self.get_setting(FSRS_PARAMETERS_KEY)? else {
+            return Ok(None);
+        };
+        let parameters = value
+            .split(',')
+            .map(|p| p.trim().parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Invalid {} setting: {}", FSRS_PARAMETERS_KEY, value))?;
+        Ok(Some(parameters))
+    }
+
+    /// Store personalised FSRS parameters
+    pub fn set_fsrs_parameters(&self, parameters: &[f32]) -> Result<()> {
+        let value = parameters
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        self.set_setting(FSRS_PARAMETERS_KEY, &value)
+    }
+
     /// Get a setting value by key
     pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
         let result = self.conn.query_row(
@@ -374,3 +1249,758 @@ impl Storage {
         Ok(Some(backup_path))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn open_temp() -> (TempDir, Storage) {
+        let dir = TempDir::new().unwrap();
+        let storage = Storage::open(&dir.path().join("kbsr.db")).unwrap();
+        (dir, storage)
+    }
+
+    fn card(keybind: &str, description: &str) -> SyncCard {
+        SyncCard {
+            keybind: keybind.to_string(),
+            description: description.to_string(),
+            card_key: None,
+            reset: false,
+            section: None,
+            tags: Vec::new(),
+            reverse: false,
+        }
+    }
+
+    fn sync(storage: &mut Storage, cards: Vec<SyncCard>) {
+        let active = HashSet::from(["test".to_string()]);
+        storage
+            .sync_decks(
+                vec![DeckSyncInput {
+                    deck_name: "test".to_string(),
+                    cards,
+                }],
+                &active,
+            )
+            .unwrap();
+    }
+
+    /// Review every due card so it has memory state, returning the stored cards
+    fn review_all(storage: &Storage) -> Vec<StoredCard> {
+        for stored in storage.get_due_cards("test", DueBy::Now).unwrap() {
+            storage
+                .update_card_after_review(
+                    stored.id,
+                    5.0,
+                    4.0,
+                    Utc::now() + chrono::Duration::days(1),
+                )
+                .unwrap();
+            storage
+                .record_review(stored.id, 3, 1000, 1, 1, false)
+                .unwrap();
+        }
+        all_cards(storage)
+    }
+
+    fn all_cards(storage: &Storage) -> Vec<StoredCard> {
+        let mut stmt = storage
+            .conn
+            .prepare(
+                "SELECT id, deck, keybind, description, stability, difficulty,
+                        due_date, last_review, review_count, section, tags, reverse_of IS NOT NULL
+                 FROM cards ORDER BY id",
+            )
+            .unwrap();
+        stmt.query_map([], row_to_stored_card)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_open_read_only() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("kbsr.db");
+        assert!(Storage::open_read_only(&path).is_err());
+        assert!(!path.exists());
+
+        let mut storage = Storage::open(&path).unwrap();
+        sync(&mut storage, vec![card("Ctrl+S", "Save file")]);
+        drop(storage);
+
+        let storage = Storage::open_read_only(&path).unwrap();
+        let stats = storage.get_deck_stats(&Default::default()).unwrap();
+        assert_eq!(stats[0].due_cards, 1);
+        assert_eq!(storage.count_due_now().unwrap(), 1);
+        assert!(storage.set_setting("show_hints", "false").is_err());
+    }
+
+    #[test]
+    fn test_description_similarity() {
+        assert_eq!(description_similarity("Save file", "save file"), 1.0);
+        assert!(description_similarity("Go to top of file", "Go to top of flie") > 0.8);
+        assert!(description_similarity("Go to top of file", "Jump to top of file") > 0.6);
+        assert!(description_similarity("Undo", "Redo") < 0.6);
+        assert!(description_similarity("Save file", "Close window") < 0.5);
+    }
+
+    #[test]
+    fn test_sync_keeps_progress_on_reworded_description() {
+        let (_dir, mut storage) = open_temp();
+        sync(&mut storage, vec![card("Ctrl+S", "Save flie")]);
+        let before = review_all(&storage);
+
+        sync(&mut storage, vec![card("Ctrl+S", "Save file")]);
+        let after = all_cards(&storage);
+        assert_eq!(after[0].id, before[0].id);
+        assert_eq!(after[0].description, "Save file");
+        assert_eq!(after[0].stability, Some(5.0));
+    }
+
+    #[test]
+    fn test_sync_resets_progress_on_new_meaning() {
+        let (_dir, mut storage) = open_temp();
+        sync(&mut storage, vec![card("Ctrl+S", "Save file")]);
+        let before = review_all(&storage);
+        storage.record_mistake(before[0].id, "wrong_key").unwrap();
+
+        sync(&mut storage, vec![card("Ctrl+S", "Open command palette")]);
+        let after = all_cards(&storage);
+        assert_eq!(after[0].stability, None);
+        assert_eq!(after[0].review_count, 0);
+        assert!(
+            storage
+                .get_reviews_for_card(after[0].id)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(storage.get_mistake_counts().unwrap().is_empty());
+        assert_eq!(storage.get_today_counts().unwrap().get("test"), None);
+    }
+
+    #[test]
+    fn test_sync_detects_keybind_rename() {
+        let (_dir, mut storage) = open_temp();
+        sync(&mut storage, vec![card("Ctrl+S", "Save file")]);
+        let before = review_all(&storage);
+
+        sync(&mut storage, vec![card("Ctrl+Shift+S", "Save file")]);
+        let after = all_cards(&storage);
+        assert_eq!(after.len(), 1);
+        assert_eq!(after[0].id, before[0].id);
+        assert_eq!(after[0].keybind, "Ctrl+Shift+S");
+        assert_eq!(after[0].stability, Some(5.0));
+        assert_eq!(storage.get_reviews_for_card(after[0].id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_sync_swapped_keybinds() {
+        let (_dir, mut storage) = open_temp();
+        sync(
+            &mut storage,
+            vec![card("Ctrl+Y", "Redo"), card("Ctrl+Z", "Undo")],
+        );
+        let before = review_all(&storage);
+
+        sync(
+            &mut storage,
+            vec![card("Ctrl+Z", "Redo"), card("Ctrl+Y", "Undo")],
+        );
+        let after = all_cards(&storage);
+        assert_eq!(after.len(), 2);
+        assert_eq!(after[0].id, before[0].id);
+        assert_eq!(after[0].keybind, "Ctrl+Z");
+        assert_eq!(after[0].description, "Redo");
+        assert_eq!(after[0].stability, Some(5.0));
+    }
+
+    #[test]
+    fn test_sync_stable_id_carries_progress() {
+        let (_dir, mut storage) = open_temp();
+        let mut first = card("Ctrl+S", "Save file");
+        first.card_key = Some("save".to_string());
+        sync(&mut storage, vec![first]);
+        let before = review_all(&storage);
+
+        let mut edited = card("Ctrl+W", "Write buffer to disk");
+        edited.card_key = Some("save".to_string());
+        sync(&mut storage, vec![edited]);
+        let after = all_cards(&storage);
+        assert_eq!(after.len(), 1);
+        assert_eq!(after[0].id, before[0].id);
+        assert_eq!(after[0].stability, Some(5.0));
+    }
+
+    #[test]
+    fn test_sync_reset_flag() {
+        let (_dir, mut storage) = open_temp();
+        sync(&mut storage, vec![card("Ctrl+S", "Save flie")]);
+        review_all(&storage);
+
+        // Unchanged cards keep progress even with the reset flag
+        let mut unchanged = card("Ctrl+S", "Save flie");
+        unchanged.reset = true;
+        sync(&mut storage, vec![unchanged]);
+        assert_eq!(all_cards(&storage)[0].stability, Some(5.0));
+
+        let mut fixed = card("Ctrl+S", "Save file");
+        fixed.reset = true;
+        sync(&mut storage, vec![fixed]);
+        assert_eq!(all_cards(&storage)[0].stability, None);
+    }
+
+    #[test]
+    fn test_practice_reviews_excluded_from_training() {
+        let (_dir, mut storage) = open_temp();
+        sync(&mut storage, vec![card("Ctrl+S", "Save file")]);
+        let cards = review_all(&storage);
+        storage
+            .record_review(cards[0].id, 4, 800, 1, 1, true)
+            .unwrap();
+
+        assert_eq!(storage.get_all_reviews().unwrap().len(), 1);
+        assert_eq!(storage.get_reviews_for_card(cards[0].id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_timing_history() {
+        let (_dir, mut storage) = open_temp();
+        let reversed = SyncCard {
+            reverse: true,
+            ..card("Ctrl+S", "Save file")
+        };
+        sync(&mut storage, vec![card("g g", "Go to top"), reversed]);
+        let cards = all_cards(&storage);
+        storage
+            .record_review(cards[0].id, 4, 900, 1, 2, true)
+            .unwrap();
+        storage
+            .record_review(cards[0].id, 2, 1500, 2, 2, false)
+            .unwrap();
+        storage
+            .record_review(cards[0].id, 1, 10000, 1, 2, false)
+            .unwrap();
+        storage
+            .record_review(cards[1].id, 3, 1200, 1, 1, false)
+            .unwrap();
+        storage
+            .record_review(cards[2].id, 3, 700, 1, 1, false)
+            .unwrap();
+        storage
+            .conn
+            .execute(
+                "UPDATE reviews SET chords = NULL WHERE card_id = ?1",
+                [cards[1].id],
+            )
+            .unwrap();
+
+        // Only first-try successes on forward cards, newest first
+        let history = storage.get_timing_history(10).unwrap();
+        let times: Vec<i64> = history.iter().map(|r| r.response_time_ms).collect();
+        assert_eq!(times, vec![1200, 900]);
+        assert_eq!(history[0].keybind, "Ctrl+S");
+        assert_eq!(history[0].chords, None);
+        assert_eq!(history[1].chords, Some(2));
+        assert_eq!(storage.get_timing_history(1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_review_keys() {
+        let (_dir, mut storage) = open_temp();
+        sync(&mut storage, vec![card("Ctrl+K Ctrl+C", "Comment")]);
+        let cards = all_cards(&storage);
+        let key =
+            |position: usize, chord: &str, expected: Option<&str>, elapsed_ms: u64| KeyPress {
+                position,
+                chord: chord.to_string(),
+                expected: expected.map(str::to_string),
+                elapsed_ms,
+                correct: expected.is_none(),
+            };
+        let keys = vec![
+            key(0, "Ctrl+K", None, 900),
+            key(1, "Ctrl+X", Some("Ctrl+C"), 1500),
+            key(0, "Ctrl+K", None, 2400),
+            key(1, "Ctrl+C", None, 2600),
+        ];
+        let review_id = storage
+            .record_review(cards[0].id, 2, 2600, 2, 2, false)
+            .unwrap();
+        storage.record_review_keys(review_id, &keys).unwrap();
+        let practice_id = storage
+            .record_review(cards[0].id, 4, 500, 1, 2, true)
+            .unwrap();
+        storage.record_review_keys(practice_id, &keys[2..]).unwrap();
+
+        let stored = storage.get_review_keys().unwrap();
+        assert_eq!(stored.len(), 4);
+        assert!(
+            stored
+                .iter()
+                .all(|k| k.review_id == review_id && !k.first_try)
+        );
+        assert_eq!(stored.into_iter().map(|k| k.key).collect::<Vec<_>>(), keys);
+
+        // Removing the deck takes the key presses with its reviews
+        storage.sync_decks(Vec::new(), &HashSet::new()).unwrap();
+        let remaining: i64 = storage
+            .conn
+            .query_row("SELECT COUNT(*) FROM review_keys", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(remaining, 0);
+    }
+
+    #[test]
+    fn test_today_counts() {
+        let (_dir, mut storage) = open_temp();
+        sync(
+            &mut storage,
+            vec![card("g g", "Top"), card("G", "Bottom"), card("x", "Delete")],
+        );
+        let cards = all_cards(&storage);
+        // Seen yesterday, reviewed again today
+        storage
+            .record_review(cards[0].id, 3, 900, 1, 2, false)
+            .unwrap();
+        storage
+            .conn
+            .execute(
+                "UPDATE reviews SET reviewed_at = ?1",
+                params![(Utc::now() - chrono::Duration::days(2)).to_rfc3339()],
+            )
+            .unwrap();
+        storage
+            .record_review(cards[0].id, 3, 900, 1, 2, false)
+            .unwrap();
+        // New today, then relearned, which isn't a review
+        storage
+            .record_review(cards[1].id, 1, 900, 3, 1, false)
+            .unwrap();
+        storage
+            .record_review(cards[1].id, 3, 900, 1, 1, false)
+            .unwrap();
+        // Practice doesn't count
+        storage
+            .record_review(cards[2].id, 3, 900, 1, 1, true)
+            .unwrap();
+
+        let counts = storage.get_today_counts().unwrap();
+        assert_eq!(
+            counts.get("test"),
+            Some(&DayCounts {
+                new_cards: 1,
+                reviews: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_today_counts_relearned_new_card() {
+        let (_dir, mut storage) = open_temp();
+        sync(&mut storage, vec![card("Ctrl+S", "Save file")]);
+        let id = all_cards(&storage)[0].id;
+        // Introduced, failed, then reviewed again once it's relearned
+        storage.record_review(id, 3, 900, 1, 1, false).unwrap();
+        storage.record_review(id, 1, 900, 3, 1, false).unwrap();
+        storage.record_review(id, 3, 900, 1, 1, false).unwrap();
+
+        assert_eq!(
+            storage.get_today_counts().unwrap().get("test"),
+            Some(&DayCounts {
+                new_cards: 1,
+                reviews: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_due_by_study_day() {
+        let (_dir, mut storage) = open_temp();
+        sync(
+            &mut storage,
+            vec![
+                card("Ctrl+S", "Save file"),
+                card("Ctrl+O", "Open file"),
+                card("Ctrl+Q", "Quit"),
+            ],
+        );
+        let storage = storage.with_study_day(StudyDay::new(4));
+        let cards = all_cards(&storage);
+        let end = storage.study_day().today_end();
+        let due_dates = [
+            // Later this study day
+            (end - chrono::Duration::seconds(1)).to_rfc3339(),
+            // Tomorrow
+            (end + chrono::Duration::hours(1)).to_rfc3339(),
+            // Overdue, stored without fractional seconds and with a Z offset
+            (Utc::now() - chrono::Duration::minutes(1))
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        ];
+        for (card, due) in cards.iter().zip(&due_dates) {
+            storage
+                .conn
+                .execute(
+                    "UPDATE cards SET due_date = ?1 WHERE id = ?2",
+                    params![due, card.id],
+                )
+                .unwrap();
+        }
+
+        let ids = |due| {
+            storage
+                .get_due_cards("test", due)
+                .unwrap()
+                .iter()
+                .map(|c| c.keybind.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(DueBy::Now), vec!["Ctrl+Q"]);
+        assert_eq!(ids(DueBy::Today), vec!["Ctrl+Q", "Ctrl+S"]);
+        assert_eq!(
+            storage.get_deck_stats(&Default::default()).unwrap()[0].due_cards,
+            2
+        );
+        assert_eq!(storage.count_due_now().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_card_due_tomorrow_not_counted_today() {
+        let (_dir, mut storage) = open_temp();
+        sync(&mut storage, vec![card("Ctrl+S", "Save file")]);
+        let storage = storage.with_study_day(StudyDay::new(4));
+        let id = all_cards(&storage)[0].id;
+
+        // A one-day interval is due right at the start of the next study day
+        let due = storage.study_day().due_date(Utc::now(), 1.0);
+        assert_eq!(due, storage.study_day().today_end());
+        storage.update_card_after_review(id, 1.0, 5.0, due).unwrap();
+
+        let stats = storage.get_deck_stats(&Default::default()).unwrap();
+        assert_eq!(stats[0].due_cards, 0);
+        assert!(
+            storage
+                .get_due_cards("test", DueBy::Today)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            storage.get_deck_detail("test").unwrap().unwrap().due_cards,
+            0
+        );
+    }
+
+    #[test]
+    fn test_mistake_counts() {
+        let (_dir, mut storage) = open_temp();
+        sync(
+            &mut storage,
+            vec![card("Super+1", "Workspace 1"), card("g g", "Go to top")],
+        );
+        let cards = all_cards(&storage);
+        storage
+            .record_mistake(cards[0].id, "wrong_modifier")
+            .unwrap();
+        storage.record_mistake(cards[1].id, "extra_key").unwrap();
+        storage
+            .record_mistake(cards[0].id, "wrong_modifier")
+            .unwrap();
+
+        let count = |card_id: i64, kind: &str, count: i64| MistakeCount {
+            card_id,
+            kind: kind.to_string(),
+            count,
+        };
+        assert_eq!(
+            storage.get_mistake_counts().unwrap(),
+            vec![
+                count(cards[0].id, "wrong_modifier", 2),
+                count(cards[1].id, "extra_key", 1)
+            ]
+        );
+
+        // Removed cards take their counts with them
+        sync(&mut storage, vec![card("g g", "Go to top")]);
+        assert_eq!(
+            storage.get_mistake_counts().unwrap(),
+            vec![count(cards[1].id, "extra_key", 1)]
+        );
+    }
+
+    #[test]
+    fn test_get_cram_cards_least_stable_first() {
+        let (_dir, mut storage) = open_temp();
+        sync(
+            &mut storage,
+            vec![card("Ctrl+S", "Save file"), card("Ctrl+Q", "Quit")],
+        );
+        let cards = review_all(&storage);
+        storage
+            .update_card_after_review(cards[0].id, 20.0, 4.0, Utc::now())
+            .unwrap();
+        sync(
+            &mut storage,
+            vec![
+                card("Ctrl+S", "Save file"),
+                card("Ctrl+Q", "Quit"),
+                card("Ctrl+N", "New file"),
+            ],
+        );
+
+        let cram: Vec<String> = storage
+            .get_cram_cards("test")
+            .unwrap()
+            .into_iter()
+            .map(|c| c.keybind)
+            .collect();
+        assert_eq!(cram, vec!["Ctrl+N", "Ctrl+Q", "Ctrl+S"]);
+    }
+
+    #[test]
+    fn test_get_cram_cards_ties_in_card_order() {
+        let (_dir, mut storage) = open_temp();
+        sync(
+            &mut storage,
+            vec![
+                card("Ctrl+S", "Save file"),
+                card("Ctrl+Q", "Quit"),
+                card("Ctrl+N", "New file"),
+                card("Ctrl+O", "Open file"),
+            ],
+        );
+        let cards = all_cards(&storage);
+        for card in &cards[2..] {
+            storage
+                .update_card_after_review(card.id, 5.0, 4.0, Utc::now())
+                .unwrap();
+        }
+
+        let cram: Vec<String> = storage
+            .get_cram_cards("test")
+            .unwrap()
+            .into_iter()
+            .map(|c| c.keybind)
+            .collect();
+        assert_eq!(cram, vec!["Ctrl+S", "Ctrl+Q", "Ctrl+N", "Ctrl+O"]);
+    }
+
+    #[test]
+    fn test_sync_sections_and_tags() {
+        let (_dir, mut storage) = open_temp();
+        let tagged =
+            |keybind: &str, description: &str, section: Option<&str>, tags: &[&str]| SyncCard {
+                section: section.map(str::to_string),
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                ..card(keybind, description)
+            };
+        sync(
+            &mut storage,
+            vec![
+                tagged("w", "Next word", Some("Navigation"), &["motion"]),
+                tagged("g g", "Go to top", Some("Navigation"), &["motion", "jump"]),
+                tagged("d d", "Delete line", Some("Editing"), &[]),
+                tagged("u", "Undo", None, &["history"]),
+            ],
+        );
+
+        let cards = all_cards(&storage);
+        assert_eq!(cards[1].section.as_deref(), Some("Navigation"));
+        assert_eq!(cards[1].tags, vec!["motion", "jump"]);
+        assert!(CardGroup::Tag("jump".to_string()).contains(&cards[1]));
+        assert!(!CardGroup::Section("Editing".to_string()).contains(&cards[1]));
+
+        let groups: Vec<(CardGroup, i32)> = storage
+            .get_group_stats("test")
+            .unwrap()
+            .into_iter()
+            .map(|g| (g.group, g.total_cards))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                (CardGroup::Section("Navigation".to_string()), 2),
+                (CardGroup::Section("Editing".to_string()), 1),
+                (CardGroup::Tag("history".to_string()), 1),
+                (CardGroup::Tag("jump".to_string()), 1),
+                (CardGroup::Tag("motion".to_string()), 2),
+            ]
+        );
+
+        // Moving a card to another section keeps its progress
+        review_all(&storage);
+        sync(
+            &mut storage,
+            vec![
+                tagged("w", "Next word", Some("Words"), &[]),
+                tagged("g g", "Go to top", Some("Navigation"), &["motion", "jump"]),
+                tagged("d d", "Delete line", Some("Editing"), &[]),
+                tagged("u", "Undo", None, &["history"]),
+            ],
+        );
+        let cards = all_cards(&storage);
+        assert_eq!(cards[0].section.as_deref(), Some("Words"));
+        assert!(cards[0].tags.is_empty());
+        assert!(cards[0].stability.is_some());
+    }
+
+    #[test]
+    fn test_sync_reverse_cards() {
+        let (_dir, mut storage) = open_temp();
+        let reversed = |keybind: &str, description: &str| SyncCard {
+            reverse: true,
+            ..card(keybind, description)
+        };
+        sync(
+            &mut storage,
+            vec![card("Ctrl+Z", "Undo"), reversed("Ctrl+S", "Save file")],
+        );
+
+        let cards = all_cards(&storage);
+        assert_eq!(cards.len(), 3);
+        assert!(cards[2].reverse);
+        assert_eq!(cards[2].keybind, "Ctrl+S");
+        assert_eq!(cards[2].description, "Save file");
+        assert_eq!(
+            storage.get_descriptions("test").unwrap(),
+            vec!["Undo", "Save file"]
+        );
+
+        // Scheduled independently, and follows its forward card through a rename
+        review_all(&storage);
+        sync(
+            &mut storage,
+            vec![
+                reversed("Ctrl+Shift+S", "Save file"),
+                card("Ctrl+Z", "Undo"),
+            ],
+        );
+        let cards = all_cards(&storage);
+        assert_eq!(cards[2].keybind, "Ctrl+Shift+S");
+        assert!(cards[2].stability.is_some());
+
+        // A new meaning resets both directions
+        sync(
+            &mut storage,
+            vec![
+                reversed("Ctrl+Shift+S", "Open settings"),
+                card("Ctrl+Z", "Undo"),
+            ],
+        );
+        let cards = all_cards(&storage);
+        assert!(cards[0].stability.is_some());
+        assert!(cards[1].stability.is_none());
+        assert!(cards[2].stability.is_none());
+
+        // Dropping the flag removes the reverse card
+        sync(
+            &mut storage,
+            vec![
+                card("Ctrl+Shift+S", "Open settings"),
+                card("Ctrl+Z", "Undo"),
+            ],
+        );
+        assert_eq!(all_cards(&storage).len(), 2);
+    }
+
+    #[test]
+    fn test_today_counts_read_only_before_practice_column() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("kbsr.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE cards (
+                    id INTEGER PRIMARY KEY,
+                    deck TEXT NOT NULL,
+                    keybind TEXT NOT NULL,
+                    description TEXT NOT NULL,
+                    stability REAL,
+                    difficulty REAL,
+                    due_date TEXT,
+                    last_review TEXT,
+                    review_count INTEGER DEFAULT 0,
+                    UNIQUE(deck, keybind)
+                );
+                CREATE TABLE reviews (
+                    id INTEGER PRIMARY KEY,
+                    card_id INTEGER NOT NULL,
+                    rating INTEGER NOT NULL,
+                    response_time_ms INTEGER,
+                    attempts INTEGER,
+                    reviewed_at TEXT NOT NULL
+                );
+                INSERT INTO cards (deck, keybind, description) VALUES ('test', 'Ctrl+S', 'Save file');",
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO reviews (card_id, rating, reviewed_at) VALUES (1, 3, ?1)",
+                params![Utc::now().to_rfc3339()],
+            )
+            .unwrap();
+        }
+
+        let storage = Storage::open_read_only(&path).unwrap();
+        assert_eq!(
+            storage.get_today_counts().unwrap().get("test"),
+            Some(&DayCounts {
+                new_cards: 1,
+                reviews: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_migrates_old_cards_table_for_reverse() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("kbsr.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE cards (
+                    id INTEGER PRIMARY KEY,
+                    deck TEXT NOT NULL,
+                    keybind TEXT NOT NULL,
+                    description TEXT NOT NULL,
+                    stability REAL,
+                    difficulty REAL,
+                    due_date TEXT,
+                    last_review TEXT,
+                    review_count INTEGER DEFAULT 0,
+                    UNIQUE(deck, keybind)
+                );
+                INSERT INTO cards (deck, keybind, description, stability, difficulty)
+                    VALUES ('test', 'Ctrl+S', 'Save file', 5.0, 4.0);",
+            )
+            .unwrap();
+        }
+
+        let mut storage = Storage::open(&path).unwrap();
+        sync(
+            &mut storage,
+            vec![SyncCard {
+                reverse: true,
+                ..card("Ctrl+S", "Save file")
+            }],
+        );
+        let cards = all_cards(&storage);
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].stability, Some(5.0));
+        assert!(cards[1].reverse);
+    }
+
+    #[test]
+    fn test_sync_deletes_removed_cards() {
+        let (_dir, mut storage) = open_temp();
+        sync(
+            &mut storage,
+            vec![card("Ctrl+S", "Save file"), card("Ctrl+Q", "Quit")],
+        );
+        review_all(&storage);
+
+        sync(&mut storage, vec![card("Ctrl+S", "Save file")]);
+        let after = all_cards(&storage);
+        assert_eq!(after.len(), 1);
+        assert_eq!(storage.get_all_reviews().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,156 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Which cards a study session picks up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DueBy {
+    /// Cards due by now
+    #[default]
+    Now,
+    /// Cards due by the end of the study day, as counted in the deck list
+    Today,
+}
+
+/// A day of study, which starts at a set local hour rather than midnight,
+/// so a review at 1am still belongs to the evening before
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StudyDay {
+    starts_at: u32,
+}
+
+impl StudyDay {
+    /// Study days starting at this local hour (0-23)
+    pub fn new(starts_at: u32) -> Self {
+        Self {
+            starts_at: starts_at.min(23),
+        }
+    }
+
+    /// The study day a moment falls in, named by the date it starts on
+    pub fn date_of(&self, at: DateTime<Utc>) -> NaiveDate {
+        self.date_in(at, &Local)
+    }
+
+    /// The current study day
+    pub fn today(&self) -> NaiveDate {
+        self.date_of(Utc::now())
+    }
+
+    /// Study days from `from` to `to`, as FSRS counts the time between reviews.
+    /// Late one evening and early the next morning can be a day apart.
+    pub fn days_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> u32 {
+        (self.date_of(to) - self.date_of(from)).num_days().max(0) as u32
+    }
+
+    /// When a study day starts
+    pub fn start_of(&self, date: NaiveDate) -> DateTime<Utc> {
+        self.start_in(date, &Local)
+    }
+
+    /// When the current study day started
+    pub fn today_start(&self) -> DateTime<Utc> {
+        self.start_of(self.today())
+    }
+
+    /// When the current study day ends (the next one starts)
+    pub fn today_end(&self) -> DateTime<Utc> {
+        self.start_of(self.today() + Duration::days(1))
+    }
+
+    /// Cards due before this moment are studied in a session
+    pub fn cutoff(&self, due: DueBy) -> DateTime<Utc> {
+        match due {
+            DueBy::Now => Utc::now(),
+            DueBy::Today => self.today_end(),
+        }
+    }
+
+    /// When a card reviewed at `reviewed` comes due after `interval_days`.
+    /// Intervals under a day are kept as they are. Longer ones are rounded to whole
+    /// study days and the card is due from the start of that day, not the time of day
+    /// it was last reviewed.
+    pub fn due_date(&self, reviewed: DateTime<Utc>, interval_days: f32) -> DateTime<Utc> {
+        if interval_days < 1.0 {
+            return reviewed + Duration::seconds((interval_days * 86400.0) as i64);
+        }
+        let days = interval_days.round() as i64;
+        self.start_of(self.date_of(reviewed) + Duration::days(days))
+    }
+
+    fn date_in<Tz: TimeZone>(&self, at: DateTime<Utc>, tz: &Tz) -> NaiveDate {
+        (at.with_timezone(tz).naive_local() - Duration::hours(self.starts_at as i64)).date()
+    }
+
+    fn start_in<Tz: TimeZone>(&self, date: NaiveDate, tz: &Tz) -> DateTime<Utc> {
+        let start = date.and_hms_opt(self.starts_at, 0, 0).unwrap();
+        // A daylight saving change can skip the start hour: start an hour later instead
+        tz.from_local_datetime(&start)
+            .earliest()
+            .or_else(|| {
+                tz.from_local_datetime(&(start + Duration::hours(1)))
+                    .earliest()
+            })
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_else(|| start.and_utc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    /// A moment in UTC-5 (a late-night studier in New York)
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        FixedOffset::west_opt(5 * 3600)
+            .unwrap()
+            .from_local_datetime(&date(day).and_hms_opt(hour, minute, 0).unwrap())
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_study_day_boundaries() {
+        let tz = FixedOffset::west_opt(5 * 3600).unwrap();
+        let day = StudyDay::new(4);
+
+        // 1am belongs to the day before; 4am starts a new one
+        assert_eq!(day.date_in(at(11, 1, 0), &tz), date(10));
+        assert_eq!(day.date_in(at(11, 3, 59), &tz), date(10));
+        assert_eq!(day.date_in(at(11, 4, 0), &tz), date(11));
+        assert_eq!(day.start_in(date(11), &tz), at(11, 4, 0));
+
+        // Midnight is the calendar day
+        let midnight = StudyDay::default();
+        assert_eq!(midnight.date_in(at(11, 0, 30), &tz), date(11));
+        assert_eq!(midnight.start_in(date(11), &tz), at(11, 0, 0));
+
+        assert_eq!(StudyDay::new(30), StudyDay::new(23));
+    }
+
+    #[test]
+    fn test_due_date() {
+        let day = StudyDay::new(4);
+        let reviewed = Utc::now();
+
+        // Under a day: exact
+        assert_eq!(
+            day.due_date(reviewed, 0.5),
+            reviewed + Duration::seconds(43200)
+        );
+
+        // A day or more: from the start of that study day
+        let due = day.due_date(reviewed, 1.2);
+        assert_eq!(due, day.start_of(day.date_of(reviewed) + Duration::days(1)));
+        assert_eq!(day.date_of(due), day.date_of(reviewed) + Duration::days(1));
+        assert_eq!(
+            day.date_of(day.due_date(reviewed, 2.6)),
+            day.date_of(reviewed) + Duration::days(3)
+        );
+    }
+}
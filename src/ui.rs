@@ -1,17 +1,25 @@
+use crate::dashboard::{
+    CardRank, ChordRank, Dashboard, HEATMAP_WEEKS, PAUSE_POSITIONS, TREND_DAYS,
+};
+use crate::keybind::{CommandPart, Keybind, command_pattern};
 use crate::matcher::MatchState;
-use crate::storage::DeckStats;
+use crate::notation::Notation;
 use crossterm::event::KeyCode;
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::Paragraph,
+    widgets::{Paragraph, Sparkline},
 };
 
 const DECK_SELECTION_HINTS: &[(&[&str], &str)] = &[
     (&["↑", "↓"], "move"),
+    (&["→", "←"], "expand"),
+    (&["Space"], "select"),
     (&["Enter"], "study"),
+    (&["c"], "cram"),
+    (&["s"], "stats"),
     (&["q", "Esc"], "quit"),
     (&["?"], "toggle hints"),
 ];
@@ -20,18 +28,45 @@ const STUDY_HINTS: &[(&[&str], &str)] = &[(&["Esc"], "reveal")];
 
 const SUMMARY_HINTS: &[(&[&str], &str)] = &[(&["any key"], "continue")];
 
+const STATS_HINTS: &[(&[&str], &str)] = &[(&["any key"], "back")];
+
+/// Heatmap shades from fewest to most reviews in a day
+const HEATMAP_SHADES: &[&str] = &["░░", "▒▒", "▓▓", "██"];
+
+/// How a multiple-choice option is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChoiceState {
+    Open,
+    /// Already picked, and wrong
+    Wrong,
+    /// The right answer, once revealed or picked
+    Answer,
+}
+
+/// A multiple-choice option on a reverse card
+pub struct Choice<'a> {
+    pub text: &'a str,
+    pub state: ChoiceState,
+}
+
 /// UI state for rendering
 pub struct UiState<'a> {
     /// The deck name
     pub deck: &'a str,
+    /// The card's keyboard mode, shown next to the deck name in mixed sessions
+    pub keyboard_mode: Option<&'a str>,
+    /// Whether this is a cram (practice-only) session
+    pub cram: bool,
     /// The clue/description to display
     pub clue: &'a str,
     /// Current match state (typed chords and success/fail)
     pub match_state: &'a MatchState,
+    /// Multiple-choice options for a reverse card (empty for normal cards)
+    pub choices: &'a [Choice<'a>],
     /// Whether we're showing the answer
     pub showing_answer: bool,
-    /// The correct answer (for showing after reveal)
-    pub answer: &'a str,
+    /// The accepted answers (for showing after reveal)
+    pub answers: &'a [String],
     /// Message to display (e.g., "Type the answer to continue")
     pub message: Option<&'a str>,
     /// Whether to show the success checkmark
@@ -48,26 +83,38 @@ pub struct UiState<'a> {
     pub cards_remaining: usize,
     /// Whether the current deck is in commands mode
     pub is_command_mode: bool,
+    /// Whether command answers have placeholders to dim
+    pub placeholders: bool,
+    /// Notation for typed chords (answers are already formatted)
+    pub notation: Notation,
 }
 
 /// Render the minimal UI
 pub fn render(frame: &mut Frame, state: &UiState) {
     let area = frame.area();
+    let typed_height = (state.choices.len() as u16 + 1).max(2);
 
     let chunks = Layout::vertical([
-        Constraint::Fill(1),   // Top spacer
-        Constraint::Length(1), // Deck name
-        Constraint::Length(3), // Clue area
-        Constraint::Length(2), // Typed keys area
-        Constraint::Length(1), // Spacer before answer/message
-        Constraint::Length(1), // Answer or checkmark area
-        Constraint::Length(1), // Message area
-        Constraint::Fill(1),   // Bottom spacer
+        Constraint::Fill(1),              // Top spacer
+        Constraint::Length(1),            // Deck name
+        Constraint::Length(3),            // Clue area
+        Constraint::Length(typed_height), // Typed keys or choices area
+        Constraint::Length(1),            // Spacer before answer/message
+        Constraint::Length(1),            // Answer or checkmark area
+        Constraint::Length(1),            // Message area
+        Constraint::Fill(1),              // Bottom spacer
     ])
     .split(area);
 
-    // Render deck name (dimmed, centered)
-    let deck = Paragraph::new(state.deck)
+    // Render deck name (dimmed, centered), with the mode when decks are mixed
+    let mut deck_label = state.deck.to_string();
+    if let Some(mode) = state.keyboard_mode {
+        deck_label.push_str(&format!(" · {}", mode));
+    }
+    if state.cram {
+        deck_label.push_str(" · cram");
+    }
+    let deck = Paragraph::new(deck_label)
         .style(Style::default().fg(Color::DarkGray))
         .alignment(Alignment::Center);
     frame.render_widget(deck, chunks[1]);
@@ -78,19 +125,70 @@ pub fn render(frame: &mut Frame, state: &UiState) {
         .alignment(Alignment::Center);
     frame.render_widget(clue, chunks[2]);
 
-    // Render typed keys with appropriate color
-    let typed_line = render_typed_chords(state.match_state, state.is_command_mode);
-    let typed = Paragraph::new(typed_line).alignment(Alignment::Center);
-    frame.render_widget(typed, chunks[3]);
+    // Render the choices for a reverse card, otherwise typed keys with appropriate color
+    if state.choices.is_empty() {
+        let typed_line =
+            render_typed_chords(state.match_state, state.is_command_mode, state.notation);
+        let typed = Paragraph::new(typed_line).alignment(Alignment::Center);
+        frame.render_widget(typed, chunks[3]);
+    } else {
+        let lines: Vec<Line> = state
+            .choices
+            .iter()
+            .enumerate()
+            .map(|(i, choice)| {
+                let style = match choice.state {
+                    ChoiceState::Open => Style::default().fg(Color::White),
+                    ChoiceState::Wrong => Style::default()
+                        .fg(Color::Red)
+                        .add_modifier(Modifier::CROSSED_OUT),
+                    ChoiceState::Answer => Style::default().fg(Color::Green),
+                };
+                Line::from(vec![
+                    Span::styled(format!("{} ", i + 1), Style::default().fg(Color::DarkGray)),
+                    Span::styled(choice.text, style),
+                ])
+            })
+            .collect();
+        let choices = Paragraph::new(lines).alignment(Alignment::Center);
+        frame.render_widget(choices, chunks[3]);
+    }
 
     // chunks[4] is spacer
 
     // Render answer if showing (below typed keys)
     if state.showing_answer {
-        let answer_line = Line::from(vec![
-            Span::styled("Answer: ", Style::default().fg(Color::DarkGray)),
-            Span::styled(state.answer, Style::default().fg(Color::White)),
-        ]);
+        let mut spans = vec![Span::styled(
+            "Answer: ",
+            Style::default().fg(Color::DarkGray),
+        )];
+        for (i, answer) in state.answers.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::styled(" or ", Style::default().fg(Color::DarkGray)));
+            }
+            if !state.is_command_mode {
+                spans.push(Span::styled(
+                    answer.as_str(),
+                    Style::default().fg(Color::White),
+                ));
+                continue;
+            }
+            // Placeholders are dimmed slots for any argument
+            for part in command_pattern(answer, state.placeholders) {
+                spans.push(match part {
+                    CommandPart::Literal(text) => {
+                        Span::styled(text, Style::default().fg(Color::White))
+                    }
+                    CommandPart::Placeholder(name) => Span::styled(
+                        name,
+                        Style::default()
+                            .fg(Color::DarkGray)
+                            .add_modifier(Modifier::ITALIC),
+                    ),
+                });
+            }
+        }
+        let answer_line = Line::from(spans);
         let answer = Paragraph::new(answer_line).alignment(Alignment::Center);
         frame.render_widget(answer, chunks[5]);
     }
@@ -126,7 +224,11 @@ pub fn render(frame: &mut Frame, state: &UiState) {
 }
 
 /// Render the typed chords with appropriate coloring
-fn render_typed_chords(state: &MatchState, is_command_mode: bool) -> Line<'static> {
+fn render_typed_chords(
+    state: &MatchState,
+    is_command_mode: bool,
+    notation: Notation,
+) -> Line<'static> {
     let chords = state.typed_chords();
 
     if chords.is_empty() {
@@ -143,17 +245,13 @@ fn render_typed_chords(state: &MatchState, is_command_mode: bool) -> Line<'stati
             })
             .collect()
     } else {
-        chords
-            .iter()
-            .map(|c| c.to_string())
-            .collect::<Vec<_>>()
-            .join(" ")
+        notation.format_keybind(&Keybind(chords.to_vec()))
     };
 
     let color = match state {
         MatchState::InProgress(_) => Color::Green,
         MatchState::Complete(_) => Color::Green,
-        MatchState::Failed(_) => Color::Red,
+        MatchState::Mistyped(_) | MatchState::Failed(_) => Color::Red,
     };
 
     // Always show red/green feedback so user knows if they're typing correctly,
@@ -163,10 +261,23 @@ fn render_typed_chords(state: &MatchState, is_command_mode: bool) -> Line<'stati
     Line::from(Span::styled(text, style))
 }
 
+/// A row in the deck picker
+pub struct PickerRow {
+    pub label: String,
+    pub due_cards: i32,
+    pub total_cards: i32,
+    /// Indent level: 0 for decks, 1 for a deck's sections and tags
+    pub depth: usize,
+    /// Whether the row is marked, or None when nothing is marked (no checkboxes shown)
+    pub marked: Option<bool>,
+    /// Whether the row is expanded, or None if it has nothing to expand
+    pub expanded: Option<bool>,
+}
+
 /// Render deck selection screen
 pub fn render_deck_selection(
     frame: &mut Frame,
-    decks: &[DeckStats],
+    rows: &[PickerRow],
     selected: usize,
     show_hints: bool,
 ) {
@@ -175,7 +286,7 @@ pub fn render_deck_selection(
     let chunks = Layout::vertical([
         Constraint::Fill(1),
         Constraint::Length(2),
-        Constraint::Length((decks.len() + 1) as u16),
+        Constraint::Length((rows.len() + 1) as u16),
         Constraint::Fill(1),
     ])
     .split(area);
@@ -187,25 +298,39 @@ pub fn render_deck_selection(
     frame.render_widget(title, chunks[1]);
 
     // Deck list
-    let mut lines: Vec<Line> = Vec::new();
-
-    for (i, deck) in decks.iter().enumerate() {
-        let prefix = if i == selected { "> " } else { "  " };
-        let style = if i == selected {
-            Style::default().fg(Color::Cyan)
-        } else {
-            Style::default().fg(Color::White)
-        };
-
-        let line = Line::from(Span::styled(
-            format!(
-                "{}{} ({} due / {} total)",
-                prefix, deck.name, deck.due_cards, deck.total_cards
-            ),
-            style,
-        ));
-        lines.push(line);
-    }
+    let lines: Vec<Line> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let style = if i == selected {
+                Style::default().fg(Color::Cyan)
+            } else if row.depth > 0 {
+                Style::default().fg(Color::Gray)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let prefix = if i == selected { "> " } else { "  " };
+            let indent = "  ".repeat(row.depth);
+            let mark = match row.marked {
+                Some(true) => "[x] ",
+                Some(false) => "[ ] ",
+                None => "",
+            };
+            let arrow = match row.expanded {
+                Some(true) => "▾ ",
+                Some(false) => "▸ ",
+                None => "",
+            };
+
+            Line::from(Span::styled(
+                format!(
+                    "{}{}{}{}{} ({} due / {} total)",
+                    prefix, indent, mark, arrow, row.label, row.due_cards, row.total_cards
+                ),
+                style,
+            ))
+        })
+        .collect();
 
     let list = Paragraph::new(lines).alignment(Alignment::Center);
     frame.render_widget(list, chunks[2]);
@@ -242,16 +367,18 @@ pub fn render_paused(frame: &mut Frame, resume_keybind: &str) {
 /// Render session summary
 pub fn render_summary(
     frame: &mut Frame,
+    cram: bool,
     reviewed: usize,
     correct: usize,
     total_time_secs: u64,
+    notes: &[String],
     show_hints: bool,
 ) {
     let area = frame.area();
 
     let chunks = Layout::vertical([
         Constraint::Fill(1),
-        Constraint::Length(5),
+        Constraint::Length(6 + notes.len() as u16),
         Constraint::Fill(1),
     ])
     .split(area);
@@ -262,16 +389,30 @@ pub fn render_summary(
         0.0
     };
 
-    let lines = vec![
-        Line::from(Span::styled(
-            "Session Complete",
-            Style::default().fg(Color::Green),
-        )),
+    let title = if cram {
+        "Cram Complete"
+    } else {
+        "Session Complete"
+    };
+    let mut lines = vec![
+        Line::from(Span::styled(title, Style::default().fg(Color::Green))),
         Line::from(""),
         Line::from(format!("Cards reviewed: {}", reviewed)),
         Line::from(format!("Correct: {} ({:.0}%)", correct, accuracy)),
         Line::from(format!("Time: {}s", total_time_secs)),
     ];
+    if cram {
+        lines.push(Line::from(Span::styled(
+            "Practice only: scheduling unchanged",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    for note in notes {
+        lines.push(Line::from(Span::styled(
+            note.as_str(),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
 
     let summary = Paragraph::new(lines).alignment(Alignment::Center);
     frame.render_widget(summary, chunks[1]);
@@ -281,6 +422,343 @@ pub fn render_summary(
     }
 }
 
+/// Render the statistics dashboard
+pub fn render_stats(frame: &mut Frame, dashboard: &Dashboard, show_hints: bool) {
+    let area = frame.area();
+    let title_style = Style::default().fg(Color::DarkGray);
+    let section_title = |title: String| Line::from(Span::styled(title, title_style));
+
+    let rows = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(2), // Title
+        Constraint::Fill(1),   // Panels
+        Constraint::Length(2), // Hints
+    ])
+    .split(area);
+
+    let title = Paragraph::new("Statistics")
+        .style(Style::default().fg(Color::White))
+        .alignment(Alignment::Center);
+    frame.render_widget(title, rows[1]);
+
+    let columns = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .horizontal_margin(2)
+        .spacing(4)
+        .split(rows[2]);
+
+    // Left: heatmap, retention, response time, chord timing and pauses
+    let left = Layout::vertical([
+        Constraint::Length(9),
+        Constraint::Length(dashboard.deck_retention.len().max(1) as u16 + 2),
+        Constraint::Length(5),
+        Constraint::Length(PAUSE_POSITIONS as u16 + 3),
+        Constraint::Length(dashboard.hesitations.len().max(1) as u16 + 2),
+        Constraint::Fill(1),
+    ])
+    .split(columns[0]);
+
+    let mut heatmap = vec![section_title(format!(
+        "Reviews since {} (last {} weeks)",
+        dashboard.heatmap_start.format("%-d %b"),
+        HEATMAP_WEEKS
+    ))];
+    let busiest = dashboard.daily_reviews.iter().copied().max().unwrap_or(0);
+    for (weekday, label) in ["Mon", "", "Wed", "", "Fri", "", "Sun"].iter().enumerate() {
+        let mut spans = vec![Span::styled(format!("{:<4}", label), title_style)];
+        for week in 0..HEATMAP_WEEKS {
+            let count = dashboard.daily_reviews.get(week * 7 + weekday).copied();
+            spans.push(match count {
+                None => Span::raw("  "),
+                Some(0) => Span::styled("··", Style::default().fg(Color::DarkGray)),
+                Some(n) => {
+                    let shade = ((n - 1) as usize * HEATMAP_SHADES.len()) / busiest as usize;
+                    Span::styled(HEATMAP_SHADES[shade], Style::default().fg(Color::Green))
+                }
+            });
+        }
+        heatmap.push(Line::from(spans));
+    }
+    frame.render_widget(Paragraph::new(heatmap), left[0]);
+
+    let mut retention = vec![section_title("True retention".to_string())];
+    let name_width = dashboard
+        .deck_retention
+        .iter()
+        .map(|d| d.name.len())
+        .max()
+        .unwrap_or(0);
+    for deck in &dashboard.deck_retention {
+        let value = match deck.retention {
+            Some(r) => format!("{:>4.0}%  ({} reviews)", r * 100.0, deck.reviews),
+            None => "   -".to_string(),
+        };
+        retention.push(Line::from(format!(
+            "{:<width$}  {}",
+            deck.name,
+            value,
+            width = name_width
+        )));
+    }
+    if dashboard.deck_retention.is_empty() {
+        retention.push(Line::from(Span::styled("No decks", title_style)));
+    }
+    frame.render_widget(Paragraph::new(retention), left[1]);
+
+    let trend: Vec<Option<u64>> = dashboard
+        .response_trend
+        .iter()
+        .map(|ms| ms.map(|ms| ms as u64))
+        .collect();
+    render_sparkline_panel(
+        frame,
+        left[2],
+        section_title(format!("Response time (last {} days)", TREND_DAYS)),
+        &trend,
+        response_caption(&dashboard.response_trend),
+    );
+
+    let mut timing = vec![section_title(
+        "Chord timing (first-try answers)".to_string(),
+    )];
+    match (dashboard.first_key_ms, dashboard.execution_ms) {
+        (Some(first_key), Some(execution)) => {
+            timing.push(Line::from(format!(
+                "First key after {:.1}s, then {:.1}s to finish",
+                first_key / 1000.0,
+                execution / 1000.0
+            )));
+            let longest = dashboard
+                .position_pauses
+                .iter()
+                .flatten()
+                .copied()
+                .fold(1.0, f64::max);
+            for (i, pause) in dashboard.position_pauses.iter().enumerate() {
+                let label = if i + 1 == PAUSE_POSITIONS {
+                    format!("#{}+", i + 1)
+                } else {
+                    format!("#{}", i + 1)
+                };
+                let mut spans = vec![Span::styled(format!("{:<6}", label), title_style)];
+                if let Some(ms) = pause {
+                    spans.push(Span::styled(
+                        "█".repeat((ms * 20.0 / longest).ceil() as usize),
+                        Style::default().fg(Color::Cyan),
+                    ));
+                    spans.push(Span::raw(format!(" {:.1}s", ms / 1000.0)));
+                }
+                timing.push(Line::from(spans));
+            }
+        }
+        _ => timing.push(Line::from(Span::styled("No timings yet", title_style))),
+    }
+    frame.render_widget(Paragraph::new(timing), left[3]);
+
+    let hesitations = chord_rank_lines(
+        section_title("Longest pauses".to_string()),
+        &dashboard.hesitations,
+        |ms| format!("{:.1}s", ms / 1000.0),
+    );
+    frame.render_widget(Paragraph::new(hesitations), left[4]);
+
+    // Right: stability, due forecast, slowest, most-failed and most mistyped
+    let right = Layout::vertical([
+        Constraint::Length(dashboard.stability_buckets.len() as u16 + 3),
+        Constraint::Length(5),
+        Constraint::Length(dashboard.slowest.len().max(1) as u16 + 2),
+        Constraint::Length(dashboard.most_failed.len().max(1) as u16 + 2),
+        Constraint::Length(dashboard.mistyped.len().max(1) as u16 + 3),
+        Constraint::Fill(1),
+    ])
+    .split(columns[1]);
+
+    let mut stability = vec![section_title("Stability".to_string())];
+    let largest = dashboard
+        .stability_buckets
+        .iter()
+        .map(|(_, n)| *n)
+        .chain(std::iter::once(dashboard.new_cards))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let bar = |label: &str, count: usize| {
+        Line::from(vec![
+            Span::styled(format!("{:<6}", label), title_style),
+            Span::styled(
+                "█".repeat((count * 20).div_ceil(largest)),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw(format!(" {}", count)),
+        ])
+    };
+    stability.push(bar("new", dashboard.new_cards));
+    for (label, count) in &dashboard.stability_buckets {
+        stability.push(bar(label, *count));
+    }
+    frame.render_widget(Paragraph::new(stability), right[0]);
+
+    let forecast: Vec<u64> = dashboard.due_forecast.iter().map(|n| *n as u64).collect();
+    render_sparkline_panel(
+        frame,
+        right[1],
+        section_title(format!("Due (next {} days)", forecast.len())),
+        &forecast.iter().map(|n| Some(*n)).collect::<Vec<_>>(),
+        format!(
+            "{} today, {} this week",
+            forecast.first().copied().unwrap_or(0),
+            forecast.iter().take(7).sum::<u64>()
+        ),
+    );
+
+    let slowest = rank_lines(
+        section_title("Slowest".to_string()),
+        &dashboard.slowest,
+        |ms| format!("{:.1}s", ms / 1000.0),
+    );
+    frame.render_widget(Paragraph::new(slowest), right[2]);
+
+    let most_failed = rank_lines(
+        section_title("Most failed".to_string()),
+        &dashboard.most_failed,
+        |n| format!("{:.0}×", n),
+    );
+    frame.render_widget(Paragraph::new(most_failed), right[3]);
+
+    let mut mistyped = chord_rank_lines(
+        section_title("Most mistyped".to_string()),
+        &dashboard.mistyped,
+        |n| format!("{:.0}×", n),
+    );
+    if !dashboard.mistake_kinds.is_empty() {
+        let kinds: Vec<String> = dashboard
+            .mistake_kinds
+            .iter()
+            .map(|(kind, n)| format!("{} {}", kind.label(), n))
+            .collect();
+        mistyped.push(Line::from(Span::styled(kinds.join(" · "), title_style)));
+    }
+    frame.render_widget(Paragraph::new(mistyped), right[4]);
+
+    if show_hints {
+        render_hints_bar(frame, area, STATS_HINTS);
+    }
+}
+
+/// A titled sparkline with a caption underneath
+fn render_sparkline_panel(
+    frame: &mut Frame,
+    area: Rect,
+    title: Line,
+    data: &[Option<u64>],
+    caption: String,
+) {
+    let parts = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(2),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ])
+    .split(area);
+
+    frame.render_widget(Paragraph::new(title), parts[0]);
+    let width = (data.len() as u16).min(parts[1].width);
+    let sparkline = Sparkline::default()
+        .data(data)
+        .style(Style::default().fg(Color::Cyan));
+    frame.render_widget(sparkline, Rect { width, ..parts[1] });
+    frame.render_widget(
+        Paragraph::new(caption).style(Style::default().fg(Color::DarkGray)),
+        parts[2],
+    );
+}
+
+/// Caption comparing the last week's average response time with the whole trend
+fn response_caption(trend: &[Option<f64>]) -> String {
+    let average = |days: &[Option<f64>]| {
+        let values: Vec<f64> = days.iter().flatten().copied().collect();
+        (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+    };
+    let recent = &trend[trend.len().saturating_sub(7)..];
+    match (average(trend), average(recent)) {
+        (Some(all), Some(week)) => format!(
+            "avg {:.1}s, last 7 days {:.1}s",
+            all / 1000.0,
+            week / 1000.0
+        ),
+        (Some(all), None) => format!("avg {:.1}s, none in the last 7 days", all / 1000.0),
+        _ => "No reviews yet".to_string(),
+    }
+}
+
+/// Lines for a ranked card list: keybind, description and a formatted value
+fn rank_lines<'a>(
+    title: Line<'a>,
+    cards: &'a [CardRank],
+    format_value: impl Fn(f64) -> String,
+) -> Vec<Line<'a>> {
+    let mut lines = vec![title];
+    for card in cards {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:>6}  ", format_value(card.value)),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::styled(card.keybind.as_str(), Style::default().fg(Color::Cyan)),
+            Span::raw(format!("  {}", card.description)),
+            Span::styled(
+                format!("  {}", card.deck),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]));
+    }
+    if cards.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Nothing yet",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines
+}
+
+/// Lines for a ranked chord list: the chord, the card it belongs to and a formatted value
+fn chord_rank_lines<'a>(
+    title: Line<'a>,
+    chords: &'a [ChordRank],
+    format_value: impl Fn(f64) -> String,
+) -> Vec<Line<'a>> {
+    let mut lines = vec![title];
+    for chord in chords {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:>6}  ", format_value(chord.card.value)),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::styled(chord.chord.as_str(), Style::default().fg(Color::Cyan)),
+            Span::styled(" in ", Style::default().fg(Color::DarkGray)),
+            Span::raw(chord.card.keybind.as_str()),
+            Span::raw(format!("  {}", chord.card.description)),
+            Span::styled(
+                format!("  {}", chord.card.deck),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(
+                chord
+                    .usual_mistake
+                    .map(|kind| format!("  mostly {}", kind.label()))
+                    .unwrap_or_default(),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]));
+    }
+    if chords.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Nothing yet",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines
+}
+
 fn render_study_hints(
     frame: &mut Frame,
     area: Rect,